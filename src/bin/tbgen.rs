@@ -0,0 +1,52 @@
+#![allow(clippy::needless_return)]
+use apotheosis::tablebase::*;
+use std::{
+    env,
+    fs,
+    process,
+    time::Instant
+};
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() < 3 {
+        eprintln!("usage: {} <output directory> <material>...", args[0]);
+        eprintln!("material is written like KRKP, white's pieces first, with at most {} pieces kings included;", MAX_TABLEBASE_PIECES);
+        eprintln!("every table a capture or promotion leads to gets generated too");
+        eprintln!("tables already in the output directory are loaded instead of generated again");
+        process::exit(2);
+    }
+    let materials = args[2..].iter().map(|name| {
+        return Material::parse(name).unwrap_or_else(|| {
+            eprintln!("{} is not a material like KRKP", name);
+            process::exit(2);
+        });
+    }).collect::<Vec<Material>>();
+    let output_directory = &args[1];
+    if let Err(error) = fs::create_dir_all(output_directory) {
+        eprintln!("cannot create {}: {}", output_directory, error);
+        process::exit(1);
+    }
+
+    let mut tablebases = Tablebases::new();
+    match tablebases.load(output_directory) {
+        Ok(table_count) if table_count > 0 => println!("loaded {} existing tables", table_count),
+        Ok(_) => {},
+        Err(error) => {
+            eprintln!("cannot load the tables in {}: {}", output_directory, error);
+            process::exit(1);
+        }
+    }
+    let start_time = Instant::now();
+    for material in materials.iter() {
+        if let Err(error) = tablebases.generate(material) {
+            eprintln!("cannot generate {}: {}", material.name(), error);
+            process::exit(1);
+        }
+    }
+    if let Err(error) = tablebases.save(output_directory) {
+        eprintln!("cannot write the tables to {}: {}", output_directory, error);
+        process::exit(1);
+    }
+    println!("generated {} in {:.1}s", tablebases.table_names().join(" "), start_time.elapsed().as_secs_f64());
+}
@@ -0,0 +1,63 @@
+#![allow(clippy::needless_return)]
+use apotheosis::{
+    eval_params::EVAL_PARAMS,
+    params::write_params_source,
+    tune::*
+};
+use std::{
+    env,
+    fs,
+    process
+};
+
+const DEFAULT_MAX_PASSES: usize = 100;
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() < 3 {
+        eprintln!("usage: {} <labelled positions> <output file> [max passes]", args[0]);
+        eprintln!("each line of the positions file is a quiet fen followed by the game result (1-0, 0-1, 1/2-1/2 or 1.0, 0.5, 0.0)");
+        eprintln!("the tuned weights get written to the output file in the format of src/eval_params.rs after every pass");
+        process::exit(2);
+    }
+    let max_passes = match args.get(3).map(|max_passes| max_passes.parse::<usize>()) {
+        Some(Ok(max_passes)) => max_passes,
+        Some(Err(_)) => {
+            eprintln!("max passes has to be a number");
+            process::exit(2);
+        },
+        None => DEFAULT_MAX_PASSES
+    };
+    let positions_file = fs::read_to_string(&args[1]).unwrap_or_else(|error| {
+        eprintln!("cannot read {}: {}", args[1], error);
+        process::exit(1);
+    });
+    let mut skipped_lines = 0;
+    let positions = positions_file.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let position = parse_labelled_position(line);
+            if position.is_none() {
+                skipped_lines += 1;
+            }
+            return position;
+        })
+        .collect::<Vec<LabelledPosition>>();
+    println!("loaded {} positions, skipped {} lines that were not labelled fens", positions.len(), skipped_lines);
+    if positions.is_empty() {
+        process::exit(1);
+    }
+
+    let mut params = EVAL_PARAMS;
+    let scaling = fit_scaling(&positions, &params);
+    println!("scaling {:.3}, starting error {:.6}", scaling, mean_squared_error(&positions, &params, scaling));
+    tune_params(&positions, &mut params, scaling, max_passes, |pass, error, params| {
+        println!("pass {}, error {:.6}", pass, error);
+        if let Err(error) = fs::write(&args[2], write_params_source(params)) {
+            eprintln!("cannot write {}: {}", args[2], error);
+            return false;
+        }
+        return true;
+    });
+    println!("tuned weights are in {}", args[2]);
+}
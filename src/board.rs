@@ -1,18 +1,13 @@
 use std::{
-    collections::{
-        HashSet,
-        HashMap
-    },
     cmp,
     default::Default,
-    fmt,
-    rc::Rc
+    fmt
 };
 use ethnum::*;
 use nohash_hasher::*;
 
 #[derive(PartialEq, Eq, Clone, PartialOrd, Ord, Debug, Hash)]
-pub struct Direction(i8, i8);
+pub struct Direction(pub i8, pub i8);
 impl Direction {
     pub const fn dx(&self) -> i8 {
         return self.0;
@@ -21,7 +16,11 @@ impl Direction {
         return self.1;
     }
     pub const fn as_square_pos_delta(&self) -> u8 {
-        return (self.0 + self.1 * 8) as u8;
+        // north is towards the 8th rank, which is the start of the board image
+        return (self.0 - self.1 * 8) as u8;
+    }
+    pub const fn reversed(&self) -> Direction {
+        return Direction(-self.0, -self.1);
     }
     pub const N: Direction = Direction(0, 1);
     pub const NE: Direction = Direction(1, 1);
@@ -35,9 +34,24 @@ impl Direction {
     pub const CARDINALS: [Direction; 4] = [Direction::N, Direction::E, Direction::S, Direction::W];
     pub const ORDINALS: [Direction; 4] = [Direction::NE, Direction::SE, Direction::SW, Direction::NW];
     pub const COMPASS_ROSE: [Direction; 8] = [Direction::N, Direction::NE, Direction::E, Direction::SE, Direction::S, Direction::SW, Direction::W, Direction::NW];
+    pub const KNIGHT_JUMPS: [Direction; 8] = [
+        Direction(1, 2),
+        Direction(-1, 2),
+        Direction(1, -2),
+        Direction(-1, -2),
+        Direction(2, 1),
+        Direction(-2, 1),
+        Direction(2, -1),
+        Direction(-2, -1)
+    ];
+
+    // the direction a pawn of the given color pushes in
+    pub const fn pawn_push(color: BoardColor) -> Direction {
+        return if color == WHITE { Direction::N } else { Direction::S };
+    }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Hash)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash)]
 pub struct BoardSquare(pub u8);
 impl BoardSquare {
     pub const fn pos(&self) -> u8 {
@@ -82,7 +96,7 @@ impl BoardSquare {
     }
     pub const fn get_square_in_direction(&self, dir: &Direction) -> Option<BoardSquare> {
         let new_x = self.x() as i8 + dir.dx();
-        let new_y = self.y() as i8 + dir.dy();
+        let new_y = self.y() as i8 - dir.dy();
         if new_x < 0 
         || new_x >= 8
         || new_y < 0 
@@ -91,6 +105,9 @@ impl BoardSquare {
         }
         return Some(BoardSquare((new_x + new_y * 8) as u8));
     }
+    pub const fn mask(&self) -> u64 {
+        return 1u64 << self.0;
+    }
     pub fn get_all_squares_in_direction(&self, dir: &Direction) -> Vec<BoardSquare> {
         let amount_of_squares = match *dir {
             Direction::N => self.y(), 
@@ -128,6 +145,8 @@ impl fmt::Display for BoardSquare {
     }
 }
 
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 pub type BoardColor = u8;
 pub const BLACK: BoardColor = 0b0000;
 pub const WHITE: BoardColor = 0b0001;
@@ -151,7 +170,7 @@ pub const fn get_piece_color(piece: BoardPiece) -> BoardPieceType {
     return piece & 0b0001;
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Clone, Copy)]
 pub struct BoardMove(u16);
 
 impl BoardMove {
@@ -215,6 +234,171 @@ impl BoardMove {
     pub const fn dest_square(&self) -> BoardSquare {
         return BoardSquare(((self.0 >> 3) & 0b111111) as u8);
     }
+    // the raw encoding described in SPECIFICATIONS.txt
+    pub const fn to_bits(&self) -> u16 {
+        return self.0;
+    }
+    pub const fn from_bits(bits: u16) -> BoardMove {
+        return BoardMove(bits);
+    }
+    pub const fn castle_color(&self) -> BoardColor {
+        return ((self.0 >> 14) & 1) as u8;
+    }
+    pub const fn is_kingside_castle(&self) -> bool {
+        return (self.0 >> 13) & 1 == 1;
+    }
+    // (king from, king dest, rook from, rook dest)
+    pub fn castle_squares(&self) -> (BoardSquare, BoardSquare, BoardSquare, BoardSquare) {
+        let back_rank = if self.castle_color() == WHITE { 56 } else { 0 };
+        if self.is_kingside_castle() {
+            return (BoardSquare(back_rank + 4), BoardSquare(back_rank + 6), BoardSquare(back_rank + 7), BoardSquare(back_rank + 5));
+        }
+        return (BoardSquare(back_rank + 4), BoardSquare(back_rank + 2), BoardSquare(back_rank), BoardSquare(back_rank + 3));
+    }
+    pub fn to_uci(&self, board: &Board) -> String {
+        if self.is_castle() {
+            let (king_from, king_dest, _, _) = self.castle_squares();
+            return format!("{}{}", king_from, king_dest);
+        }
+        let mut uci_move = format!("{}{}", self.from_square(), self.dest_square());
+        if board.is_promotion(self) {
+            uci_move.push(match self.promotion_target_piece() {
+                KNIGHT => 'n',
+                BISHOP => 'b',
+                ROOK => 'r',
+                _ => 'q'
+            });
+        }
+        return uci_move;
+    }
+    pub fn from_uci(board: &Board, uci_move: &str) -> Option<BoardMove> {
+        return board.get_valid_moves().into_iter().find(|valid_move| valid_move.to_uci(board) == uci_move);
+    }
+    // standard algebraic notation, like Nbd2, exd6, e8=Q+ or O-O-O#
+    pub fn to_san(&self, board: &Board) -> String {
+        let mut san_move = if self.is_castle() {
+            (if self.is_kingside_castle() { "O-O" } else { "O-O-O" }).to_string()
+        } else {
+            let from_square = self.from_square();
+            let dest_square = self.dest_square();
+            let piece_type = board.get_piece_at(&from_square).map(get_piece_type).unwrap_or(PAWN);
+            let mut san_move = String::new();
+            if piece_type == PAWN {
+                if board.is_capture(self) {
+                    san_move.push_str(&from_square.to_string()[..1]);
+                }
+            } else {
+                san_move.push(piece_letter(piece_type));
+                // only as much of the origin square as it takes to tell the pieces apart
+                let ambiguous_moves = board.get_valid_moves().into_iter().filter(|valid_move| {
+                    return !valid_move.is_castle()
+                        && valid_move.dest_square() == dest_square
+                        && valid_move.from_square() != from_square
+                        && board.get_piece_at(&valid_move.from_square()).map(get_piece_type) == Some(piece_type);
+                }).collect::<Vec<BoardMove>>();
+                if !ambiguous_moves.is_empty() {
+                    let is_file_unique = ambiguous_moves.iter().all(|ambiguous_move| ambiguous_move.from_square().x() != from_square.x());
+                    let is_rank_unique = ambiguous_moves.iter().all(|ambiguous_move| ambiguous_move.from_square().y() != from_square.y());
+                    let from_notation = from_square.to_string();
+                    san_move.push_str(if is_file_unique {
+                        &from_notation[..1]
+                    } else if is_rank_unique {
+                        &from_notation[1..]
+                    } else {
+                        &from_notation
+                    });
+                }
+            }
+            if board.is_capture(self) {
+                san_move.push('x');
+            }
+            san_move.push_str(&dest_square.to_string());
+            if board.is_promotion(self) {
+                san_move.push('=');
+                san_move.push(piece_letter(self.promotion_target_piece()));
+            }
+            san_move
+        };
+        let next_board = board.create_board_from_move(self);
+        if next_board.is_in_check() {
+            san_move.push(if next_board.get_valid_moves().is_empty() { '#' } else { '+' });
+        }
+        return san_move;
+    }
+    // lenient about check marks, annotations, capture marks and extra disambiguation
+    pub fn from_san(board: &Board, san_move: &str) -> Option<BoardMove> {
+        let san_move = san_move.trim_end_matches(['+', '#', '!', '?']).replace("e.p.", "");
+        let valid_moves = board.get_valid_moves();
+        if san_move == "O-O" || san_move == "0-0" || san_move == "O-O-O" || san_move == "0-0-0" {
+            let is_kingside = san_move.len() == 3;
+            return valid_moves.into_iter().find(|valid_move| valid_move.is_castle() && valid_move.is_kingside_castle() == is_kingside);
+        }
+        let mut san_chars = san_move.chars().filter(|san_char| *san_char != 'x' && *san_char != '=' && *san_char != '-').collect::<Vec<char>>();
+        let piece_type = match san_chars.first() {
+            Some('N') => KNIGHT,
+            Some('B') => BISHOP,
+            Some('R') => ROOK,
+            Some('Q') => QUEEN,
+            Some('K') => KING,
+            Some(_) => PAWN,
+            None => return None
+        };
+        if piece_type != PAWN {
+            san_chars.remove(0);
+        }
+        let promotion_piece = match san_chars.last() {
+            Some('N') | Some('n') => Some(KNIGHT),
+            Some('B') => Some(BISHOP),
+            Some('R') | Some('r') => Some(ROOK),
+            Some('Q') | Some('q') => Some(QUEEN),
+            _ => None
+        };
+        if promotion_piece.is_some() {
+            san_chars.pop();
+        }
+        // whatever comes before the destination square narrows down the origin
+        if san_chars.len() < 2 {
+            return None;
+        }
+        let dest_notation = san_chars.split_off(san_chars.len() - 2).into_iter().collect::<String>();
+        if !matches!(dest_notation.as_bytes(), [b'a'..=b'h', b'1'..=b'8']) {
+            return None;
+        }
+        let dest_square = BoardSquare::from(&dest_notation);
+        let mut from_file = None;
+        let mut from_rank = None;
+        for from_char in san_chars {
+            match from_char {
+                'a'..='h' => from_file = Some(from_char as u8 - b'a'),
+                '1'..='8' => from_rank = Some(8 - (from_char as u8 - b'0')),
+                _ => return None
+            }
+        }
+        let mut matching_moves = valid_moves.into_iter().filter(|valid_move| {
+            let from_square = valid_move.from_square();
+            return !valid_move.is_castle()
+                && valid_move.dest_square() == dest_square
+                && board.get_piece_at(&from_square).map(get_piece_type) == Some(piece_type)
+                && from_file.is_none_or(|from_file| from_square.x() == from_file)
+                && from_rank.is_none_or(|from_rank| from_square.y() == from_rank)
+                && (!board.is_promotion(valid_move) || Some(valid_move.promotion_target_piece()) == promotion_piece);
+        });
+        let matching_move = matching_moves.next()?;
+        if matching_moves.next().is_some() {
+            return None;
+        }
+        return Some(matching_move);
+    }
+}
+const fn piece_letter(piece_type: BoardPieceType) -> char {
+    return match piece_type {
+        KNIGHT => 'N',
+        BISHOP => 'B',
+        ROOK => 'R',
+        QUEEN => 'Q',
+        KING => 'K',
+        _ => 'P'
+    };
 }
 impl fmt::Display for BoardMove {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -260,11 +444,17 @@ impl BoardSquareInfo {
         }
     }
 }
+impl Default for BoardSquareInfo {
+    fn default() -> BoardSquareInfo {
+        return BoardSquareInfo::new();
+    }
+}
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct BoardInfo {
     pub valid_moves: [Vec<BoardMove>; 2],
     pub board_pieces: BoardPieces,
-    pub square_control: [BoardSquareInfo; 64]
+    pub square_control: [BoardSquareInfo; 64],
+    pub checking_squares: [Vec<BoardSquare>; 2]
 }
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
 pub struct Board(u256, u32);
@@ -320,7 +510,7 @@ impl Board {
                 'q' => 1u32 << 29,
                 'K' => 1u32 << 28,
                 'Q' => 1u32 << 27,
-                '-' | _ => 0
+                _ => 0
             };
             board_state |= castle_flag_mask;
         }
@@ -335,6 +525,53 @@ impl Board {
         let board = Board(board_image, board_state);
         return board;
     }
+    // the board keeps no move counters, so they come out as 0 and 1
+    pub fn to_fen(&self) -> String {
+        let mut fen_board = String::new();
+        for y in 0..8 {
+            let mut empty_squares = 0;
+            for x in 0..8 {
+                match self.get_piece_at(&BoardSquare(y * 8 + x)) {
+                    Some(board_piece) => {
+                        if empty_squares > 0 {
+                            fen_board.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+                        let letter = piece_letter(get_piece_type(board_piece));
+                        fen_board.push(if get_piece_color(board_piece) == WHITE { letter } else { letter.to_ascii_lowercase() });
+                    },
+                    None => empty_squares += 1
+                }
+            }
+            if empty_squares > 0 {
+                fen_board.push_str(&empty_squares.to_string());
+            }
+            if y < 7 {
+                fen_board.push('/');
+            }
+        }
+        let active_color = if self.active_color() == WHITE { "w" } else { "b" };
+        let [[black_king, black_queen], [white_king, white_queen]] = self.castle_availibility();
+        let mut castle_availibility = String::new();
+        for (is_available, castle_flag) in [(white_king, 'K'), (white_queen, 'Q'), (black_king, 'k'), (black_queen, 'q')] {
+            if is_available {
+                castle_availibility.push(castle_flag);
+            }
+        }
+        if castle_availibility.is_empty() {
+            castle_availibility.push('-');
+        }
+        let en_passant_target = self.en_passant_target_square().map_or("-".to_string(), |target_square| target_square.to_string());
+        return format!("{} {} {} {} 0 1", fen_board, active_color, castle_availibility, en_passant_target);
+    }
+    // a position with just these pieces on it, without castling or en passant rights
+    pub fn from_pieces(pieces: &[(BoardPiece, BoardSquare)], active_color: BoardColor) -> Board {
+        let mut board = Board(U256::new(0), (active_color as u32) << 31);
+        for (piece, square) in pieces {
+            board.set_piece_at(square, *piece);
+        }
+        return board;
+    }
     pub const fn active_color(&self) -> BoardColor {
         return ((self.1 >> 31) & 0b1u32) as u8;
     }
@@ -389,15 +626,158 @@ impl Board {
         }
         return board_pieces;
     }
+    pub fn get_piece_grid(&self) -> [Option<BoardPiece>; 64] {
+        let mut piece_grid = [None; 64];
+        let (upper_half, lower_half) = self.0.into_words();
+        for square_pos in 0..32 {
+            let lower_piece = ((lower_half >> (square_pos * 4)) & 0b1111) as u8;
+            let upper_piece = ((upper_half >> (square_pos * 4)) & 0b1111) as u8;
+            if is_piece(lower_piece) {
+                piece_grid[square_pos] = Some(lower_piece);
+            }
+            if is_piece(upper_piece) {
+                piece_grid[square_pos + 32] = Some(upper_piece);
+            }
+        }
+        return piece_grid;
+    }
+    // kings included, without unpacking every square
+    pub fn piece_count(&self) -> u32 {
+        let (upper_half, lower_half) = self.0.into_words();
+        let occupied_nibbles = |half: u128| (half | half >> 1 | half >> 2 | half >> 3) & 0x1111_1111_1111_1111_1111_1111_1111_1111;
+        return occupied_nibbles(upper_half).count_ones() + occupied_nibbles(lower_half).count_ones();
+    }
+    pub fn is_square_attacked(&self, square: &BoardSquare, attacking_color: BoardColor) -> bool {
+        let attacker_at = |dir: &Direction, attacker_types: &[BoardPieceType]| {
+            if let Some(attacker_square) = square.get_square_in_direction(dir) {
+                if let Some(attacker) = self.get_piece_at(&attacker_square) {
+                    return get_piece_color(attacker) == attacking_color && attacker_types.contains(&get_piece_type(attacker));
+                }
+            }
+            return false;
+        };
+        // enemy pawns attack towards us, so look back along their push direction
+        let pawn_dir = Direction::pawn_push(attacking_color).reversed();
+        if attacker_at(&Direction(-1, pawn_dir.dy()), &[PAWN]) || attacker_at(&Direction(1, pawn_dir.dy()), &[PAWN]) {
+            return true;
+        }
+        if Direction::KNIGHT_JUMPS.iter().any(|dir| attacker_at(dir, &[KNIGHT])) {
+            return true;
+        }
+        if Direction::COMPASS_ROSE.iter().any(|dir| attacker_at(dir, &[KING])) {
+            return true;
+        }
+        for (slider_type, slider_directions) in [(ROOK, Direction::CARDINALS), (BISHOP, Direction::ORDINALS)] {
+            for dir in slider_directions {
+                for square_in_dir in square.get_all_squares_in_direction(&dir) {
+                    if let Some(piece_in_dir) = self.get_piece_at(&square_in_dir) {
+                        let piece_in_dir_type = get_piece_type(piece_in_dir);
+                        if get_piece_color(piece_in_dir) == attacking_color && (piece_in_dir_type == slider_type || piece_in_dir_type == QUEEN) {
+                            return true;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        return false;
+    }
+    pub fn is_in_check(&self) -> bool {
+        let king_piece = self.active_color() | KING;
+        for square_pos in 0..64 {
+            if self.get_piece_at(&BoardSquare(square_pos)) == Some(king_piece) {
+                return self.is_square_attacked(&BoardSquare(square_pos), self.active_color() ^ 1);
+            }
+        }
+        return false;
+    }
+    pub fn is_capture(&self, board_move: &BoardMove) -> bool {
+        if board_move.is_castle() {
+            return false;
+        }
+        return board_move.is_en_passant() || self.get_piece_at(&board_move.dest_square()).is_some();
+    }
+    pub fn is_promotion(&self, board_move: &BoardMove) -> bool {
+        if board_move.is_castle() {
+            return false;
+        }
+        let dest_square = board_move.dest_square();
+        return (dest_square.y() == 0 || dest_square.y() == 7) 
+            && self.get_piece_at(&board_move.from_square()).map(get_piece_type) == Some(PAWN);
+    }
+    // valid moves for the side to move
+    pub fn get_valid_moves(&self) -> Vec<BoardMove> {
+        let active_color = self.active_color();
+        let mut board_info = self.get_board_info();
+        return std::mem::take(&mut board_info.valid_moves[active_color as usize]);
+    }
     pub fn get_board_info(&self) -> BoardInfo {
-        let mut valid_moves: [Vec<BoardMove>; 2] = [Vec::with_capacity(10), Vec::with_capacity(10)];
-        let mut board_pieces = self.get_pieces();
+        let mut valid_moves: [Vec<BoardMove>; 2] = [Vec::with_capacity(40), Vec::with_capacity(40)];
+        let board_pieces = self.get_pieces();
+        let piece_grid = self.get_piece_grid();
         
         const BOARD_SQUARE_INFO_DEFAULT: BoardSquareInfo = BoardSquareInfo::new();
         let mut square_control: [BoardSquareInfo; 64] = [BOARD_SQUARE_INFO_DEFAULT; 64];
+        // squares behind an attacked king, which a sliding piece would see if the king stepped back
+        let mut xray_control: [u64; 2] = [0, 0];
+
+        for origin_square_pos in 0..64u8 {
+            let origin_piece = match piece_grid[origin_square_pos as usize] {
+                Some(origin_piece) => origin_piece,
+                None => continue
+            };
+            let origin_square = BoardSquare(origin_square_pos);
+            let origin_piece_color = get_piece_color(origin_piece);
+            square_control[origin_square_pos as usize].occupant = Some(origin_piece);
+
+            match get_piece_type(origin_piece) {
+                PAWN => {
+                    let pawn_dir = Direction::pawn_push(origin_piece_color);
+                    for capture_direction in [Direction(-1, pawn_dir.dy()), Direction(1, pawn_dir.dy())] {
+                        if let Some(capturable_square) = origin_square.get_square_in_direction(&capture_direction) {
+                            square_control[capturable_square.pos() as usize].visibility[origin_piece_color as usize] += 1;
+                        }
+                    }
+                },
+                piece_type @ (KNIGHT | KING) => {
+                    let move_directions = if piece_type == KNIGHT { Direction::KNIGHT_JUMPS } else { Direction::COMPASS_ROSE };
+                    for move_direction in move_directions {
+                        if let Some(reachable_square) = origin_square.get_square_in_direction(&move_direction) {
+                            square_control[reachable_square.pos() as usize].visibility[origin_piece_color as usize] += 1;
+                        }
+                    }
+                },
+                piece_type => {
+                    let move_directions: &[Direction] = match piece_type {
+                        BISHOP => &Direction::ORDINALS,
+                        ROOK => &Direction::CARDINALS,
+                        _ => &Direction::COMPASS_ROSE
+                    };
+                    for move_direction in move_directions {
+                        let mut is_behind_king = false;
+                        for reachable_square in origin_square.get_all_squares_in_direction(move_direction) {
+                            if is_behind_king {
+                                xray_control[origin_piece_color as usize] |= reachable_square.mask();
+                                break;
+                            }
+                            square_control[reachable_square.pos() as usize].visibility[origin_piece_color as usize] += 1;
+                            if let Some(reachable_piece) = piece_grid[reachable_square.pos() as usize] {
+                                if reachable_piece == (origin_piece_color ^ 1) | KING {
+                                    is_behind_king = true;
+                                    continue;
+                                }
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
 
         let mut pinned_pieces: [IntMap<u8, Direction>; 2] = [IntMap::default(), IntMap::default()];
         let mut checking_squares: [Vec<BoardSquare>; 2] = [Vec::new(), Vec::new()];
+        // squares a non-king move has to land on, which are restricted to capturing or blocking a checker
+        let mut check_evasion_mask: [u64; 2] = [u64::MAX, u64::MAX];
 
         for king_color in [BLACK, WHITE] {
             if let Some(square_of_king) = &board_pieces.kings[king_color as usize] {
@@ -410,175 +790,176 @@ impl Board {
                 ] {
                     for dir in pinner_piece_move_directions {
                         // pinned piece is of the same color as color being checked
-                        let mut possible_pinned_piece: Option<u8> = None;
+                        let mut possible_pinned_square: Option<BoardSquare> = None;
+                        let mut blocking_squares_mask = 0u64;
                         'pin_direction_scan: for square_in_dir in square_of_king.get_all_squares_in_direction(&dir) {
-                            if let Some(piece_in_dir) = self.get_piece_at(&square_in_dir) {
+                            if let Some(piece_in_dir) = piece_grid[square_in_dir.pos() as usize] {
                                 let piece_in_dir_color = get_piece_color(piece_in_dir);
                                 let piece_in_dir_type = get_piece_type(piece_in_dir);
-                                if let Some(pinned_piece) = possible_pinned_piece {
-                                    if piece_in_dir_type == pinner_piece_type || piece_in_dir_type == QUEEN {
-                                        if piece_in_dir_color != king_color {
-                                            pinned_pieces[king_color as usize].insert(square_in_dir.pos(), dir);
-                                        }
+                                let is_pinner_piece = piece_in_dir_color != king_color 
+                                    && (piece_in_dir_type == pinner_piece_type || piece_in_dir_type == QUEEN);
+                                if let Some(pinned_square) = &possible_pinned_square {
+                                    if is_pinner_piece {
+                                        pinned_pieces[king_color as usize].insert(pinned_square.pos(), dir.clone());
                                     }
                                     break 'pin_direction_scan;
+                                } else if piece_in_dir_color == king_color {
+                                    possible_pinned_square = Some(square_in_dir);
                                 } else {
-                                    // no pinned piece
-                                    if piece_in_dir_color == king_color {
-                                        possible_pinned_piece = Some(piece_in_dir);
-                                    } else {
-                                        if piece_in_dir_type == pinner_piece_type || piece_in_dir_type == QUEEN {
-                                            // enemy piece is checking
-                                            checking_squares[king_color as usize].push(square_in_dir);
-                                        }
-                                        break 'pin_direction_scan;
+                                    if is_pinner_piece {
+                                        // enemy piece is checking
+                                        checking_squares[king_color as usize].push(square_in_dir);
+                                        check_evasion_mask[king_color as usize] &= blocking_squares_mask | square_in_dir.mask();
                                     }
+                                    break 'pin_direction_scan;
                                 }
+                            } else {
+                                blocking_squares_mask |= square_in_dir.mask();
+                            }
+                        }
+                    }
+                }
+                let enemy_pawn_dir = Direction::pawn_push(king_color);
+                for (checker_type, checker_directions) in [
+                    (KNIGHT, &Direction::KNIGHT_JUMPS[..]),
+                    (PAWN, &[Direction(-1, enemy_pawn_dir.dy()), Direction(1, enemy_pawn_dir.dy())][..])
+                ] {
+                    for checker_direction in checker_directions {
+                        if let Some(checker_square) = square_of_king.get_square_in_direction(checker_direction) {
+                            if piece_grid[checker_square.pos() as usize] == Some((king_color ^ 1) | checker_type) {
+                                check_evasion_mask[king_color as usize] &= checker_square.mask();
+                                checking_squares[king_color as usize].push(checker_square);
                             }
                         }
                     }
                 }
             }
         }
-        println!("{:?}", pinned_pieces);
-        for origin_square_pos in 0..64 {
+
+        for origin_square_pos in 0..64u8 {
+            let origin_piece = match piece_grid[origin_square_pos as usize] {
+                Some(origin_piece) => origin_piece,
+                None => continue
+            };
             let origin_square = BoardSquare(origin_square_pos);
-            let possible_origin_piece = self.get_piece_at(&origin_square);
-            if let None = possible_origin_piece { continue; }
-            let origin_piece = possible_origin_piece.unwrap();
-            
             let origin_piece_color = get_piece_color(origin_piece);
-            let is_white = origin_piece_color == WHITE;
             let origin_piece_type = get_piece_type(origin_piece);
+            let color_moves = &mut valid_moves[origin_piece_color as usize];
+            
+            let is_reachable = |dest_square: &BoardSquare| {
+                if let Some(dest_piece) = piece_grid[dest_square.pos() as usize] {
+                    return get_piece_color(dest_piece) != origin_piece_color;
+                }
+                return true;
+            };
+            let evades_check = |dest_square: &BoardSquare| check_evasion_mask[origin_piece_color as usize] & dest_square.mask() != 0;
+            let pinned_direction = pinned_pieces[origin_piece_color as usize].get(&origin_square_pos);
+            let follows_pin = |move_direction: &Direction| {
+                if let Some(pinned_direction) = pinned_direction {
+                    return *move_direction == *pinned_direction || *move_direction == pinned_direction.reversed();
+                }
+                return true;
+            };
             
-            square_control[origin_square_pos as usize].occupant = Some(origin_piece);
-            // TODO: track captures
             match origin_piece_type {
                 PAWN => {
-                    if let Some(pinned_direction) = pinned_pieces[origin_piece_color as usize].get(&origin_square_pos) {
-                        continue;
-                    }
-                    let dir = if is_white { -1i8 } else { 1i8 };
-                    let base_reachable_square = origin_square
-                        .get_square_in_direction(&Direction(0, dir * 1))
-                        .expect("this can only be invalid in invalid positions");
-                    if let None = self.get_piece_at(&base_reachable_square) {
-                        valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &base_reachable_square));
-                    }
-                    
-                    let is_on_home_square = origin_square.y() == 6 && is_white 
-                        || origin_square.y() == 1 && !is_white;
-                    if is_on_home_square {
-                        let extended_reachable_square = origin_square
-                            .get_square_in_direction(&Direction(0, dir * 2))
-                            .expect("cannot go oob when on home square");
-                        if let None = self.get_piece_at(&extended_reachable_square) {
-                            valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &extended_reachable_square));
+                    let pawn_dir = Direction::pawn_push(origin_piece_color);
+                    let mut push_pawn_move = |dest_square: &BoardSquare, is_en_passant: bool| {
+                        if is_en_passant {
+                            color_moves.push(BoardMove::new_as_en_passant(&origin_square, dest_square));
+                        } else if dest_square.y() == 0 || dest_square.y() == 7 {
+                            for promotion_target_piece in [QUEEN, ROOK, BISHOP, KNIGHT] {
+                                color_moves.push(BoardMove::new_as_promotion(&origin_square, dest_square, promotion_target_piece));
+                            }
+                        } else {
+                            color_moves.push(BoardMove::new(&origin_square, dest_square));
+                        }
+                    };
+                    if follows_pin(&pawn_dir) {
+                        let base_reachable_square = origin_square
+                            .get_square_in_direction(&pawn_dir)
+                            .expect("this can only be invalid in invalid positions");
+                        if piece_grid[base_reachable_square.pos() as usize].is_none() {
+                            if evades_check(&base_reachable_square) {
+                                push_pawn_move(&base_reachable_square, false);
+                            }
+                            
+                            let is_on_home_square = origin_square.y() == 6 && origin_piece_color == WHITE 
+                                || origin_square.y() == 1 && origin_piece_color == BLACK;
+                            if is_on_home_square {
+                                let extended_reachable_square = base_reachable_square
+                                    .get_square_in_direction(&pawn_dir)
+                                    .expect("cannot go oob when on home square");
+                                if piece_grid[extended_reachable_square.pos() as usize].is_none() && evades_check(&extended_reachable_square) {
+                                    push_pawn_move(&extended_reachable_square, false);
+                                }
+                            }
                         }
                     }
                     
-                    let possible_capturable_directions = [
-                        Direction(-1, dir * 1),
-                        Direction(1, dir * 1)
-                    ];
-                    for possible_capturable_direction in possible_capturable_directions {
-                        let possible_capturable_square = origin_square.get_square_in_direction(&possible_capturable_direction);
-
-                        if let Some(capturable_square) = possible_capturable_square {
-                            // en passant
-                            square_control[capturable_square.pos() as usize].visibility[origin_piece_color as usize] += 1;
-                            if let Some(en_passant_target_square) = self.en_passant_target_square() {
-                                if en_passant_target_square == capturable_square {
-                                    if let Some(en_passant_captured_square) = origin_square.get_square_in_direction(&Direction(possible_capturable_direction.dx(), 0)) {
-                                        if let Some(en_passant_captured_piece) = self.get_piece_at(&en_passant_captured_square) {
-                                            if origin_piece_color != get_piece_color(en_passant_captured_piece) {
-                                                valid_moves[origin_piece_color as usize].push(BoardMove::new_as_en_passant(&origin_square, &capturable_square));
-                                                continue;
-                                            }
-                                        }
-                                    }
-                                }
+                    for capture_direction in [Direction(-1, pawn_dir.dy()), Direction(1, pawn_dir.dy())] {
+                        if !follows_pin(&capture_direction) {
+                            continue;
+                        }
+                        let capturable_square = match origin_square.get_square_in_direction(&capture_direction) {
+                            Some(capturable_square) => capturable_square,
+                            None => continue
+                        };
+                        if let Some(capturable_piece) = piece_grid[capturable_square.pos() as usize] {
+                            if get_piece_color(capturable_piece) != origin_piece_color && evades_check(&capturable_square) {
+                                push_pawn_move(&capturable_square, false);
                             }
-                            if let Some(capturable_piece) = self.get_piece_at(&capturable_square) {
-                                let capturable_piece_color = get_piece_color(capturable_piece);
-                                if origin_piece_color != capturable_piece_color {
-                                    valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &capturable_square));
+                        } else if origin_piece_color == self.active_color() && self.en_passant_target_square() == Some(capturable_square) {
+                            // en passant can uncover a check along the rank, so just try it out
+                            let en_passant_move = BoardMove::new_as_en_passant(&origin_square, &capturable_square);
+                            let moved_board = self.create_board_from_move(&en_passant_move);
+                            if let Some(square_of_king) = &board_pieces.kings[origin_piece_color as usize] {
+                                if !moved_board.is_square_attacked(square_of_king, origin_piece_color ^ 1) {
+                                    push_pawn_move(&capturable_square, true);
                                 }
                             }
                         }
                     }
                 },
-                KNIGHT | KING => {
-                    if let Some(_) = pinned_pieces[origin_piece_color as usize].get(&origin_square_pos) {
-                        // kings cannot be pinned, and knights cannot move when pinned
+                KNIGHT => {
+                    if pinned_direction.is_some() {
+                        // knights cannot move when pinned
                         continue;
                     }
-                    let move_directions = match origin_piece_type {
-                        KNIGHT => [
-                            Direction(1, 2),
-                            Direction(-1, 2),
-                            Direction(1, -2),
-                            Direction(-1, -2),
-                            Direction(2, 1),
-                            Direction(-2, 1),
-                            Direction(2, -1),
-                            Direction(-2, -1)
-                        ],
-                        KING => Direction::COMPASS_ROSE,
-                        _ => unreachable!()
-                    };
-                    for move_direction in move_directions {
-                        let possible_reachable_square = origin_square.get_square_in_direction(
-                            &move_direction
-                        );
-                        if let Some(reachable_square) = possible_reachable_square {
-                            square_control[reachable_square.pos() as usize].visibility[origin_piece_color as usize] += 1;
-                            if square_control[reachable_square.pos() as usize].visibility[origin_piece_color as usize ^ 1] != 0 {
-                                continue;
+                    for move_direction in Direction::KNIGHT_JUMPS {
+                        if let Some(reachable_square) = origin_square.get_square_in_direction(&move_direction) {
+                            if is_reachable(&reachable_square) && evades_check(&reachable_square) {
+                                color_moves.push(BoardMove::new(&origin_square, &reachable_square));
                             }
-                            if let Some(reachable_piece) = self.get_piece_at(&reachable_square) {
-                                let piece_color = get_piece_color(reachable_piece);
-                                if piece_color != origin_piece_color {
-                                    if get_piece_type(reachable_piece) == KING && origin_piece_type == KNIGHT {
-                                        checking_squares[piece_color as usize].push(origin_square.clone()); 
-                                    }
-                                    valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &reachable_square));
-                                }
-                            } else {
-                                valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &reachable_square));
+                        }
+                    }
+                },
+                KING => {
+                    let enemy_color = (origin_piece_color ^ 1) as usize;
+                    for move_direction in Direction::COMPASS_ROSE {
+                        if let Some(reachable_square) = origin_square.get_square_in_direction(&move_direction) {
+                            let is_attacked = square_control[reachable_square.pos() as usize].visibility[enemy_color] != 0
+                                || xray_control[enemy_color] & reachable_square.mask() != 0;
+                            if is_reachable(&reachable_square) && !is_attacked {
+                                color_moves.push(BoardMove::new(&origin_square, &reachable_square));
                             }
                         }
                     }
                 },
                 BISHOP | ROOK | QUEEN => {
-                    let move_directions: Box<[Direction]> = if let Some(pinned_direction) = pinned_pieces[origin_piece_color as usize].get(&origin_square_pos) {
-                        let pinned_directions: Box<[Direction]> = Box::new([pinned_direction.clone(), Direction(-pinned_direction.dx(), -pinned_direction.dy())]);
-                        match origin_piece_type {
-                            BISHOP => if Direction::ORDINALS.contains(pinned_direction) { pinned_directions } else { Box::new([]) },
-                            ROOK => if Direction::CARDINALS.contains(pinned_direction) { pinned_directions } else { Box::new([]) },
-                            QUEEN => pinned_directions,
-                            _ => unreachable!()
-                        }
-                    } else {
-                        match origin_piece_type {
-                            BISHOP => Box::new(Direction::ORDINALS),
-                            ROOK => Box::new(Direction::CARDINALS),
-                            QUEEN => Box::new(Direction::COMPASS_ROSE),
-                            _ => unreachable!()
-                        }
+                    let move_directions: &[Direction] = match origin_piece_type {
+                        BISHOP => &Direction::ORDINALS,
+                        ROOK => &Direction::CARDINALS,
+                        _ => &Direction::COMPASS_ROSE
                     };
-                    for move_direction in move_directions.into_iter() {
-                        let reachable_squares = origin_square.get_all_squares_in_direction(move_direction);
-                        let mut seen_pieces: Vec<BoardPiece> = Vec::new();
-                        for reachable_square in reachable_squares.into_iter() {
-                            square_control[reachable_square.pos() as usize].visibility[origin_piece_color as usize] += 1;
-                            if let Some(reachable_piece) = self.get_piece_at(&reachable_square) {
-                                if get_piece_color(reachable_piece) != origin_piece_color {
-                                    valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &reachable_square));
-                                }
+                    for move_direction in move_directions.iter().filter(|move_direction| follows_pin(move_direction)) {
+                        for reachable_square in origin_square.get_all_squares_in_direction(move_direction) {
+                            let reachable_piece = piece_grid[reachable_square.pos() as usize];
+                            if is_reachable(&reachable_square) && evades_check(&reachable_square) {
+                                color_moves.push(BoardMove::new(&origin_square, &reachable_square));
+                            }
+                            if reachable_piece.is_some() {
                                 break;
-                            } else {
-                                valid_moves[origin_piece_color as usize].push(BoardMove::new(&origin_square, &reachable_square));
                             }
                         }
                     }
@@ -586,94 +967,97 @@ impl Board {
                 _ => {}
             }
         }
-        // castling, this should be constant
-        let castling_info: [[(BoardMove, u8, Box<[BoardSquare]>); 2]; 2] = [
-            [
-                (BoardMove::CASTLE_WK, WHITE, Box::new([BoardSquare::from("e1"), BoardSquare::from("f1"), BoardSquare::from("g1")])),
-                (BoardMove::CASTLE_BK, BLACK, Box::new([BoardSquare::from("e8"), BoardSquare::from("f8"), BoardSquare::from("g8")])),
-            ],
-            [
-                (BoardMove::CASTLE_WQ, WHITE, Box::new([BoardSquare::from("b1"), BoardSquare::from("c1"), BoardSquare::from("d1"), BoardSquare::from("e1")])),
-                (BoardMove::CASTLE_BQ, BLACK, Box::new([BoardSquare::from("b8"), BoardSquare::from("c8"), BoardSquare::from("d8"), BoardSquare::from("e8")])),
-            ]
-        ];
-        for castle_board_side in castling_info {
-            'castle_check: for (castle_move, color, castle_squares) in castle_board_side {
-                if self.castle_availibility()[color as usize][0] {
-                    for castle_square in castle_squares.into_iter() {
-                        if square_control[castle_square.pos() as usize].visibility[(color ^ 1) as usize] != 0 {
-                            // enemy attacks are on square
-                            continue 'castle_check;
-                        }
-                    }
-                    valid_moves[color as usize].push(castle_move);
-                }
+        for (castle_move, color) in [
+            (BoardMove::CASTLE_WK, WHITE),
+            (BoardMove::CASTLE_WQ, WHITE),
+            (BoardMove::CASTLE_BK, BLACK),
+            (BoardMove::CASTLE_BQ, BLACK)
+        ] {
+            let castle_side = if castle_move.is_kingside_castle() { 0 } else { 1 };
+            if !self.castle_availibility()[color as usize][castle_side] || !checking_squares[color as usize].is_empty() {
+                continue;
+            }
+            let (king_from, king_dest, rook_from, _) = castle_move.castle_squares();
+            if piece_grid[king_from.pos() as usize] != Some(color | KING) || piece_grid[rook_from.pos() as usize] != Some(color | ROOK) {
+                continue;
+            }
+            let (low_pos, high_pos) = (cmp::min(king_from.pos(), rook_from.pos()), cmp::max(king_from.pos(), rook_from.pos()));
+            let is_path_clear = (low_pos + 1..high_pos).all(|square_pos| piece_grid[square_pos as usize].is_none());
+            // the king cannot pass through or land on an attacked square
+            let (king_low_pos, king_high_pos) = (cmp::min(king_from.pos(), king_dest.pos()), cmp::max(king_from.pos(), king_dest.pos()));
+            let is_path_safe = (king_low_pos..=king_high_pos).all(|square_pos| square_control[square_pos as usize].visibility[(color ^ 1) as usize] == 0);
+            if is_path_clear && is_path_safe {
+                valid_moves[color as usize].push(castle_move);
             }
         }
 
         return BoardInfo {
             valid_moves,
             board_pieces,
-            square_control
+            square_control,
+            checking_squares
         };
     }
     pub fn set_piece_at(&mut self, board_square: &BoardSquare, new_piece: BoardPiece) {
-        self.0 &= !(new_piece.as_u256() << U256::from(board_square.pos() * 4));
+        let square_offset = U256::from(board_square.pos() as u32 * 4);
+        self.0 &= !(U256::new(0b1111) << square_offset);
+        self.0 |= U256::from(new_piece) << square_offset;
     }
     pub fn create_board_from_move(&self, board_move: &BoardMove) -> Board {
         // this does not check for move validity
         let mut new_board = self.clone();
+        // en passant is only possible right after a double push
+        new_board.1 &= !(0b1111111u32 << 20);
         if board_move.is_castle() {
-            let [king_x_delta, rook_x_delta] = match *board_move {
-                BoardMove::CASTLE_BQ | BoardMove::CASTLE_WQ => [-3, 3],
-                BoardMove::CASTLE_BK | BoardMove::CASTLE_WK => [2, -2],
-                _ => unreachable!()
-            };
-
-            new_board.1 &= !(0b11u32 << if self.active_color() == BLACK { 28u32 } else { 26u32 });
-            todo!();
+            let castle_color = board_move.castle_color();
+            let (king_from, king_dest, rook_from, rook_dest) = board_move.castle_squares();
+            new_board.set_piece_at(&king_from, 0b0000u8);
+            new_board.set_piece_at(&rook_from, 0b0000u8);
+            new_board.set_piece_at(&king_dest, castle_color | KING);
+            new_board.set_piece_at(&rook_dest, castle_color | ROOK);
+            new_board.1 &= !(0b11u32 << if castle_color == BLACK { 29u32 } else { 27u32 });
         } else {
             let from_square = board_move.from_square();
             let dest_square = board_move.dest_square();
 
             let from_piece = self.get_piece_at(&from_square).expect("board move should have valid move squares");
+            let from_piece_color = get_piece_color(from_piece);
             let from_piece_type = get_piece_type(from_piece);
             new_board.set_piece_at(&from_square, 0b0000u8);
 
-            let is_promotion = from_piece_type == PAWN && (from_square.y() == 0 || from_square.y() == 7);
+            let is_promotion = from_piece_type == PAWN && (dest_square.y() == 0 || dest_square.y() == 7);
             if is_promotion {
-                new_board.set_piece_at(&dest_square, board_move.promotion_target_piece());
+                new_board.set_piece_at(&dest_square, from_piece_color | board_move.promotion_target_piece());
             } else {
                 new_board.set_piece_at(&dest_square, from_piece);
             }
 
             if board_move.is_en_passant() {
-                let en_passant_captured_square = from_square.get_square_in_direction(
-                    if get_piece_color(from_piece) == WHITE { &Direction::S } else { &Direction::N }
-                ).expect("square should be valid");
+                // the captured pawn is beside the capturing pawn
+                let en_passant_captured_square = BoardSquare(from_square.y() * 8 + dest_square.x());
                 new_board.set_piece_at(&en_passant_captured_square, 0b0000u8);
             }
-
-            match from_piece_type {
-                ROOK => {
-                    // color would not be a problem
-                    // since the rook would have to be on that square in order to move out of it
-                    match (from_square.x(), from_square.y()) {
-                        (7, 7) => new_board.1 &= !(1u32 << 29), // black queenside
-                        (7, 0) => new_board.1 &= !(1u32 << 30),
-                        (0, 7) => new_board.1 &= !(1u32 << 27),
-                        (0, 0) => new_board.1 &= !(1u32 << 28),
-                        _ =>  {}
-                    }
-                },
-                KING => {
-                    new_board.1 &= !(0b11u32 << if self.active_color() == BLACK { 28u32 } else { 26u32 });
-                },
-                _ => {}
+            if from_piece_type == PAWN && (from_square.y() as i8 - dest_square.y() as i8).abs() == 2 {
+                let en_passant_target_square = BoardSquare((from_square.pos() + dest_square.pos()) / 2);
+                new_board.1 |= 1u32 << 26;
+                new_board.1 |= (en_passant_target_square.pos() as u32) << 20;
+            }
+            if from_piece_type == KING {
+                new_board.1 &= !(0b11u32 << if from_piece_color == BLACK { 29u32 } else { 27u32 });
+            }
+            // moving a rook from or capturing a rook on its home square disables castling on that side
+            for rook_square in [&from_square, &dest_square] {
+                match (rook_square.x(), rook_square.y()) {
+                    (0, 0) => new_board.1 &= !(1u32 << 29), // black queenside
+                    (7, 0) => new_board.1 &= !(1u32 << 30), // black kingside
+                    (0, 7) => new_board.1 &= !(1u32 << 27), // white queenside
+                    (7, 7) => new_board.1 &= !(1u32 << 28), // white kingside
+                    _ =>  {}
+                }
             }
         }
         // toggle active color 
-        new_board.1 ^= (1u32 << 31);
+        new_board.1 ^= 1u32 << 31;
 
         return new_board;
     }
@@ -682,7 +1066,7 @@ impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut s = String::with_capacity(12 + ((2 + 8 + 1) * 8) + 8);
         let mask = U256::new(0b1111);
-        s.push_str("  abcdefg\n\n");
+        s.push_str("  abcdefgh\n\n");
         for row in 0..8 {
             s.push_str(&(8 - row).to_string());
             s.push(' ');
@@ -716,9 +1100,10 @@ impl std::hash::Hash for Board {
         const U64_PRIME: u64 = 18361375334787046697u64;
 
         let [l, r] = self.0.0;
-        let (ul, ll) = ((l >> 32u128) as u64, l as u64);
-        let (ur, lr) = ((r >> 32u128) as u64, r as u64);
-        hasher.write_u64(ul ^ lr ^ ll ^ ur ^ (self.1 as u64 + (self.1 as u64) << 32u64));
+        let (ul, ll) = ((l >> 64u128) as u64, l as u64);
+        let (ur, lr) = ((r >> 64u128) as u64, r as u64);
+        let mixed_image = ul ^ ll.rotate_left(16) ^ ur.rotate_left(32) ^ lr.rotate_left(48);
+        hasher.write_u64((mixed_image ^ self.1 as u64).wrapping_mul(U64_PRIME));
     }
 }
 impl nohash_hasher::IsEnabled for Board {}
\ No newline at end of file
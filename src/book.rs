@@ -0,0 +1,368 @@
+use crate::{
+    board::*,
+    book_keys::POLYGLOT_RANDOM64,
+    game::Game
+};
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fs,
+    io,
+    path::Path
+};
+
+pub const BOOK_ENTRY_SIZE: usize = 16;
+pub const POLYGLOT_KEY_COUNT: usize = 781;
+
+/*
+polyglot book format, every number big endian:
+    offset  size    field
+    0       8       key of the position
+    8       2       move
+    10      2       weight, how often the move should be played relative to the others
+    12      4       learn, left alone
+entries are sorted by key, so all the moves of a position are next to each other
+
+move bits, rows counting from rank 1:
+    0-2     to file
+    3-5     to row
+    6-8     from file
+    9-11    from row
+    12-14   promotion piece, 1 to 4 for knight, bishop, rook and queen
+castling is written as the king taking its own rook, e1h1 instead of e1g1
+
+key = xor of the key of every piece on its square, at 64 * kind + square with kinds from
+    black pawn 0, white pawn 1 up to white king 11, then 768 + white short, white long,
+    black short and black long castling, 772 + the en passant file when a pawn can actually
+    take en passant, and 780 when white is to move
+
+a book only works with the exact 781 keys it was written with, which for every published book
+is the Random64 table from polyglot's source; that table is the default here, and other keys can
+be loaded from a file, either as the hex numbers of the table like polyglot's own source has them
+or as 781 big endian u64s
+*/
+const CASTLING_KEY_OFFSET: usize = 768;
+const EN_PASSANT_KEY_OFFSET: usize = 772;
+const WHITE_TO_MOVE_KEY_OFFSET: usize = 780;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PolyglotKeys(Vec<u64>);
+impl Default for PolyglotKeys {
+    // polyglot's own keys
+    fn default() -> PolyglotKeys {
+        return PolyglotKeys(POLYGLOT_RANDOM64.to_vec());
+    }
+}
+impl PolyglotKeys {
+    pub fn from_keys(keys: Vec<u64>) -> Option<PolyglotKeys> {
+        if keys.len() != POLYGLOT_KEY_COUNT {
+            return None;
+        }
+        return Some(PolyglotKeys(keys));
+    }
+    // every 0x prefixed number in the text, so a copy of the table from c source works as it is
+    pub fn from_text(text: &str) -> Option<PolyglotKeys> {
+        let mut keys = Vec::with_capacity(POLYGLOT_KEY_COUNT);
+        for word in text.split(|word_char: char| !word_char.is_ascii_alphanumeric()) {
+            let hex_digits = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                Some(hex_digits) => hex_digits.trim_end_matches(['u', 'U', 'l', 'L']),
+                None => continue
+            };
+            keys.push(u64::from_str_radix(hex_digits, 16).ok()?);
+        }
+        return PolyglotKeys::from_keys(keys);
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Option<PolyglotKeys> {
+        if bytes.len() != POLYGLOT_KEY_COUNT * 8 {
+            return None;
+        }
+        let keys = bytes.chunks_exact(8).map(|key_bytes| {
+            let mut key = 0u64;
+            for key_byte in key_bytes {
+                key = key << 8 | *key_byte as u64;
+            }
+            return key;
+        }).collect();
+        return PolyglotKeys::from_keys(keys);
+    }
+    pub fn load(path: impl AsRef<Path>) -> io::Result<PolyglotKeys> {
+        let bytes = fs::read(path)?;
+        let keys = if bytes.len() == POLYGLOT_KEY_COUNT * 8 {
+            PolyglotKeys::from_bytes(&bytes)
+        } else {
+            std::str::from_utf8(&bytes).ok().and_then(PolyglotKeys::from_text)
+        };
+        return keys.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("expected {} polyglot keys", POLYGLOT_KEY_COUNT)));
+    }
+
+    pub fn key(&self, board: &Board) -> u64 {
+        let mut key = 0u64;
+        for (square_pos, possible_piece) in board.get_piece_grid().iter().enumerate() {
+            if let Some(piece) = possible_piece {
+                let kind = (get_piece_type(*piece) / 2 - 1) * 2 + get_piece_color(*piece);
+                key ^= self.0[64 * kind as usize + to_book_square(square_pos as u8) as usize];
+            }
+        }
+        let [[black_king, black_queen], [white_king, white_queen]] = board.castle_availibility();
+        for (castling_index, can_castle) in [white_king, white_queen, black_king, black_queen].iter().enumerate() {
+            if *can_castle {
+                key ^= self.0[CASTLING_KEY_OFFSET + castling_index];
+            }
+        }
+        if let Some(en_passant_square) = board.en_passant_target_square().filter(|target_square| can_take_en_passant(board, target_square)) {
+            key ^= self.0[EN_PASSANT_KEY_OFFSET + en_passant_square.x() as usize];
+        }
+        if board.active_color() == WHITE {
+            key ^= self.0[WHITE_TO_MOVE_KEY_OFFSET];
+        }
+        return key;
+    }
+}
+
+// books count rows up from rank 1, the board counts down from rank 8
+const fn to_book_square(square_pos: u8) -> u8 {
+    return square_pos ^ 0b111000;
+}
+// the en passant file only goes into the key when a pawn stands next to the one that just moved
+fn can_take_en_passant(board: &Board, target_square: &BoardSquare) -> bool {
+    let active_color = board.active_color();
+    let pawn_y = if active_color == WHITE { target_square.y() + 1 } else { target_square.y().wrapping_sub(1) };
+    if pawn_y > 7 {
+        return false;
+    }
+    return [target_square.x().wrapping_sub(1), target_square.x() + 1].iter()
+        .filter(|pawn_x| **pawn_x < 8)
+        .any(|pawn_x| board.get_piece_at(&BoardSquare(pawn_y * 8 + pawn_x)) == Some(active_color | PAWN));
+}
+
+// the book's move bits as a legal move of the position, if there is one
+pub fn decode_book_move(board: &Board, book_move: u16) -> Option<BoardMove> {
+    let book_square = |bits: u16| to_book_square((bits & 0b111111) as u8);
+    let from_square = BoardSquare(book_square(book_move >> 6));
+    let mut dest_square = BoardSquare(book_square(book_move));
+    let is_king_move = board.get_piece_at(&from_square).map(get_piece_type) == Some(KING);
+    // the king takes its own rook when castling
+    if is_king_move && from_square.x() == 4 && from_square.y() == dest_square.y() && board.get_piece_at(&dest_square) == Some(board.active_color() | ROOK) {
+        dest_square = BoardSquare(dest_square.pos() / 8 * 8 + if dest_square.x() == 7 { 6 } else { 2 });
+    }
+    let promotion_suffix = match (book_move >> 12) & 0b111 {
+        0 => "",
+        1 => "n",
+        2 => "b",
+        3 => "r",
+        4 => "q",
+        _ => return None
+    };
+    return BoardMove::from_uci(board, &format!("{}{}{}", from_square, dest_square, promotion_suffix));
+}
+pub fn encode_book_move(board: &Board, board_move: &BoardMove) -> u16 {
+    let (from_square, dest_square) = if board_move.is_castle() {
+        let (king_from, _, rook_from, _) = board_move.castle_squares();
+        (king_from, rook_from)
+    } else {
+        (board_move.from_square(), board_move.dest_square())
+    };
+    let promotion_bits = if board.is_promotion(board_move) {
+        match board_move.promotion_target_piece() {
+            KNIGHT => 1,
+            BISHOP => 2,
+            ROOK => 3,
+            _ => 4
+        }
+    } else {
+        0
+    };
+    return promotion_bits << 12 | (to_book_square(from_square.pos()) as u16) << 6 | to_book_square(dest_square.pos()) as u16;
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct BookEntry {
+    pub key: u64,
+    pub book_move: u16,
+    pub weight: u16,
+    pub learn: u32
+}
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8; BOOK_ENTRY_SIZE]) -> BookEntry {
+        let read_number = |range: std::ops::Range<usize>| bytes[range].iter().fold(0u64, |number, number_byte| number << 8 | *number_byte as u64);
+        return BookEntry {
+            key: read_number(0..8),
+            book_move: read_number(8..10) as u16,
+            weight: read_number(10..12) as u16,
+            learn: read_number(12..16) as u32
+        };
+    }
+    pub fn to_bytes(&self) -> [u8; BOOK_ENTRY_SIZE] {
+        let mut bytes = [0u8; BOOK_ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.book_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        return bytes;
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum BookSelection {
+    // always the move with the highest weight
+    BestWeight,
+    // a move picked with a chance in proportion to its weight, by this random number
+    WeightedRandom(u64)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    entries: Vec<BookEntry>
+}
+impl Book {
+    // sorted by key, moves of the same position keep their order
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Book {
+        entries.sort_by_key(|entry| entry.key);
+        return Book {
+            entries
+        };
+    }
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Book> {
+        if !bytes.len().is_multiple_of(BOOK_ENTRY_SIZE) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "book size is not a whole number of entries"));
+        }
+        let entries = bytes.chunks_exact(BOOK_ENTRY_SIZE)
+            .map(|entry_bytes| BookEntry::from_bytes(entry_bytes.try_into().expect("chunks are entry sized")))
+            .collect();
+        return Ok(Book::from_entries(entries));
+    }
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Book> {
+        return Book::from_bytes(&fs::read(path)?);
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return self.entries.iter().flat_map(|entry| entry.to_bytes()).collect();
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        return fs::write(path, self.to_bytes());
+    }
+    pub fn entries(&self) -> &[BookEntry] {
+        return &self.entries;
+    }
+
+    // the legal book moves of the position with their weights, in the order the book has them
+    pub fn moves(&self, keys: &PolyglotKeys, board: &Board) -> Vec<(BoardMove, u16)> {
+        let key = keys.key(board);
+        let first_index = self.entries.partition_point(|entry| entry.key < key);
+        return self.entries[first_index..].iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| decode_book_move(board, entry.book_move).map(|board_move| (board_move, entry.weight)))
+            .collect();
+    }
+    // moves with no weight are in the book only to say they should not be played
+    pub fn select_move(&self, keys: &PolyglotKeys, board: &Board, selection: BookSelection) -> Option<BoardMove> {
+        let book_moves = self.moves(keys, board).into_iter().filter(|(_, weight)| *weight > 0).collect::<Vec<_>>();
+        return match selection {
+            // the first of equally good moves, like the book orders them
+            BookSelection::BestWeight => book_moves.iter().rev().max_by_key(|(_, weight)| *weight).map(|(board_move, _)| *board_move),
+            BookSelection::WeightedRandom(random_number) => {
+                let total_weight = book_moves.iter().map(|(_, weight)| *weight as u64).sum::<u64>();
+                if total_weight == 0 {
+                    return None;
+                }
+                let mut remaining_weight = random_number % total_weight;
+                for (board_move, weight) in book_moves.iter() {
+                    if remaining_weight < *weight as u64 {
+                        return Some(*board_move);
+                    }
+                    remaining_weight -= *weight as u64;
+                }
+                unreachable!("the random weight is below the total");
+            }
+        };
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct BookBuildOptions {
+    // only moves played this many plies into a game or earlier
+    pub max_ply: usize,
+    // moves played in fewer games are left out
+    pub min_games: u32,
+    // and so are moves that scored less than this for the side playing them, from 0 to 1
+    pub min_score: f64
+}
+impl Default for BookBuildOptions {
+    fn default() -> BookBuildOptions {
+        return BookBuildOptions {
+            max_ply: 20,
+            min_games: 1,
+            min_score: 0.0
+        };
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+struct MoveStats {
+    games: u32,
+    // two for a win and one for a draw, which is also the weight polyglot gives moves
+    half_points: u64
+}
+
+// counts how often each move was played in each position, and how it went
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    keys: PolyglotKeys,
+    options: BookBuildOptions,
+    move_stats: HashMap<(u64, u16), MoveStats>,
+    game_count: usize
+}
+impl BookBuilder {
+    pub fn new(keys: PolyglotKeys, options: BookBuildOptions) -> BookBuilder {
+        return BookBuilder {
+            keys,
+            options,
+            move_stats: HashMap::new(),
+            game_count: 0
+        };
+    }
+    // the moves of one game from where it started, scored by how it ended as points for white: 1, a half or 0
+    pub fn add_line(&mut self, starting_board: &Board, moves: &[BoardMove], white_points: f64) {
+        let mut board = starting_board.clone();
+        for board_move in moves.iter().take(self.options.max_ply) {
+            let points = if board.active_color() == WHITE { white_points } else { 1.0 - white_points };
+            let move_stats = self.move_stats.entry((self.keys.key(&board), encode_book_move(&board, board_move))).or_default();
+            move_stats.games += 1;
+            move_stats.half_points += (points * 2.0) as u64;
+            board = board.create_board_from_move(board_move);
+        }
+        self.game_count += 1;
+    }
+    // returns whether the game was used, which takes a result to score the moves with
+    pub fn add_game(&mut self, game: &Game) -> bool {
+        let result = match game.result {
+            Some(result) => result,
+            None => return false
+        };
+        self.add_line(&game.starting_board, game.moves(), result.points_for(WHITE));
+        return true;
+    }
+    pub const fn game_count(&self) -> usize {
+        return self.game_count;
+    }
+    pub fn build(&self) -> Book {
+        let is_kept = |move_stats: &MoveStats| {
+            let score = move_stats.half_points as f64 / (2 * move_stats.games) as f64;
+            return move_stats.games >= self.options.min_games && score >= self.options.min_score && move_stats.half_points > 0;
+        };
+        let mut kept_moves = self.move_stats.iter().filter(|(_, move_stats)| is_kept(move_stats)).collect::<Vec<_>>();
+        // weights are scaled down together if the most played move would not fit
+        let max_half_points = kept_moves.iter().map(|(_, move_stats)| move_stats.half_points).max().unwrap_or(0);
+        let scale = (max_half_points as f64 / u16::MAX as f64).max(1.0);
+        // best move first within a position, like polyglot writes them, and the same book from the same games
+        kept_moves.sort_by_key(|((key, book_move), move_stats)| (*key, std::cmp::Reverse(move_stats.half_points), *book_move));
+        let entries = kept_moves.into_iter().map(|((key, book_move), move_stats)| {
+            return BookEntry {
+                key: *key,
+                book_move: *book_move,
+                weight: ((move_stats.half_points as f64 / scale).round() as u16).max(1),
+                learn: 0
+            };
+        }).collect();
+        return Book::from_entries(entries);
+    }
+}
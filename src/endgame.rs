@@ -0,0 +1,175 @@
+use crate::{
+    board::*,
+    eval::*,
+    params::*
+};
+
+// how many of each piece both sides have, which is all it takes to pick an endgame evaluator
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct MaterialSignature {
+    // indexed by color and then by piece index, kings are left out
+    pub counts: [[usize; 5]; 2]
+}
+impl MaterialSignature {
+    pub fn new(board_pieces: &BoardPieces) -> MaterialSignature {
+        let color_counts = |color: usize| [
+            board_pieces.pawns[color].len(),
+            board_pieces.knights[color].len(),
+            board_pieces.bishops[color].len(),
+            board_pieces.rooks[color].len(),
+            board_pieces.queens[color].len()
+        ];
+        return MaterialSignature {
+            counts: [color_counts(BLACK as usize), color_counts(WHITE as usize)]
+        };
+    }
+    pub const fn count(&self, color: BoardColor, piece_type: BoardPieceType) -> usize {
+        return self.counts[color as usize][piece_index(piece_type)];
+    }
+    // knights, bishops, rooks and queens
+    pub fn piece_count(&self, color: BoardColor) -> usize {
+        return self.counts[color as usize][1..].iter().sum();
+    }
+    pub fn is_lone_king(&self, color: BoardColor) -> bool {
+        return self.counts[color as usize].iter().all(|count| *count == 0);
+    }
+    // whether the pieces alone are enough to mate a bare king by force
+    pub fn can_force_mate(&self, color: BoardColor) -> bool {
+        return self.count(color, QUEEN) > 0
+            || self.count(color, ROOK) > 0
+            || self.count(color, BISHOP) >= 2
+            || (self.count(color, BISHOP) >= 1 && self.count(color, KNIGHT) >= 1);
+    }
+}
+
+const fn square_color(square: &BoardSquare) -> u8 {
+    return (square.x() + square.y()) % 2;
+}
+// 0 on the four centre squares up to 6 in a corner
+const fn distance_from_center(square: &BoardSquare) -> i32 {
+    let file_distance = if square.x() < 4 { 3 - square.x() } else { square.x() - 4 };
+    let rank_distance = if square.y() < 4 { 3 - square.y() } else { square.y() - 4 };
+    return (file_distance + rank_distance) as i32;
+}
+fn promotion_square(pawn_square: &BoardSquare, color: BoardColor) -> BoardSquare {
+    return BoardSquare(pawn_square.x() + if color == WHITE { 0 } else { 7 * 8 });
+}
+fn non_pawn_material(board_pieces: &BoardPieces, params: &EvalParams, color: BoardColor) -> i32 {
+    let color_index = color as usize;
+    let piece_lists = [
+        (KNIGHT, &board_pieces.knights[color_index]),
+        (BISHOP, &board_pieces.bishops[color_index]),
+        (ROOK, &board_pieces.rooks[color_index]),
+        (QUEEN, &board_pieces.queens[color_index])
+    ];
+    return piece_lists.iter().map(|(piece_type, piece_squares)| piece_squares.len() as i32 * params[PIECE_VALUES + piece_index(*piece_type) * 2 + 1]).sum();
+}
+
+// enough material against a bare king, so only driving it to the edge and mating matters
+fn eval_kxk(params: &EvalParams, strong_king: &BoardSquare, weak_king: &BoardSquare) -> i32 {
+    return params[KNOWN_WIN_BONUS]
+        + params[PUSH_TO_EDGE_WEIGHT] * distance_from_center(weak_king)
+        + params[KING_PROXIMITY_WEIGHT] * (7 - strong_king.distance_from(weak_king) as i32);
+}
+
+// bishop and knight can only mate in a corner of the bishop's color
+fn eval_kbnk(params: &EvalParams, strong_king: &BoardSquare, weak_king: &BoardSquare, bishop_square: &BoardSquare) -> i32 {
+    let corners = if square_color(bishop_square) == 0 { ["a8", "h1"] } else { ["a1", "h8"] };
+    let corner_distance = corners.iter().map(|corner| weak_king.distance_from(&BoardSquare::from(corner))).min().unwrap_or(0);
+    return params[KNOWN_WIN_BONUS]
+        + params[PUSH_TO_CORNER_WEIGHT] * (7 - corner_distance as i32)
+        + params[KING_PROXIMITY_WEIGHT] * (7 - strong_king.distance_from(weak_king) as i32);
+}
+
+// the rule of the square, a pawn the bare king cannot catch is as good as a queen;
+// a rook pawn whose corner the bare king already holds is a draw
+fn eval_kpk(params: &EvalParams, trace: &mut EvalTrace, strong_color: BoardColor, strong_king: &BoardSquare, weak_king: &BoardSquare, pawn_square: &BoardSquare) {
+    let weak_color = strong_color ^ 1;
+    let promotion_square = promotion_square(pawn_square, strong_color);
+    // the double push saves a move from the starting rank
+    let pawn_moves = (8 - relative_rank(pawn_square, strong_color)).min(5) as i32;
+    let weak_king_moves = weak_king.distance_from(&promotion_square) as i32 - i32::from(trace.active_color == weak_color);
+    let is_path_blocked = strong_king.x() == pawn_square.x() && relative_rank(strong_king, strong_color) > relative_rank(pawn_square, strong_color);
+    if weak_king_moves > pawn_moves && !is_path_blocked {
+        let bonus = params[KNOWN_WIN_BONUS] + params[UNSTOPPABLE_PAWN_RANK_WEIGHT] * relative_rank(pawn_square, strong_color) as i32;
+        trace.add(EvalTerm::Endgame, strong_color, TaperedScore(bonus, bonus));
+    } else if (pawn_square.x() == 0 || pawn_square.x() == 7) && weak_king.distance_from(&promotion_square) <= 1 {
+        trace.endgame_scale = 0;
+    }
+}
+
+// how much of the score to keep in endings that are drawish even with more material
+fn endgame_scale(board_pieces: &BoardPieces, signature: &MaterialSignature, params: &EvalParams, strong_color: BoardColor) -> i32 {
+    let weak_color = strong_color ^ 1;
+    let strong_index = strong_color as usize;
+    let weak_index = weak_color as usize;
+    if signature.count(strong_color, PAWN) == 0 {
+        // pieces that cannot mate on their own leave nothing to win with
+        if !signature.can_force_mate(strong_color) && signature.count(weak_color, PAWN) == 0 {
+            return 0;
+        }
+        let rook_value = params[PIECE_VALUES + piece_index(ROOK) * 2 + 1];
+        if non_pawn_material(board_pieces, params, strong_color) - non_pawn_material(board_pieces, params, weak_color) < rook_value {
+            let is_lone_minor = signature.piece_count(strong_color) == 1 && signature.count(strong_color, KNIGHT) + signature.count(strong_color, BISHOP) == 1;
+            return if is_lone_minor { 0 } else { params[LESS_THAN_A_ROOK_AHEAD_SCALE] };
+        }
+    }
+
+    // a lone bishop that does not cover the corner cannot shift the bare king out of it
+    let strong_pawns = &board_pieces.pawns[strong_index];
+    if signature.piece_count(strong_color) == 1 && signature.count(strong_color, BISHOP) == 1 && !strong_pawns.is_empty() {
+        let pawn_file = strong_pawns[0].x();
+        if (pawn_file == 0 || pawn_file == 7) && strong_pawns.iter().all(|pawn_square| pawn_square.x() == pawn_file) {
+            let promotion_square = promotion_square(&strong_pawns[0], strong_color);
+            let is_wrong_bishop = square_color(&board_pieces.bishops[strong_index][0]) != square_color(&promotion_square);
+            if let Some(weak_king) = board_pieces.kings[weak_index] {
+                if is_wrong_bishop && weak_king.distance_from(&promotion_square) <= 1 {
+                    return 0;
+                }
+            }
+        }
+    }
+
+    if signature.count(strong_color, BISHOP) == 1 && signature.count(weak_color, BISHOP) == 1
+        && square_color(&board_pieces.bishops[strong_index][0]) != square_color(&board_pieces.bishops[weak_index][0]) {
+        let is_bishops_only = signature.piece_count(strong_color) == 1 && signature.piece_count(weak_color) == 1;
+        return if is_bishops_only { params[OPPOSITE_BISHOPS_SCALE] } else { params[OPPOSITE_BISHOPS_WITH_PIECES_SCALE] };
+    }
+    return FULL_ENDGAME_SCALE;
+}
+
+// picks a specialised evaluator by material, falling back to scaling the generic terms
+pub fn eval_endgame(board_pieces: &BoardPieces, params: &EvalParams, trace: &mut EvalTrace) {
+    let signature = MaterialSignature::new(board_pieces);
+    let (black_king, white_king) = match board_pieces.kings {
+        [Some(black_king), Some(white_king)] => (black_king, white_king),
+        _ => return
+    };
+    for strong_color in [BLACK, WHITE] {
+        let weak_color = strong_color ^ 1;
+        if !signature.is_lone_king(weak_color) || signature.is_lone_king(strong_color) {
+            continue;
+        }
+        let (strong_king, weak_king) = if strong_color == WHITE { (white_king, black_king) } else { (black_king, white_king) };
+        let strong_index = strong_color as usize;
+        let is_only = |piece_type: BoardPieceType, count: usize| {
+            return signature.count(strong_color, piece_type) == count;
+        };
+        if is_only(PAWN, 0) && is_only(KNIGHT, 1) && is_only(BISHOP, 1) && is_only(ROOK, 0) && is_only(QUEEN, 0) {
+            let bonus = eval_kbnk(params, &strong_king, &weak_king, &board_pieces.bishops[strong_index][0]);
+            trace.add(EvalTerm::Endgame, strong_color, TaperedScore(bonus, bonus));
+            return;
+        }
+        if is_only(PAWN, 1) && signature.piece_count(strong_color) == 0 {
+            eval_kpk(params, trace, strong_color, &strong_king, &weak_king, &board_pieces.pawns[strong_index][0]);
+            return;
+        }
+        if signature.can_force_mate(strong_color) {
+            let bonus = eval_kxk(params, &strong_king, &weak_king);
+            trace.add(EvalTerm::Endgame, strong_color, TaperedScore(bonus, bonus));
+            return;
+        }
+    }
+    let strong_color = if trace.total().taper(trace.game_phase) >= 0 { WHITE } else { BLACK };
+    trace.endgame_scale = endgame_scale(board_pieces, &signature, params, strong_color);
+}
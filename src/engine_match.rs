@@ -0,0 +1,422 @@
+use crate::{
+    board::*,
+    game::*,
+    pgn::is_valid_fen,
+    score::Score,
+    uci::{
+        UciEngine,
+        ENGINE_NAME
+    }
+};
+use std::{
+    fs,
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Write
+    },
+    path::Path,
+    process::{
+        Child,
+        ChildStdin,
+        ChildStdout,
+        Command,
+        Stdio
+    },
+    sync::{
+        Arc,
+        Mutex
+    },
+    time::{
+        Duration,
+        Instant
+    }
+};
+
+// how an engine is reached, by uci either way
+enum EngineConnection {
+    // this engine in the same process, its output collected in a buffer
+    Internal {
+        engine: Box<UciEngine>,
+        output: Arc<Mutex<Vec<u8>>>
+    },
+    // a program run as a child process, talked to over its stdin and stdout
+    External {
+        child: Child,
+        stdin: ChildStdin,
+        stdout: BufReader<ChildStdout>
+    }
+}
+
+// one side of a match
+pub struct MatchEngine {
+    pub name: String,
+    connection: EngineConnection
+}
+impl MatchEngine {
+    // this engine with the given uci options set
+    pub fn internal(options: &[(String, String)]) -> io::Result<MatchEngine> {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let option_names = options.iter().map(|(option_name, option_value)| format!("{}={}", option_name, option_value)).collect::<Vec<String>>();
+        let mut match_engine = MatchEngine {
+            name: std::iter::once(ENGINE_NAME.to_string()).chain(option_names).collect::<Vec<String>>().join(" "),
+            connection: EngineConnection::Internal {
+                engine: Box::new(UciEngine::new(output.clone())),
+                output
+            }
+        };
+        match_engine.start(options)?;
+        return Ok(match_engine);
+    }
+    // starts the program and takes the name it gives in its uci reply
+    pub fn spawn(program: impl AsRef<Path>, options: &[(String, String)]) -> io::Result<MatchEngine> {
+        let mut child = Command::new(program.as_ref()).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut match_engine = MatchEngine {
+            name: program.as_ref().display().to_string(),
+            connection: EngineConnection::External { child, stdin, stdout }
+        };
+        let uci_lines = match_engine.start(options)?;
+        if let Some(engine_name) = uci_lines.iter().find_map(|uci_line| uci_line.strip_prefix("id name ")) {
+            match_engine.name = engine_name.trim().to_string();
+        }
+        return Ok(match_engine);
+    }
+    fn start(&mut self, options: &[(String, String)]) -> io::Result<Vec<String>> {
+        self.send("uci")?;
+        let uci_lines = self.read_until("uciok")?;
+        for (option_name, option_value) in options {
+            self.send(&format!("setoption name {} value {}", option_name, option_value))?;
+        }
+        self.wait_until_ready()?;
+        return Ok(uci_lines);
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        return match &mut self.connection {
+            EngineConnection::Internal { engine, .. } => engine.handle_command(command).map(|_| ()),
+            EngineConnection::External { stdin, .. } => {
+                writeln!(stdin, "{}", command)?;
+                stdin.flush()
+            }
+        };
+    }
+    // the lines up to and including the first that starts with the prefix
+    fn read_until(&mut self, prefix: &str) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        match &mut self.connection {
+            EngineConnection::Internal { engine, output } => {
+                // the internal engine answers as soon as its search is done
+                engine.finish_search();
+                let output = std::mem::take(&mut *output.lock().unwrap());
+                for line in String::from_utf8_lossy(&output).lines() {
+                    lines.push(line.to_string());
+                    if line.starts_with(prefix) {
+                        return Ok(lines);
+                    }
+                }
+            },
+            EngineConnection::External { stdout, .. } => {
+                let mut line = String::new();
+                while stdout.read_line(&mut line)? > 0 {
+                    let trimmed_line = line.trim_end().to_string();
+                    line.clear();
+                    let is_done = trimmed_line.starts_with(prefix);
+                    lines.push(trimmed_line);
+                    if is_done {
+                        return Ok(lines);
+                    }
+                }
+            }
+        }
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("{} stopped before answering with {}", self.name, prefix)));
+    }
+    fn wait_until_ready(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        return self.read_until("readyok").map(|_| ());
+    }
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send("ucinewgame")?;
+        return self.wait_until_ready();
+    }
+    // the move the engine wants to play in the game's current position, with the last score it gave for it
+    pub fn choose_move(&mut self, game: &Game, go_command: &str) -> io::Result<(Option<BoardMove>, Option<Score>)> {
+        let mut board = game.starting_board.clone();
+        let uci_moves = game.moves().iter().map(|board_move| {
+            let uci_move = board_move.to_uci(&board);
+            board = board.create_board_from_move(board_move);
+            return uci_move;
+        }).collect::<Vec<String>>();
+        let moves_part = if uci_moves.is_empty() { String::new() } else { format!(" moves {}", uci_moves.join(" ")) };
+        self.send(&format!("position fen {}{}", game.starting_fen(), moves_part))?;
+        self.send(go_command)?;
+        let search_lines = self.read_until("bestmove")?;
+
+        let best_move = search_lines.last().and_then(|bestmove_line| bestmove_line.split_whitespace().nth(1));
+        let best_move = best_move.and_then(|uci_move| BoardMove::from_uci(&game.current_board, uci_move));
+        let score = search_lines.iter().rev().find_map(|info_line| {
+            let info_parts = info_line.split_whitespace().collect::<Vec<&str>>();
+            let score_index = info_parts.iter().position(|info_part| *info_part == "score")?;
+            let score_value = info_parts.get(score_index + 2)?.parse::<i32>().ok()?;
+            return match info_parts.get(score_index + 1) {
+                Some(&"cp") => Some(Score::from_centipawns(score_value)),
+                Some(&"mate") if score_value > 0 => Some(Score::mate_in(score_value as usize * 2 - 1)),
+                Some(&"mate") => Some(Score::mated_in(score_value.unsigned_abs() as usize * 2)),
+                _ => None
+            };
+        });
+        return Ok((best_move, score));
+    }
+}
+impl Drop for MatchEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        if let EngineConnection::External { child, .. } = &mut self.connection {
+            // an engine that does not quit on its own gets killed
+            let quit_deadline = Instant::now() + Duration::from_secs(1);
+            while Instant::now() < quit_deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// what each move gets to search with
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MatchLimits {
+    Depth(u8),
+    Nodes(u64),
+    MoveTime(Duration),
+    // a clock for each side that gains the increment after every move
+    Clock {
+        base: Duration,
+        increment: Duration
+    }
+}
+impl MatchLimits {
+    fn go_command(self, clocks: [Duration; 2]) -> String {
+        return match self {
+            MatchLimits::Depth(depth) => format!("go depth {}", depth),
+            MatchLimits::Nodes(nodes) => format!("go nodes {}", nodes),
+            MatchLimits::MoveTime(move_time) => format!("go movetime {}", move_time.as_millis()),
+            MatchLimits::Clock { increment, .. } => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                clocks[WHITE as usize].as_millis(),
+                clocks[BLACK as usize].as_millis(),
+                increment.as_millis(),
+                increment.as_millis()
+            )
+        };
+    }
+}
+
+// plays one game from the opening, the engines indexed by the color they play;
+// the rules end it, or a side loses by playing an illegal move or running out of time
+pub fn play_game(engines: [&mut MatchEngine; 2], opening_fen: &str, limits: MatchLimits) -> io::Result<Game> {
+    let [black_engine, white_engine] = engines;
+    let mut engines = [black_engine, white_engine];
+    let mut game = Game::from_fen(opening_fen);
+    game.set_tag("White", &engines[WHITE as usize].name);
+    game.set_tag("Black", &engines[BLACK as usize].name);
+    for engine in engines.iter_mut() {
+        engine.new_game()?;
+    }
+    let (base_time, increment) = match limits {
+        MatchLimits::Clock { base, increment } => (base, increment),
+        _ => (Duration::ZERO, Duration::ZERO)
+    };
+    let mut clocks = [base_time; 2];
+    let termination = loop {
+        if let Some(outcome) = game.outcome() {
+            game.result = Some(outcome.result());
+            break None;
+        }
+        let mover = game.current_board.active_color();
+        let move_start = Instant::now();
+        let (best_move, score) = engines[mover as usize].choose_move(&game, &limits.go_command(clocks))?;
+        let move_time = move_start.elapsed();
+        let loser_result = if mover == WHITE { GameResult::BlackWins } else { GameResult::WhiteWins };
+        if let MatchLimits::Clock { .. } = limits {
+            if move_time > clocks[mover as usize] {
+                game.result = Some(loser_result);
+                break Some("time forfeit");
+            }
+            clocks[mover as usize] = clocks[mover as usize] - move_time + increment;
+        }
+        // from_uci only gives legal moves, so anything else is a move the engine may not play
+        let Some(best_move) = best_move else {
+            game.result = Some(loser_result);
+            break Some("rules infraction");
+        };
+        game.play_move(&best_move);
+        let move_index = game.moves().len() - 1;
+        let annotation = game.annotation_mut(move_index).expect("the move was just played");
+        annotation.eval = score.map(|score| if mover == WHITE { score } else { -score });
+        if let MatchLimits::Clock { .. } = limits {
+            annotation.clock = Some(clocks[mover as usize]);
+        }
+    };
+    if let Some(termination) = termination {
+        game.set_tag("Termination", termination);
+    }
+    return Ok(game);
+}
+
+// the positions games start from, one fen a line; the move counters may be left out like in epd
+pub fn load_openings(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+    let mut openings = Vec::new();
+    for (line_index, opening_line) in fs::read_to_string(path)?.lines().enumerate() {
+        let opening_line = opening_line.trim();
+        if opening_line.is_empty() || opening_line.starts_with('#') {
+            continue;
+        }
+        let opening_fen = opening_line.split_whitespace().take(6).collect::<Vec<&str>>().join(" ");
+        if !is_valid_fen(&opening_fen) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {} is not a position", line_index + 1, opening_line)));
+        }
+        openings.push(opening_fen);
+    }
+    return Ok(openings);
+}
+
+// wins, draws and losses from the first engine's side
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32
+}
+impl MatchScore {
+    pub fn add_result(&mut self, result: GameResult, first_engine_color: BoardColor) {
+        match result.points_for(first_engine_color) {
+            points if points > 0.75 => self.wins += 1,
+            points if points < 0.25 => self.losses += 1,
+            _ => self.draws += 1
+        }
+    }
+    pub const fn games(&self) -> u32 {
+        return self.wins + self.draws + self.losses;
+    }
+    // the share of the points, from 0 to 1
+    pub fn score(&self) -> f64 {
+        return (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64;
+    }
+    // the variance of a single game's points
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        let squared_deviations = self.wins as f64 * (1.0 - score).powi(2) + self.draws as f64 * (0.5 - score).powi(2) + self.losses as f64 * score.powi(2);
+        return squared_deviations / self.games().max(1) as f64;
+    }
+    // the difference the score points to, none while every game went one way
+    pub fn elo(&self) -> Option<f64> {
+        let score = self.score();
+        if self.games() == 0 || score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+        return Some(elo_from_score(score));
+    }
+    // the 95% confidence interval around the elo difference
+    pub fn elo_interval(&self) -> Option<(f64, f64)> {
+        self.elo()?;
+        let margin = 1.96 * (self.variance() / self.games() as f64).sqrt();
+        let clamp_score = |score: f64| score.clamp(1e-6, 1.0 - 1e-6);
+        return Some((elo_from_score(clamp_score(self.score() - margin)), elo_from_score(clamp_score(self.score() + margin))));
+    }
+}
+pub fn elo_from_score(score: f64) -> f64 {
+    return 400.0 * (score / (1.0 - score)).log10();
+}
+pub fn score_from_elo(elo: f64) -> f64 {
+    return 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SprtStatus {
+    Continue,
+    // the first engine is no more than elo0 stronger
+    AcceptH0,
+    // the first engine is at least elo1 stronger
+    AcceptH1
+}
+
+// sequential probability ratio test of elo0 against elo1, stopping the match once the log likelihood
+// ratio leaves the bounds set by the false positive rate alpha and the false negative rate beta
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64
+}
+impl Default for Sprt {
+    fn default() -> Sprt {
+        return Sprt {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05
+        };
+    }
+}
+impl Sprt {
+    pub fn bounds(&self) -> (f64, f64) {
+        return ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln());
+    }
+    // the normal approximation of the generalized sprt on the game scores
+    pub fn llr(&self, match_score: &MatchScore) -> f64 {
+        let variance = match_score.variance();
+        if match_score.games() == 0 || variance <= 0.0 {
+            return 0.0;
+        }
+        let (score0, score1) = (score_from_elo(self.elo0), score_from_elo(self.elo1));
+        return match_score.games() as f64 * (score1 - score0) * (2.0 * match_score.score() - score0 - score1) / (2.0 * variance);
+    }
+    pub fn status(&self, match_score: &MatchScore) -> SprtStatus {
+        let (lower_bound, upper_bound) = self.bounds();
+        let llr = self.llr(match_score);
+        if llr <= lower_bound {
+            return SprtStatus::AcceptH0;
+        }
+        if llr >= upper_bound {
+            return SprtStatus::AcceptH1;
+        }
+        return SprtStatus::Continue;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    pub games: u32,
+    // each is played twice, with the engines swapping colors
+    pub openings: Vec<String>,
+    pub limits: MatchLimits,
+    pub sprt: Option<Sprt>
+}
+
+// plays the games in order, handing each one over as it finishes; the first engine has white in even games
+pub fn run_match(engines: [&mut MatchEngine; 2], options: &MatchOptions, mut on_game: impl FnMut(&Game, &MatchScore) -> io::Result<()>) -> io::Result<MatchScore> {
+    let [first_engine, second_engine] = engines;
+    let mut match_score = MatchScore::default();
+    let openings = if options.openings.is_empty() { vec![STARTING_FEN.to_string()] } else { options.openings.clone() };
+    for game_index in 0..options.games {
+        let opening_fen = &openings[(game_index as usize / 2) % openings.len()];
+        let first_engine_color = if game_index % 2 == 0 { WHITE } else { BLACK };
+        let game_engines = if first_engine_color == WHITE { [&mut *second_engine, &mut *first_engine] } else { [&mut *first_engine, &mut *second_engine] };
+        let mut game = play_game(game_engines, opening_fen, options.limits)?;
+        game.set_tag("Event", "engine match");
+        game.set_tag("Round", &(game_index + 1).to_string());
+        match_score.add_result(game.result.expect("play_game always ends with a result"), first_engine_color);
+        on_game(&game, &match_score)?;
+        if options.sprt.is_some_and(|sprt| sprt.status(&match_score) != SprtStatus::Continue) {
+            break;
+        }
+    }
+    return Ok(match_score);
+}
@@ -0,0 +1,186 @@
+use crate::{
+    board::*,
+    pgn::is_valid_fen,
+    score::Score,
+    search::*
+};
+use std::{
+    fs,
+    io,
+    path::Path,
+    time::Duration
+};
+
+/*
+an epd line is the first four fields of a fen followed by operations, each an opcode,
+its operands and a semicolon:
+    r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - bm Nxc6; id "WAC.001";
+operands in double quotes may hold spaces and semicolons
+
+opcodes a test suite cares about:
+    bm      best moves, any of them counts as solving the position
+    am      moves to avoid
+    dm      a mate in this many moves
+    id      the name of the position
+    c0-c9   comments
+*/
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EpdPosition {
+    pub board: Board,
+    // opcodes with their operands, in the order they came
+    pub operations: Vec<(String, Vec<String>)>
+}
+impl EpdPosition {
+    pub fn parse(epd_line: &str) -> Result<EpdPosition, String> {
+        let mut fen_fields = Vec::new();
+        let mut rest = epd_line.trim();
+        for _ in 0..4 {
+            let (fen_field, after_field) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            fen_fields.push(fen_field);
+            rest = after_field.trim_start();
+        }
+        let fen = fen_fields.join(" ");
+        if !is_valid_fen(&fen) {
+            return Err(format!("{} is not a position", fen));
+        }
+
+        let mut operations = Vec::new();
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut is_in_quotes = false;
+        let mut is_in_word = false;
+        for operation_char in rest.chars() {
+            match operation_char {
+                '"' => {
+                    is_in_quotes = !is_in_quotes;
+                    is_in_word = true;
+                },
+                ';' if !is_in_quotes => {
+                    if is_in_word {
+                        words.push(std::mem::take(&mut word));
+                        is_in_word = false;
+                    }
+                    if words.is_empty() {
+                        continue;
+                    }
+                    let opcode = words.remove(0);
+                    operations.push((opcode, std::mem::take(&mut words)));
+                },
+                _ if operation_char.is_whitespace() && !is_in_quotes => {
+                    if is_in_word {
+                        words.push(std::mem::take(&mut word));
+                        is_in_word = false;
+                    }
+                },
+                _ => {
+                    word.push(operation_char);
+                    is_in_word = true;
+                }
+            }
+        }
+        if is_in_quotes {
+            return Err("a quoted operand is never closed".to_string());
+        }
+        if is_in_word {
+            words.push(word);
+        }
+        // the last operation may go without its semicolon
+        if !words.is_empty() {
+            let opcode = words.remove(0);
+            operations.push((opcode, words));
+        }
+        return Ok(EpdPosition {
+            board: Board::from_fen(&fen),
+            operations
+        });
+    }
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        return self.operations.iter().find(|(operation_opcode, _)| operation_opcode == opcode).map(|(_, operands)| operands.as_slice());
+    }
+    pub fn id(&self) -> Option<&str> {
+        return self.operands("id").and_then(|operands| operands.first()).map(String::as_str);
+    }
+    // c0 to c9
+    pub fn comment(&self, comment_number: usize) -> Option<&str> {
+        return self.operands(&format!("c{}", comment_number)).and_then(|operands| operands.first()).map(String::as_str);
+    }
+    pub fn best_moves(&self) -> Result<Vec<BoardMove>, String> {
+        return self.moves_of("bm");
+    }
+    pub fn avoid_moves(&self) -> Result<Vec<BoardMove>, String> {
+        return self.moves_of("am");
+    }
+    pub fn mate_moves(&self) -> Option<u32> {
+        return self.operands("dm").and_then(|operands| operands.first()).and_then(|mate_moves| mate_moves.parse().ok());
+    }
+    // san like the standard has it, though some suites use uci
+    fn moves_of(&self, opcode: &str) -> Result<Vec<BoardMove>, String> {
+        return self.operands(opcode).unwrap_or(&[]).iter().map(|operand| {
+            return BoardMove::from_san(&self.board, operand)
+                .or_else(|| BoardMove::from_uci(&self.board, operand))
+                .ok_or_else(|| format!("{} {} is not a legal move", opcode, operand));
+        }).collect();
+    }
+}
+
+// every position of a suite, skipping blank lines and # comments
+pub fn parse_epd(epd_text: &str) -> io::Result<Vec<EpdPosition>> {
+    let mut positions = Vec::new();
+    for (line_index, epd_line) in epd_text.lines().enumerate() {
+        let epd_line = epd_line.trim();
+        if epd_line.is_empty() || epd_line.starts_with('#') {
+            continue;
+        }
+        let position = EpdPosition::parse(epd_line).map_err(|message| {
+            return io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_index + 1, message));
+        })?;
+        positions.push(position);
+    }
+    return Ok(positions);
+}
+pub fn load_epd(path: impl AsRef<Path>) -> io::Result<Vec<EpdPosition>> {
+    return parse_epd(&fs::read_to_string(path)?);
+}
+
+// how the search did on one position
+#[derive(Debug, Clone)]
+pub struct EpdOutcome {
+    pub best_move: Option<BoardMove>,
+    pub score: Score,
+    pub depth: u8,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    // none when the position has no bm, am or dm to check against
+    pub solved: Option<bool>
+}
+
+// searches the position from scratch and checks the move against bm and am, and the score against dm
+pub fn run_epd_position(searcher: &mut Searcher, position: &EpdPosition, limits: &SearchLimits) -> Result<EpdOutcome, String> {
+    let best_moves = position.best_moves()?;
+    let avoid_moves = position.avoid_moves()?;
+    let mate_moves = position.mate_moves();
+    let mut limits = limits.clone();
+    if mate_moves.is_some() {
+        limits.mate = mate_moves;
+    }
+    searcher.clear_hash();
+    let result = searcher.search(&position.board, &[], &limits, |_| {});
+
+    let has_criteria = !best_moves.is_empty() || !avoid_moves.is_empty() || mate_moves.is_some();
+    let solved = has_criteria.then(|| {
+        let found_move = |board_move: &BoardMove| result.best_move == Some(*board_move);
+        let is_mate_found = mate_moves.is_none_or(|mate_moves| {
+            return result.score.mate_moves().is_some_and(|found_moves| found_moves > 0 && found_moves as u32 <= mate_moves);
+        });
+        return (best_moves.is_empty() || best_moves.iter().any(found_move)) && !avoid_moves.iter().any(found_move) && is_mate_found;
+    });
+    return Ok(EpdOutcome {
+        best_move: result.best_move,
+        score: result.score,
+        depth: result.depth,
+        nodes: result.nodes,
+        elapsed: result.elapsed,
+        solved
+    });
+}
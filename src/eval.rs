@@ -1,35 +1,474 @@
-use crate::board::*;
-pub fn eval_board_basic(board: Board) -> i32 {
-    let BoardInfo {
-        board_pieces,
-        valid_moves,
-        square_control
-    } = board.get_board_info();
-    
-    let victor_side = (i32::from(board_pieces.white_king.is_some()) - i32::from(board_pieces.black_king.is_some()));
-    // victor_side is 0 when both kings (or none) exist
-    if victor_side {
-        return victor_side * i32::MAX;
-    }
-    let material_evaluation: i32 = 
-        (board_pieces.white_pawns.len() as i32 - board_pieces.black_pawns.len() as i32) * 1
-        + (board_pieces.white_knights.len() as i32 - board_pieces.black_knights.len() as i32) * 3
-        + (board_pieces.white_bishops.len() as i32 - board_pieces.black_bishops.len() as i32) * 3
-        + (board_pieces.white_rooks.len() as i32 - board_pieces.black_rooks.len() as i32) * 5
-        + (board_pieces.white_queens.len() as i32 - board_pieces.black_queens.len() as i32) * 9;
-    
-    // expressed through a number, higher means more endgame
-    let game_phase = [
-        (8 - board_pieces.white_pawns.len()) * 1
-        + (3 - board_pieces.white_knight.len()) * 5
-        + (3 - board_pieces.white_bishops.len()) * 5
-        + (3 - board_pieces.white_rooks.len()) * 7
-        + (1 - board_pieces.white_queens.len()) * 15,
-        (8 - board_pieces.black_pawns.len()) * 1
-        + (3 - board_pieces.black_knight.len()) * 5
-        + (3 - board_pieces.black_bishops.len()) * 5
-        + (3 - board_pieces.black_rooks.len()) * 7
-        + (1 - board_pieces.black_queens.len()) * 15,
+use crate::{
+    board::*,
+    endgame::eval_endgame,
+    eval_params::EVAL_PARAMS,
+    params::*,
+    pawns::*,
+    score::Score
+};
+use std::{
+    fmt,
+    ops::{
+        Add,
+        AddAssign,
+        Mul,
+        Neg,
+        Sub,
+        SubAssign
+    }
+};
+
+// a middlegame and an endgame value, blended together by the game phase
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub struct TaperedScore(pub i32, pub i32);
+impl TaperedScore {
+    pub const fn mg(&self) -> i32 {
+        return self.0;
+    }
+    pub const fn eg(&self) -> i32 {
+        return self.1;
+    }
+    pub const fn taper(&self, game_phase: i32) -> i32 {
+        return (self.mg() * (MAX_GAME_PHASE - game_phase) + self.eg() * game_phase) / MAX_GAME_PHASE;
+    }
+}
+impl Add for TaperedScore {
+    type Output = TaperedScore;
+    fn add(self, other: TaperedScore) -> TaperedScore {
+        return TaperedScore(self.0 + other.0, self.1 + other.1);
+    }
+}
+impl AddAssign for TaperedScore {
+    fn add_assign(&mut self, other: TaperedScore) {
+        *self = *self + other;
+    }
+}
+impl Sub for TaperedScore {
+    type Output = TaperedScore;
+    fn sub(self, other: TaperedScore) -> TaperedScore {
+        return TaperedScore(self.0 - other.0, self.1 - other.1);
+    }
+}
+impl SubAssign for TaperedScore {
+    fn sub_assign(&mut self, other: TaperedScore) {
+        *self = *self - other;
+    }
+}
+impl Mul<i32> for TaperedScore {
+    type Output = TaperedScore;
+    fn mul(self, multiplier: i32) -> TaperedScore {
+        return TaperedScore(self.0 * multiplier, self.1 * multiplier);
+    }
+}
+impl Neg for TaperedScore {
+    type Output = TaperedScore;
+    fn neg(self) -> TaperedScore {
+        return TaperedScore(-self.0, -self.1);
+    }
+}
+
+// the game phase is scaled to this whatever the phase weights add up to
+pub const MAX_GAME_PHASE: i32 = 256;
+// endgame scale factors are out of this, anything less pulls the score towards a draw
+pub const FULL_ENDGAME_SCALE: i32 = 64;
+
+// pawn is 0 up to king being 5, for indexing the tables
+pub const fn piece_index(piece_type: BoardPieceType) -> usize {
+    return (piece_type >> 1) as usize - 1;
+}
+pub const fn piece_square_value(params: &EvalParams, piece_type: BoardPieceType, color: BoardColor, square: &BoardSquare) -> TaperedScore {
+    // black reads the tables upside down
+    let table_pos = if color == WHITE { square.pos() } else { square.pos() ^ 0b111000 } as usize;
+    return tapered(params, PIECE_SQUARE_TABLES + (piece_index(piece_type) * 64 + table_pos) * 2);
+}
+
+// expressed through a number, higher means more endgame
+pub fn game_phase(board_pieces: &BoardPieces, params: &EvalParams) -> i32 {
+    let phase_weight = |piece_type: BoardPieceType| params[PHASE_WEIGHTS + piece_index(piece_type)];
+    // the phase weight of one side's starting pieces
+    let starting_phase_material = 8 * phase_weight(PAWN) + 2 * phase_weight(KNIGHT) + 2 * phase_weight(BISHOP) + 2 * phase_weight(ROOK) + phase_weight(QUEEN);
+    let mut phase_material = 0;
+    for color in [BLACK, WHITE] {
+        let color = color as usize;
+        phase_material += board_pieces.pawns[color].len() as i32 * phase_weight(PAWN)
+            + board_pieces.knights[color].len() as i32 * phase_weight(KNIGHT)
+            + board_pieces.bishops[color].len() as i32 * phase_weight(BISHOP)
+            + board_pieces.rooks[color].len() as i32 * phase_weight(ROOK)
+            + board_pieces.queens[color].len() as i32 * phase_weight(QUEEN);
+    }
+    // the tuner may zero every weight, which leaves nothing to tell the phases apart
+    let max_phase_material = (starting_phase_material * 2).max(1);
+    // promotions can leave more material than the game started with
+    return (max_phase_material - phase_material).clamp(0, max_phase_material) * MAX_GAME_PHASE / max_phase_material;
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum EvalTerm {
+    Material,
+    PieceSquares,
+    Mobility,
+    CenterControl,
+    Space,
+    Outposts,
+    PawnStructure,
+    PassedPawns,
+    KingAttacks,
+    KingShelter,
+    Endgame
+}
+pub const EVAL_TERM_COUNT: usize = 11;
+impl EvalTerm {
+    pub const ALL: [EvalTerm; EVAL_TERM_COUNT] = [
+        EvalTerm::Material,
+        EvalTerm::PieceSquares,
+        EvalTerm::Mobility,
+        EvalTerm::CenterControl,
+        EvalTerm::Space,
+        EvalTerm::Outposts,
+        EvalTerm::PawnStructure,
+        EvalTerm::PassedPawns,
+        EvalTerm::KingAttacks,
+        EvalTerm::KingShelter,
+        EvalTerm::Endgame
     ];
-    todo!();
-}
\ No newline at end of file
+    pub const fn name(&self) -> &'static str {
+        return match self {
+            EvalTerm::Material => "material",
+            EvalTerm::PieceSquares => "piece squares",
+            EvalTerm::Mobility => "mobility",
+            EvalTerm::CenterControl => "center control",
+            EvalTerm::Space => "space",
+            EvalTerm::Outposts => "outposts",
+            EvalTerm::PawnStructure => "pawn structure",
+            EvalTerm::PassedPawns => "passed pawns",
+            EvalTerm::KingAttacks => "king attacks",
+            EvalTerm::KingShelter => "king shelter",
+            EvalTerm::Endgame => "endgame"
+        };
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EvalTrace {
+    // indexed by term and color, each from that color's point of view
+    pub terms: [[TaperedScore; 2]; EVAL_TERM_COUNT],
+    pub game_phase: i32,
+    pub active_color: BoardColor,
+    // out of FULL_ENDGAME_SCALE, lowered in endings that are hard to win whatever the terms say
+    pub endgame_scale: i32,
+    // only nonzero when a king is missing, which decides the game outright
+    pub victor_side: i32
+}
+impl EvalTrace {
+    pub fn add(&mut self, term: EvalTerm, color: BoardColor, score: TaperedScore) {
+        self.terms[term as usize][color as usize] += score;
+    }
+    pub fn term(&self, term: EvalTerm, color: BoardColor) -> TaperedScore {
+        return self.terms[term as usize][color as usize];
+    }
+    // every term added up from white's point of view, before tapering
+    pub fn total(&self) -> TaperedScore {
+        return self.terms.iter().fold(TaperedScore::default(), |total, term_scores| total + term_scores[WHITE as usize] - term_scores[BLACK as usize]);
+    }
+    // the score the search sees, in centipawns from the side to move's point of view
+    pub fn score(&self) -> Score {
+        let side_multiplier = if self.active_color == WHITE { 1 } else { -1 };
+        if self.victor_side != 0 {
+            return if side_multiplier * self.victor_side > 0 { Score::mate_in(0) } else { Score::mated_in(0) };
+        }
+        return Score::from_centipawns(side_multiplier * self.total().taper(self.game_phase) * self.endgame_scale / FULL_ENDGAME_SCALE);
+    }
+}
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>16} | {:>13} | {:>13} | {:>13}", "term", "white", "black", "total")?;
+        writeln!(f, "{:>16} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}", "", "mg", "eg", "mg", "eg", "mg", "eg")?;
+        writeln!(f, "{}", "-".repeat(16 + 3 * 16))?;
+        for term in EvalTerm::ALL {
+            let white_score = self.term(term, WHITE);
+            let black_score = self.term(term, BLACK);
+            let total_score = white_score - black_score;
+            writeln!(
+                f,
+                "{:>16} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+                term.name(),
+                white_score.mg(), white_score.eg(),
+                black_score.mg(), black_score.eg(),
+                total_score.mg(), total_score.eg()
+            )?;
+        }
+        writeln!(f, "{}", "-".repeat(16 + 3 * 16))?;
+        let total = self.total();
+        writeln!(f, "{:>16} | {:>29} | {:>6} {:>6}", "total", "", total.mg(), total.eg())?;
+        writeln!(f, "game phase {} of {}, where {} is a bare endgame", self.game_phase, MAX_GAME_PHASE, MAX_GAME_PHASE)?;
+        writeln!(f, "endgame scale {} of {}", self.endgame_scale, FULL_ENDGAME_SCALE)?;
+        let side_to_move = if self.active_color == WHITE { "white" } else { "black" };
+        let white_score = if self.active_color == WHITE { self.score() } else { -self.score() };
+        return write!(f, "score {} for white, {} for {} to move", white_score, self.score(), side_to_move);
+    }
+}
+
+// material and placement of every piece
+fn eval_piece_squares(board_pieces: &BoardPieces, params: &EvalParams, trace: &mut EvalTrace) {
+    for color in [BLACK, WHITE] {
+        let color_index = color as usize;
+        let king_squares = board_pieces.kings[color_index].iter().copied().collect::<Vec<BoardSquare>>();
+        let piece_lists = [
+            (PAWN, &board_pieces.pawns[color_index]),
+            (KNIGHT, &board_pieces.knights[color_index]),
+            (BISHOP, &board_pieces.bishops[color_index]),
+            (ROOK, &board_pieces.rooks[color_index]),
+            (QUEEN, &board_pieces.queens[color_index]),
+            (KING, &king_squares)
+        ];
+        for (piece_type, piece_squares) in piece_lists {
+            for piece_square in piece_squares {
+                trace.add(EvalTerm::Material, color, tapered(params, PIECE_VALUES + piece_index(piece_type) * 2));
+                trace.add(EvalTerm::PieceSquares, color, piece_square_value(params, piece_type, color, piece_square));
+            }
+        }
+    }
+}
+
+const CENTER_SQUARES: [BoardSquare; 4] = [BoardSquare::from("d4"), BoardSquare::from("e4"), BoardSquare::from("d5"), BoardSquare::from("e5")];
+// rank 1 to rank 8, as seen from the given side
+pub const fn relative_rank(square: &BoardSquare, color: BoardColor) -> u8 {
+    return if color == WHITE { 8 - square.y() } else { square.y() + 1 };
+}
+// squares the pawns of the given color attack
+pub fn pawn_attacks(board_pieces: &BoardPieces, color: BoardColor) -> u64 {
+    let pawn_dir = Direction::pawn_push(color);
+    let mut attacks = 0u64;
+    for pawn_square in board_pieces.pawns[color as usize].iter() {
+        for capture_direction in [Direction(-1, pawn_dir.dy()), Direction(1, pawn_dir.dy())] {
+            if let Some(attacked_square) = pawn_square.get_square_in_direction(&capture_direction) {
+                attacks |= attacked_square.mask();
+            }
+        }
+    }
+    return attacks;
+}
+// whether an enemy pawn could ever attack this square, from the given side's point of view
+fn can_be_attacked_by_pawns(board_pieces: &BoardPieces, square: &BoardSquare, color: BoardColor) -> bool {
+    let enemy_color = color ^ 1;
+    return board_pieces.pawns[enemy_color as usize].iter().any(|pawn_square| {
+        return pawn_square.x().abs_diff(square.x()) == 1 && relative_rank(pawn_square, color) > relative_rank(square, color);
+    });
+}
+
+// moves of the minor and major pieces which do not land on squares enemy pawns guard
+fn eval_mobility(board: &Board, board_info: &BoardInfo, params: &EvalParams, trace: &mut EvalTrace) {
+    for color in [BLACK, WHITE] {
+        let enemy_pawn_attacks = pawn_attacks(&board_info.board_pieces, color ^ 1);
+        for board_move in board_info.valid_moves[color as usize].iter() {
+            if board_move.is_castle() || enemy_pawn_attacks & board_move.dest_square().mask() != 0 {
+                continue;
+            }
+            if let Some(moving_piece) = board.get_piece_at(&board_move.from_square()) {
+                trace.add(EvalTerm::Mobility, color, tapered(params, MOBILITY_WEIGHTS + piece_index(get_piece_type(moving_piece)) * 2));
+            }
+        }
+    }
+}
+
+// centre control, space and outposts
+fn eval_square_control(board_info: &BoardInfo, params: &EvalParams, trace: &mut EvalTrace) {
+    let board_pieces = &board_info.board_pieces;
+    for color in [BLACK, WHITE] {
+        for center_square in CENTER_SQUARES.iter() {
+            let visibility = board_info.square_control[center_square.pos() as usize].visibility;
+            trace.add(EvalTerm::CenterControl, color, tapered(params, CENTER_CONTROL_WEIGHT) * visibility[color as usize] as i32);
+        }
+
+        let enemy_pawn_attacks = pawn_attacks(board_pieces, color ^ 1);
+        let friendly_pawn_attacks = pawn_attacks(board_pieces, color);
+
+        let mut space = 0;
+        for square_pos in 0..64u8 {
+            let square = BoardSquare(square_pos);
+            let square_info = &board_info.square_control[square_pos as usize];
+            let is_space_square = (2..=5).contains(&square.x()) && (2..=4).contains(&relative_rank(&square, color));
+            let is_safe = enemy_pawn_attacks & square.mask() == 0 && square_info.occupant != Some(color | PAWN);
+            if is_space_square && is_safe && square_info.visibility[color as usize] > 0 {
+                space += 1;
+            }
+        }
+        trace.add(EvalTerm::Space, color, tapered(params, SPACE_WEIGHT) * space);
+
+        for (outpost_index, outpost_squares) in [&board_pieces.knights[color as usize], &board_pieces.bishops[color as usize]].iter().enumerate() {
+            for outpost_square in outpost_squares.iter() {
+                let is_outpost = (4..=6).contains(&relative_rank(outpost_square, color))
+                    && friendly_pawn_attacks & outpost_square.mask() != 0
+                    && !can_be_attacked_by_pawns(board_pieces, outpost_square, color);
+                if is_outpost {
+                    trace.add(EvalTerm::Outposts, color, tapered(params, OUTPOST_BONUSES + outpost_index * 2));
+                }
+            }
+        }
+    }
+}
+
+// how many ranks in front of the king the shield and storm weights reach
+const PAWN_SHIELD_DISTANCES: u8 = 3;
+const PAWN_STORM_DISTANCES: u8 = 5;
+
+// squares a piece attacks, with sliding pieces stopping at the first piece in the way
+fn piece_attacks(board_info: &BoardInfo, piece: BoardPiece, square: &BoardSquare) -> u64 {
+    let (attack_directions, is_slider): (&[Direction], bool) = match get_piece_type(piece) {
+        PAWN => {
+            let pawn_dir = Direction::pawn_push(get_piece_color(piece));
+            let mut attacks = 0u64;
+            for capture_direction in [Direction(-1, pawn_dir.dy()), Direction(1, pawn_dir.dy())].iter() {
+                if let Some(attacked_square) = square.get_square_in_direction(capture_direction) {
+                    attacks |= attacked_square.mask();
+                }
+            }
+            return attacks;
+        },
+        KNIGHT => (&Direction::KNIGHT_JUMPS, false),
+        BISHOP => (&Direction::ORDINALS, true),
+        ROOK => (&Direction::CARDINALS, true),
+        QUEEN => (&Direction::COMPASS_ROSE, true),
+        _ => (&Direction::COMPASS_ROSE, false)
+    };
+    let mut attacks = 0u64;
+    for attack_direction in attack_directions {
+        let mut current_square = *square;
+        while let Some(attacked_square) = current_square.get_square_in_direction(attack_direction) {
+            attacks |= attacked_square.mask();
+            if !is_slider || board_info.square_control[attacked_square.pos() as usize].occupant.is_some() {
+                break;
+            }
+            current_square = attacked_square;
+        }
+    }
+    return attacks;
+}
+
+// attacks on the king zone, safe checks, and the pawns and files around the king
+fn eval_king_safety(board_info: &BoardInfo, params: &EvalParams, trace: &mut EvalTrace) {
+    let board_pieces = &board_info.board_pieces;
+    for color in [BLACK, WHITE] {
+        let enemy_color = color ^ 1;
+        let king_square = match board_pieces.kings[color as usize] {
+            Some(king_square) => king_square,
+            None => continue
+        };
+        let king_zone = piece_attacks(board_info, color | KING, &king_square) | king_square.mask();
+
+        let mut attacker_count = 0;
+        let mut attack_units = 0;
+        for (square_pos, square_info) in board_info.square_control.iter().enumerate() {
+            let attacker_piece = match square_info.occupant {
+                Some(piece) if get_piece_color(piece) == enemy_color => piece,
+                _ => continue
+            };
+            let attacker_index = piece_index(get_piece_type(attacker_piece));
+            if params[KING_ATTACK_WEIGHTS + attacker_index] == 0 {
+                continue;
+            }
+            let attacker_square = BoardSquare(square_pos as u8);
+            let attacks = piece_attacks(board_info, attacker_piece, &attacker_square);
+            let king_zone_attacks = attacks & king_zone;
+            if king_zone_attacks != 0 {
+                attacker_count += 1;
+                attack_units += params[KING_ATTACK_WEIGHTS + attacker_index] * king_zone_attacks.count_ones() as i32;
+            }
+            // the squares this piece could check from are the ones it would see from the king
+            let checking_squares = piece_attacks(board_info, attacker_piece, &king_square) & attacks;
+            for check_square_pos in 0..64 {
+                let check_square_info = &board_info.square_control[check_square_pos];
+                let is_safe_check = checking_squares & (1u64 << check_square_pos) != 0
+                    && check_square_info.occupant.is_none_or(|occupant| get_piece_color(occupant) == color)
+                    && check_square_info.visibility[color as usize] == 0;
+                if is_safe_check {
+                    attack_units += params[SAFE_CHECK_WEIGHTS + attacker_index];
+                }
+            }
+        }
+        for king_zone_square_pos in 0..64 {
+            let visibility = board_info.square_control[king_zone_square_pos].visibility;
+            if king_zone & (1u64 << king_zone_square_pos) != 0 && visibility[enemy_color as usize] > visibility[color as usize] {
+                attack_units += params[WEAK_KING_ZONE_WEIGHT];
+            }
+        }
+        // a lone attacker rarely gets anywhere
+        if attacker_count >= 2 {
+            // attack units turn into a penalty that grows quadratically, since attacks only get dangerous together
+            let king_danger = (attack_units * attack_units / params[KING_DANGER_DIVISOR].max(1)).min(params[MAX_KING_DANGER]);
+            trace.add(EvalTerm::KingAttacks, color, -TaperedScore(king_danger, 0));
+        }
+
+        let friendly_pawns = pawn_mask(board_pieces, color);
+        let enemy_pawns = pawn_mask(board_pieces, enemy_color);
+        let forward_mask = forward_ranks_mask(&king_square, color);
+        let king_rank = relative_rank(&king_square, color);
+        for file in king_square.x().saturating_sub(1)..=(king_square.x() + 1).min(7) {
+            let file_mask = file_mask(file);
+            // the pawn closest to the king on each side, which is the one that matters
+            let closest_pawn_distance = |pawns: u64| {
+                return (0..64u8)
+                    .filter(|square_pos| pawns & file_mask & forward_mask & (1u64 << square_pos) != 0)
+                    .map(|square_pos| relative_rank(&BoardSquare(square_pos), color) - king_rank)
+                    .min();
+            };
+            trace.add(EvalTerm::KingShelter, color, match closest_pawn_distance(friendly_pawns) {
+                Some(distance) if distance < PAWN_SHIELD_DISTANCES => tapered(params, PAWN_SHIELD_BONUSES + distance as usize * 2),
+                Some(_) => TaperedScore::default(),
+                None => tapered(params, MISSING_SHIELD_PENALTY)
+            });
+            if let Some(distance) = closest_pawn_distance(enemy_pawns).filter(|distance| *distance < PAWN_STORM_DISTANCES) {
+                trace.add(EvalTerm::KingShelter, color, tapered(params, PAWN_STORM_PENALTIES + distance as usize * 2));
+            }
+            if friendly_pawns & file_mask == 0 {
+                trace.add(EvalTerm::KingShelter, color, tapered(params, if enemy_pawns & file_mask == 0 { OPEN_KING_FILE_PENALTY } else { HALF_OPEN_KING_FILE_PENALTY }));
+            }
+        }
+    }
+}
+
+fn trace_with(board: &Board, pawn_table: Option<&mut PawnTable>, params: &EvalParams) -> EvalTrace {
+    let board_info = board.get_board_info();
+    let board_pieces = &board_info.board_pieces;
+    let mut trace = EvalTrace {
+        terms: [[TaperedScore::default(); 2]; EVAL_TERM_COUNT],
+        game_phase: game_phase(board_pieces, params),
+        active_color: board.active_color(),
+        endgame_scale: FULL_ENDGAME_SCALE,
+        victor_side: i32::from(board_pieces.kings[WHITE as usize].is_some()) - i32::from(board_pieces.kings[BLACK as usize].is_some())
+    };
+    if trace.victor_side != 0 {
+        return trace;
+    }
+    let pawn_structure = match pawn_table {
+        Some(pawn_table) => pawn_table.probe(board, board_pieces, params),
+        None => eval_pawn_structure(board_pieces, params)
+    };
+    eval_piece_squares(board_pieces, params, &mut trace);
+    eval_mobility(board, &board_info, params, &mut trace);
+    eval_square_control(&board_info, params, &mut trace);
+    eval_king_safety(&board_info, params, &mut trace);
+    for color in [BLACK, WHITE] {
+        trace.add(EvalTerm::PawnStructure, color, pawn_structure.scores[color as usize]);
+    }
+    eval_passed_pawns(&board_info, pawn_structure.passed_pawns, params, &mut trace);
+    // last, since the scaling looks at who the other terms favour
+    eval_endgame(board_pieces, params, &mut trace);
+    return trace;
+}
+// every term that goes into the evaluation, which adds up to exactly what eval_board_basic returns
+pub fn trace(board: &Board) -> EvalTrace {
+    return trace_with(board, None, &EVAL_PARAMS);
+}
+// the same with other weights, for tuning them
+pub fn trace_with_params(board: &Board, params: &EvalParams) -> EvalTrace {
+    return trace_with(board, None, params);
+}
+// in centipawns, from the side to move's point of view
+pub fn eval_board_basic(board: &Board) -> Score {
+    return trace_with(board, None, &EVAL_PARAMS).score();
+}
+// same as eval_board_basic, but looks the pawn structure up in the table first
+pub fn eval_board(board: &Board, pawn_table: &mut PawnTable) -> Score {
+    return trace_with(board, Some(pawn_table), &EVAL_PARAMS).score();
+}
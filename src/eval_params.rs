@@ -0,0 +1,105 @@
+// the evaluation weights, laid out the way the tune binary writes them so its output can replace this file
+use crate::params::EvalParams;
+
+pub const EVAL_PARAMS: EvalParams = [
+    // piece values
+    90, 110, 320, 300, 330, 320, 480, 540, 950, 1000, 0, 0,
+    // pawn square table
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    60, 60, 60, 60, 60, 60, 60, 60, 60, 60, 60, 60, 60, 60, 60, 60,
+    20, 40, 20, 40, 30, 40, 40, 40, 40, 40, 30, 40, 20, 40, 20, 40,
+    5, 20, 5, 20, 10, 20, 30, 20, 30, 20, 10, 20, 5, 20, 5, 20,
+    0, 10, 0, 10, 5, 10, 25, 10, 25, 10, 5, 10, 0, 10, 0, 10,
+    5, 5, -5, 5, -5, 5, 5, 5, 5, 5, -5, 5, -5, 5, 5, 5,
+    5, 0, 10, 0, 10, 0, -20, 0, -20, 0, 10, 0, 10, 0, 5, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    // knight square table
+    -50, -40, -40, -30, -30, -20, -30, -20, -30, -20, -30, -20, -40, -30, -50, -40,
+    -40, -30, -20, -15, 0, -5, 0, 0, 0, 0, 0, -5, -20, -15, -40, -30,
+    -30, -20, 0, -5, 10, 10, 15, 15, 15, 15, 10, 10, 0, -5, -30, -20,
+    -30, -20, 5, 0, 15, 15, 20, 20, 20, 20, 15, 15, 5, 0, -30, -20,
+    -30, -20, 0, 0, 15, 15, 20, 20, 20, 20, 15, 15, 0, 0, -30, -20,
+    -30, -20, 5, -5, 10, 10, 15, 15, 15, 15, 10, 10, 5, -5, -30, -20,
+    -40, -30, -20, -15, 0, -5, 5, 0, 5, 0, 0, -5, -20, -15, -40, -30,
+    -50, -40, -40, -30, -30, -20, -30, -20, -30, -20, -30, -20, -40, -30, -50, -40,
+    // bishop square table
+    -20, -15, -10, -10, -10, -10, -10, -10, -10, -10, -10, -10, -10, -10, -20, -15,
+    -10, -10, 0, -5, 0, 0, 0, 0, 0, 0, 0, 0, 0, -5, -10, -10,
+    -10, -10, 0, 0, 5, 5, 10, 5, 10, 5, 5, 5, 0, 0, -10, -10,
+    -10, -10, 5, 0, 5, 5, 10, 10, 10, 10, 5, 5, 5, 0, -10, -10,
+    -10, -10, 0, 0, 10, 5, 10, 10, 10, 10, 10, 5, 0, 0, -10, -10,
+    -10, -10, 10, 0, 10, 5, 10, 5, 10, 5, 10, 5, 10, 0, -10, -10,
+    -10, -10, 5, -5, 0, 0, 0, 0, 0, 0, 0, 0, 5, -5, -10, -10,
+    -20, -15, -10, -10, -10, -10, -10, -10, -10, -10, -10, -10, -10, -10, -20, -15,
+    // rook square table
+    0, 5, 0, 5, 0, 5, 0, 5, 0, 5, 0, 5, 0, 5, 0, 5,
+    5, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 5, 10,
+    -5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -5, 0,
+    -5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -5, 0,
+    -5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -5, 0,
+    -5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -5, 0,
+    -5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, -5, 0,
+    0, 0, 0, 0, 0, 0, 5, 0, 5, 0, 0, 0, 0, 0, 0, 0,
+    // queen square table
+    -20, -20, -10, -10, -10, -10, -5, -10, -5, -10, -10, -10, -10, -10, -20, -20,
+    -10, -10, 0, 0, 0, 5, 0, 5, 0, 5, 0, 5, 0, 0, -10, -10,
+    -10, -10, 0, 5, 5, 10, 5, 10, 5, 10, 5, 10, 0, 5, -10, -10,
+    -5, -10, 0, 5, 5, 10, 5, 15, 5, 15, 5, 10, 0, 5, -5, -10,
+    -5, -10, 0, 5, 5, 10, 5, 15, 5, 15, 5, 10, 0, 5, -5, -10,
+    -10, -10, 0, 5, 5, 10, 5, 10, 5, 10, 5, 10, 0, 5, -10, -10,
+    -10, -10, 0, 0, 0, 5, 0, 5, 0, 5, 0, 5, 0, 0, -10, -10,
+    -20, -20, -10, -10, -10, -10, -5, -10, -5, -10, -10, -10, -10, -10, -20, -20,
+    // king square table
+    -30, -50, -40, -40, -40, -30, -50, -20, -50, -20, -40, -30, -40, -40, -30, -50,
+    -30, -30, -40, -20, -40, -10, -50, 0, -50, 0, -40, -10, -40, -20, -30, -30,
+    -30, -30, -40, -10, -40, 20, -50, 30, -50, 30, -40, 20, -40, -10, -30, -30,
+    -30, -30, -40, -10, -40, 30, -50, 40, -50, 40, -40, 30, -40, -10, -30, -30,
+    -20, -30, -30, -10, -30, 30, -40, 40, -40, 40, -30, 30, -30, -10, -20, -30,
+    -10, -30, -20, -10, -20, 20, -20, 30, -20, 30, -20, 20, -20, -10, -10, -30,
+    20, -30, 20, -30, 0, 0, 0, 0, 0, 0, 0, 0, 20, -30, 20, -30,
+    20, -50, 30, -30, 10, -30, 0, -30, 0, -30, 10, -30, 30, -30, 20, -50,
+    // mobility weights
+    0, 0, 4, 4, 5, 5, 2, 4, 1, 2, 0, 0,
+    // center control weight
+    6, 0,
+    // space weight
+    3, 0,
+    // outpost bonuses
+    25, 10, 12, 5,
+    // doubled penalty
+    -10, -20,
+    // isolated penalty
+    -10, -15,
+    // backward penalty
+    -8, -10,
+    // connected bonuses
+    0, 0, 0, 0, 5, 2, 8, 4, 12, 8, 20, 15, 35, 25, 0, 0,
+    // passed bonuses
+    0, 0, 0, 0, 5, 10, 10, 20, 20, 35, 35, 60, 60, 100, 0, 0,
+    // candidate bonuses
+    0, 0, 0, 0, 2, 5, 5, 10, 10, 18, 18, 30, 0, 0, 0, 0,
+    // passed blocked penalties
+    0, 0, 0, 0, 0, -5, -2, -10, -5, -15, -10, -25, -15, -35, 0, 0,
+    // passed free path bonuses
+    0, 0, 0, 0, 0, 5, 2, 10, 5, 20, 10, 35, 20, 60, 0, 0,
+    // passed controlled stop bonuses
+    0, 0, 0, 0, 0, 2, 1, 5, 2, 10, 5, 15, 10, 25, 0, 0,
+    // king attack weights, weak king zone weight and safe check weights
+    0, 2, 2, 3, 5, 0, 2, 0, 6, 4, 8, 6, 0,
+    // king danger divisor and max king danger
+    16, 600,
+    // pawn shield bonuses
+    0, 0, 15, 0, 8, 0,
+    // missing shield penalty
+    -15, 0,
+    // pawn storm penalties
+    0, 0, 0, 0, -20, 0, -12, 0, -6, 0,
+    // half open and open king file penalties
+    -12, 0, -20, 0,
+    // phase weights
+    1, 5, 5, 7, 15,
+    // known win bonus and endgame weights
+    2000, 20, 10, 40, 20,
+    // endgame scales
+    24, 48, 16,
+];
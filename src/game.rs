@@ -1,12 +1,319 @@
-use crate::board::*;
-use std::{
-    collections::HashMap
+use crate::{
+    board::*,
+    game_tree::*,
+    score::Score
 };
-use ethnum::*;
 use nohash_hasher::*;
+use std::{
+    fmt,
+    time::Duration
+};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw
+}
+impl GameResult {
+    pub fn from_pgn(result_token: &str) -> Option<GameResult> {
+        return match result_token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None
+        };
+    }
+    pub const fn to_pgn(self) -> &'static str {
+        return match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2"
+        };
+    }
+    // 1 for a win, a half for a draw
+    pub fn points_for(self, color: BoardColor) -> f64 {
+        return match self {
+            GameResult::WhiteWins => if color == WHITE { 1.0 } else { 0.0 },
+            GameResult::BlackWins => if color == BLACK { 1.0 } else { 0.0 },
+            GameResult::Draw => 0.5
+        };
+    }
+}
+
+// why a game is over by the rules
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum GameOutcome {
+    Checkmate(BoardColor),
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoves,
+    Repetition
+}
+impl GameOutcome {
+    pub const fn result(self) -> GameResult {
+        return match self {
+            GameOutcome::Checkmate(WHITE) => GameResult::WhiteWins,
+            GameOutcome::Checkmate(_) => GameResult::BlackWins,
+            _ => GameResult::Draw
+        };
+    }
+}
+impl fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            GameOutcome::Checkmate(WHITE) => write!(f, "white mates"),
+            GameOutcome::Checkmate(_) => write!(f, "black mates"),
+            GameOutcome::Stalemate => write!(f, "stalemate"),
+            GameOutcome::InsufficientMaterial => write!(f, "insufficient material"),
+            GameOutcome::FiftyMoves => write!(f, "fifty move rule"),
+            GameOutcome::Repetition => write!(f, "threefold repetition")
+        };
+    }
+}
+
+// what a pgn has to say about a move besides the move itself
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct MoveAnnotation {
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    // numeric annotation glyphs, 1 for a good move, 2 for a mistake and so on
+    pub nags: Vec<u8>,
+    // what the mover's clock showed after the move, for [%clk]
+    pub clock: Option<Duration>,
+    // the search's score after the move from white's side, for [%eval]
+    pub eval: Option<Score>
+}
 
+// a game played out from a starting position, keeping every position along the way;
+// the moves are the current line through a tree that keeps the variations explored too
+#[derive(Debug, Clone)]
 pub struct Game {
+    pub starting_board: Board,
     pub current_board: Board,
+    // plies since the last capture or pawn move
     pub halfmove: u8,
-    pub threefold_store: IntMap<BoardMove, u8>
-}
\ No newline at end of file
+    pub fullmove_number: u32,
+    // how often each position came up, by zobrist key
+    pub threefold_store: IntMap<u64, u8>,
+    // pgn tags in the order they came in
+    pub tags: Vec<(String, String)>,
+    // as recorded, not necessarily how the position on the board ends
+    pub result: Option<GameResult>,
+    tree: GameTree,
+    current_node: NodeId,
+    moves: Vec<BoardMove>,
+    // the position before each move, with its halfmove clock
+    previous_positions: Vec<(Board, u8)>
+}
+impl Game {
+    pub fn new(starting_board: Board) -> Game {
+        let mut threefold_store = IntMap::default();
+        threefold_store.insert(starting_board.zobrist_key(), 1);
+        let tree = GameTree::new(starting_board.clone());
+        return Game {
+            current_board: starting_board.clone(),
+            starting_board,
+            halfmove: 0,
+            fullmove_number: 1,
+            threefold_store,
+            tags: Vec::new(),
+            result: None,
+            current_node: tree.root(),
+            tree,
+            moves: Vec::new(),
+            previous_positions: Vec::new()
+        };
+    }
+    // the board itself has no move counters, so they are taken from the fen here
+    pub fn from_fen(fen: &str) -> Game {
+        let mut game = Game::new(Board::from_fen(fen));
+        let mut counters = fen.split_whitespace().skip(4).map(|counter| counter.parse::<u32>().ok());
+        game.halfmove = counters.next().flatten().unwrap_or(0).min(u8::MAX as u32) as u8;
+        game.fullmove_number = counters.next().flatten().unwrap_or(1).max(1);
+        return game;
+    }
+    // with the counters the game started with
+    pub fn starting_fen(&self) -> String {
+        let starting_halfmove = self.previous_positions.first().map_or(self.halfmove, |(_, halfmove)| *halfmove);
+        let black_moves = self.boards_before_moves().filter(|board| board.active_color() == BLACK).count() as u32;
+        return with_counters(&self.starting_board, starting_halfmove, self.fullmove_number - black_moves);
+    }
+    pub fn fen(&self) -> String {
+        return with_counters(&self.current_board, self.halfmove, self.fullmove_number);
+    }
+    pub fn moves(&self) -> &[BoardMove] {
+        return &self.moves;
+    }
+    // one for each move
+    pub fn annotations(&self) -> Vec<&MoveAnnotation> {
+        return self.tree.path_to(self.current_node).into_iter().map(|node_id| &self.tree.node(node_id).annotation).collect();
+    }
+    pub fn annotation_mut(&mut self, move_index: usize) -> Option<&mut MoveAnnotation> {
+        let node_id = *self.tree.path_to(self.current_node).get(move_index)?;
+        return self.tree.annotation_mut(node_id);
+    }
+    pub const fn tree(&self) -> &GameTree {
+        return &self.tree;
+    }
+    // the node of the last move played, or the root
+    pub const fn current_node(&self) -> NodeId {
+        return self.current_node;
+    }
+    // the position each move was played in
+    pub fn boards_before_moves(&self) -> impl Iterator<Item = &Board> {
+        return self.previous_positions.iter().map(|(board, _)| board);
+    }
+    // every earlier position, like the search takes them for spotting repetitions
+    pub fn history(&self) -> Vec<Board> {
+        return self.boards_before_moves().cloned().collect();
+    }
+    pub fn tag(&self, tag_name: &str) -> Option<&str> {
+        return self.tags.iter().find(|(name, _)| name == tag_name).map(|(_, value)| value.as_str());
+    }
+    pub fn set_tag(&mut self, tag_name: &str, tag_value: &str) {
+        match self.tags.iter_mut().find(|(name, _)| name == tag_name) {
+            Some((_, value)) => *value = tag_value.to_string(),
+            None => self.tags.push((tag_name.to_string(), tag_value.to_string()))
+        }
+    }
+
+    // returns false and leaves the game as it was for a move that is not legal here;
+    // a move that was played here before is followed again, with its annotation
+    pub fn play_move(&mut self, board_move: &BoardMove) -> bool {
+        return match self.tree.add_move(self.current_node, board_move) {
+            Some(node_id) => {
+                self.step_forward(node_id);
+                true
+            },
+            None => false
+        };
+    }
+    // takes the last move back, and everything played after it out of the tree
+    pub fn undo_move(&mut self) -> Option<BoardMove> {
+        let node_id = self.current_node;
+        let board_move = self.step_back()?;
+        self.tree.delete_variation(node_id);
+        return Some(board_move);
+    }
+    // takes the last move back but keeps it in the tree to come back to
+    pub fn go_back(&mut self) -> Option<BoardMove> {
+        return self.step_back();
+    }
+    // makes the line through the node the current one; false for a deleted node
+    pub fn go_to(&mut self, node_id: NodeId) -> bool {
+        if !self.tree.contains(node_id) {
+            return false;
+        }
+        let path = self.tree.path_to(node_id);
+        while self.current_node != self.tree.root() && !path.contains(&self.current_node) {
+            self.step_back();
+        }
+        let played_moves = self.moves.len();
+        for path_id in path.into_iter().skip(played_moves) {
+            self.step_forward(path_id);
+        }
+        return true;
+    }
+
+    // a line from the position after the node, kept next to what was played there already
+    pub fn add_variation(&mut self, node_id: NodeId, board_move: &BoardMove) -> Option<NodeId> {
+        return self.tree.add_move(node_id, board_move);
+    }
+    pub fn promote_variation(&mut self, node_id: NodeId) -> bool {
+        return self.tree.promote_variation(node_id);
+    }
+    pub fn promote_to_mainline(&mut self, node_id: NodeId) -> bool {
+        return self.tree.promote_to_mainline(node_id);
+    }
+    // the game goes back to before the node first if the current line runs through it
+    pub fn delete_variation(&mut self, node_id: NodeId) -> bool {
+        let Some(parent_id) = self.tree.get(node_id).and_then(GameNode::parent) else {
+            return false;
+        };
+        if self.tree.path_to(self.current_node).contains(&node_id) {
+            self.go_to(parent_id);
+        }
+        return self.tree.delete_variation(node_id);
+    }
+    pub fn node_annotation_mut(&mut self, node_id: NodeId) -> Option<&mut MoveAnnotation> {
+        return self.tree.annotation_mut(node_id);
+    }
+
+    fn step_forward(&mut self, node_id: NodeId) {
+        let board_move = self.tree.node(node_id).board_move().expect("only the root has no move");
+        let is_zeroing = self.current_board.is_capture(&board_move)
+            || self.current_board.get_piece_at(&board_move.from_square()).map(get_piece_type) == Some(PAWN);
+        let next_board = self.tree.node(node_id).board().clone();
+        let previous_board = std::mem::replace(&mut self.current_board, next_board);
+        if previous_board.active_color() == BLACK {
+            self.fullmove_number += 1;
+        }
+        self.previous_positions.push((previous_board, self.halfmove));
+        self.halfmove = if is_zeroing { 0 } else { self.halfmove.saturating_add(1) };
+        *self.threefold_store.entry(self.current_board.zobrist_key()).or_insert(0) += 1;
+        self.moves.push(board_move);
+        self.current_node = node_id;
+    }
+    fn step_back(&mut self) -> Option<BoardMove> {
+        let board_move = self.moves.pop()?;
+        let (previous_board, previous_halfmove) = self.previous_positions.pop().expect("every move has the position before it");
+        if let Some(position_count) = self.threefold_store.get_mut(&self.current_board.zobrist_key()) {
+            *position_count -= 1;
+        }
+        if previous_board.active_color() == BLACK {
+            self.fullmove_number -= 1;
+        }
+        self.current_board = previous_board;
+        self.halfmove = previous_halfmove;
+        self.current_node = self.tree.node(self.current_node).parent().expect("a node with a move has a parent");
+        return Some(board_move);
+    }
+
+    // whether the rules end the game here, draws by the fifty move rule and threefold repetition included
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if self.current_board.get_valid_moves().is_empty() {
+            if self.current_board.is_in_check() {
+                return Some(GameOutcome::Checkmate(self.current_board.active_color() ^ 1));
+            }
+            return Some(GameOutcome::Stalemate);
+        }
+        if is_insufficient_material(&self.current_board) {
+            return Some(GameOutcome::InsufficientMaterial);
+        }
+        if self.halfmove >= 100 {
+            return Some(GameOutcome::FiftyMoves);
+        }
+        if self.threefold_store.get(&self.current_board.zobrist_key()).is_some_and(|position_count| *position_count >= 3) {
+            return Some(GameOutcome::Repetition);
+        }
+        return None;
+    }
+    // the recorded result, otherwise whatever the rules say if the game is over
+    pub fn adjudicated_result(&self) -> Option<GameResult> {
+        return self.result.or_else(|| self.outcome().map(GameOutcome::result));
+    }
+}
+fn with_counters(board: &Board, halfmove: u8, fullmove_number: u32) -> String {
+    let fen = board.to_fen();
+    let position = fen.rsplitn(3, ' ').nth(2).expect("a fen ends in its two counters");
+    return format!("{} {} {}", position, halfmove, fullmove_number);
+}
+
+// no sequence of legal moves can mate: bare kings, a single minor piece, or bishops all on one colour
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let board_pieces = board.get_pieces();
+    let has_mating_material = (0..2).any(|color| {
+        return !board_pieces.pawns[color].is_empty() || !board_pieces.rooks[color].is_empty() || !board_pieces.queens[color].is_empty();
+    });
+    if has_mating_material {
+        return false;
+    }
+    let knight_count = board_pieces.knights.iter().map(Vec::len).sum::<usize>();
+    let bishops = board_pieces.bishops.iter().flatten().collect::<Vec<&BoardSquare>>();
+    if knight_count + bishops.len() <= 1 {
+        return true;
+    }
+    let square_color = |square: &BoardSquare| (square.x() + square.y()) % 2;
+    return knight_count == 0 && bishops.iter().all(|bishop_square| square_color(bishop_square) == square_color(bishops[0]));
+}
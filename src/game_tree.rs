@@ -0,0 +1,180 @@
+use crate::{
+    board::*,
+    game::MoveAnnotation
+};
+
+// a node's place in its tree; ids of deleted nodes are never handed out again
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct NodeId(usize);
+
+// a position in the tree and the move that led to it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct GameNode {
+    board_move: Option<BoardMove>,
+    board: Board,
+    ply: usize,
+    parent: Option<NodeId>,
+    // the first child continues the line, the rest are variations of it
+    children: Vec<NodeId>,
+    pub annotation: MoveAnnotation
+}
+impl GameNode {
+    // none for the root
+    pub const fn board_move(&self) -> Option<BoardMove> {
+        return self.board_move;
+    }
+    // the position after the move
+    pub const fn board(&self) -> &Board {
+        return &self.board;
+    }
+    // moves from the root
+    pub const fn ply(&self) -> usize {
+        return self.ply;
+    }
+    pub const fn parent(&self) -> Option<NodeId> {
+        return self.parent;
+    }
+    pub fn children(&self) -> &[NodeId] {
+        return &self.children;
+    }
+    pub fn main_child(&self) -> Option<NodeId> {
+        return self.children.first().copied();
+    }
+}
+
+// every line explored from a starting position; nodes live in one vector and point at each other by id,
+// so deleting a variation only leaves gaps behind
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct GameTree {
+    nodes: Vec<Option<GameNode>>
+}
+impl GameTree {
+    pub fn new(starting_board: Board) -> GameTree {
+        return GameTree {
+            nodes: vec![Some(GameNode {
+                board_move: None,
+                board: starting_board,
+                ply: 0,
+                parent: None,
+                children: Vec::new(),
+                annotation: MoveAnnotation::default()
+            })]
+        };
+    }
+    pub const fn root(&self) -> NodeId {
+        return NodeId(0);
+    }
+    // panics on a deleted node, like indexing out of bounds
+    pub fn node(&self, node_id: NodeId) -> &GameNode {
+        return self.get(node_id).expect("the node was deleted");
+    }
+    pub fn get(&self, node_id: NodeId) -> Option<&GameNode> {
+        return self.nodes.get(node_id.0).and_then(Option::as_ref);
+    }
+    pub fn annotation_mut(&mut self, node_id: NodeId) -> Option<&mut MoveAnnotation> {
+        return self.nodes.get_mut(node_id.0).and_then(Option::as_mut).map(|node| &mut node.annotation);
+    }
+    pub fn contains(&self, node_id: NodeId) -> bool {
+        return self.get(node_id).is_some();
+    }
+
+    // the child for the move, made a variation when there is a line already; none if the move is not legal there
+    pub fn add_move(&mut self, parent_id: NodeId, board_move: &BoardMove) -> Option<NodeId> {
+        let parent = self.get(parent_id)?;
+        if let Some(child_id) = parent.children.iter().find(|child_id| self.node(**child_id).board_move == Some(*board_move)) {
+            return Some(*child_id);
+        }
+        if !parent.board.get_valid_moves().contains(board_move) {
+            return None;
+        }
+        let child = GameNode {
+            board_move: Some(*board_move),
+            board: parent.board.create_board_from_move(board_move),
+            ply: parent.ply + 1,
+            parent: Some(parent_id),
+            children: Vec::new(),
+            annotation: MoveAnnotation::default()
+        };
+        let child_id = NodeId(self.nodes.len());
+        self.nodes.push(Some(child));
+        self.nodes[parent_id.0].as_mut().expect("the parent was found above").children.push(child_id);
+        return Some(child_id);
+    }
+    // makes the variation the line its parent continues with; false for the root or a deleted node
+    pub fn promote_variation(&mut self, node_id: NodeId) -> bool {
+        let Some(parent_id) = self.get(node_id).and_then(GameNode::parent) else {
+            return false;
+        };
+        let siblings = &mut self.nodes[parent_id.0].as_mut().expect("a parent outlives its children").children;
+        let node_index = siblings.iter().position(|sibling_id| *sibling_id == node_id).expect("a parent knows its children");
+        siblings[..=node_index].rotate_right(1);
+        return true;
+    }
+    // promotes the node and everything before it, so it ends up on the mainline
+    pub fn promote_to_mainline(&mut self, node_id: NodeId) -> bool {
+        if !self.contains(node_id) {
+            return false;
+        }
+        let mut current_id = node_id;
+        while self.promote_variation(current_id) {
+            current_id = self.node(current_id).parent.expect("only nodes with a parent get promoted");
+        }
+        return true;
+    }
+    // removes the node and every line after it; the root stays
+    pub fn delete_variation(&mut self, node_id: NodeId) -> bool {
+        let Some(parent_id) = self.get(node_id).and_then(GameNode::parent) else {
+            return false;
+        };
+        self.nodes[parent_id.0].as_mut().expect("a parent outlives its children").children.retain(|child_id| *child_id != node_id);
+        let mut deleted_ids = vec![node_id];
+        while let Some(deleted_id) = deleted_ids.pop() {
+            if let Some(deleted_node) = self.nodes[deleted_id.0].take() {
+                deleted_ids.extend(deleted_node.children);
+            }
+        }
+        return true;
+    }
+
+    // the moves played after the root along first children
+    pub fn mainline(&self) -> Mainline<'_> {
+        return self.line_from(self.root());
+    }
+    // the nodes after this one along first children
+    pub fn line_from(&self, node_id: NodeId) -> Mainline<'_> {
+        return Mainline {
+            tree: self,
+            node_id: self.get(node_id).and_then(GameNode::main_child)
+        };
+    }
+    // the nodes from just after the root down to this one
+    pub fn path_to(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut path = Vec::new();
+        let mut current_id = node_id;
+        while let Some(parent_id) = self.get(current_id).and_then(GameNode::parent) {
+            path.push(current_id);
+            current_id = parent_id;
+        }
+        path.reverse();
+        return path;
+    }
+    pub fn is_on_mainline(&self, node_id: NodeId) -> bool {
+        return self.path_to(node_id).into_iter().all(|path_id| {
+            let parent_id = self.node(path_id).parent.expect("the path starts after the root");
+            return self.node(parent_id).main_child() == Some(path_id);
+        });
+    }
+}
+
+pub struct Mainline<'a> {
+    tree: &'a GameTree,
+    node_id: Option<NodeId>
+}
+impl Iterator for Mainline<'_> {
+    type Item = NodeId;
+    fn next(&mut self) -> Option<NodeId> {
+        let node_id = self.node_id?;
+        self.node_id = self.tree.node(node_id).main_child();
+        return Some(node_id);
+    }
+}
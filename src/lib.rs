@@ -1 +1,27 @@
-pub mod board;
\ No newline at end of file
+#![allow(clippy::needless_return)]
+
+pub mod bench;
+pub mod board;
+pub mod book;
+pub mod book_keys;
+pub mod endgame;
+pub mod engine_match;
+pub mod epd;
+pub mod eval;
+pub mod eval_params;
+pub mod game;
+pub mod game_tree;
+pub mod nnue;
+pub mod params;
+pub mod pawns;
+pub mod pgn;
+pub mod play;
+pub mod score;
+pub mod search;
+pub mod syzygy;
+pub mod tablebase;
+pub mod time;
+pub mod tt;
+pub mod tune;
+pub mod uci;
+pub mod zobrist;
@@ -1,5 +1,405 @@
-use apotheosis::board::Board;
+#![allow(clippy::needless_return)]
+
+use apotheosis::{
+    bench::*,
+    board::*,
+    book::*,
+    engine_match::*,
+    epd::*,
+    pgn::*,
+    play::*,
+    search::*,
+    uci
+};
+use std::{
+    env,
+    fs::File,
+    io::Write,
+    process,
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
 fn main() {
-    let bb = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-    println!("{}\n", bb);
+    let args = env::args().collect::<Vec<String>>();
+    match args.get(1).map(|command| command.as_str()) {
+        None | Some("uci") => uci::run_uci_loop().expect("stdin or stdout closed unexpectedly"),
+        Some("book") if args.get(2).map(|command| command.as_str()) == Some("build") => build_book(&args[3..]),
+        Some("testsuite") => run_test_suite(&args[2..]),
+        Some("match") => run_engine_match(&args[2..]),
+        Some("bench") => run_bench_command(&args[2..]),
+        Some("play") => play_against_engine(&args[2..]),
+        Some(_) => {
+            eprintln!("usage: {} [command]", args[0]);
+            eprintln!("commands:");
+            eprintln!("    uci                 talk uci over stdin and stdout, the default");
+            eprintln!("    book build          make a polyglot book out of pgn files");
+            eprintln!("    testsuite           search the positions of an epd suite and score the answers");
+            eprintln!("    match               play two engines against each other and work out the elo difference");
+            eprintln!("    bench               search a fixed set of positions and print the node count");
+            eprintln!("    play                play a game against the engine in the terminal");
+            process::exit(2);
+        }
+    }
+}
+
+// the value after each --option, the rest in order
+fn split_options<'a>(args: &'a [String], option_names: &[&str]) -> (Vec<(&'a str, &'a str)>, Vec<&'a str>) {
+    let mut options = Vec::new();
+    let mut positional_args = Vec::new();
+    let mut arg_iter = args.iter();
+    while let Some(arg) = arg_iter.next() {
+        match arg.strip_prefix("--") {
+            Some(option_name) if option_names.contains(&option_name) => match arg_iter.next() {
+                Some(option_value) => options.push((option_name, option_value.as_str())),
+                None => {
+                    eprintln!("--{} needs a value", option_name);
+                    process::exit(2);
+                }
+            },
+            Some(option_name) => {
+                eprintln!("unknown option --{}", option_name);
+                process::exit(2);
+            },
+            None => positional_args.push(arg.as_str())
+        }
+    }
+    return (options, positional_args);
+}
+fn parse_option<T: std::str::FromStr>(option_name: &str, option_value: &str) -> T {
+    return option_value.parse().unwrap_or_else(|_| {
+        eprintln!("--{} cannot be {}", option_name, option_value);
+        process::exit(2);
+    });
+}
+// negative, infinite or nan seconds would panic in Duration::from_secs_f64
+fn parse_seconds_option(option_name: &str, option_value: &str) -> Duration {
+    return Duration::try_from_secs_f64(parse_option(option_name, option_value)).unwrap_or_else(|_| {
+        eprintln!("--{} cannot be {}", option_name, option_value);
+        process::exit(2);
+    });
+}
+
+fn build_book(args: &[String]) {
+    let (options, positional_args) = split_options(args, &["max-ply", "min-games", "min-score", "keys"]);
+    if positional_args.len() < 2 {
+        eprintln!("usage: book build <output book> <pgn file>... [--max-ply N] [--min-games N] [--min-score S] [--keys FILE]");
+        eprintln!("moves are counted up to --max-ply plies into each game (default 20), and kept when they were played");
+        eprintln!("in at least --min-games games (default 1) and scored at least --min-score from 0 to 1 (default 0)");
+        eprintln!("positions are keyed with polyglot's Random64 table unless --keys names a file with other keys");
+        process::exit(2);
+    }
+    let mut build_options = BookBuildOptions::default();
+    let mut keys = PolyglotKeys::default();
+    for (option_name, option_value) in options {
+        match option_name {
+            "max-ply" => build_options.max_ply = parse_option(option_name, option_value),
+            "min-games" => build_options.min_games = parse_option(option_name, option_value),
+            "min-score" => build_options.min_score = parse_option(option_name, option_value),
+            _ => keys = PolyglotKeys::load(option_value).unwrap_or_else(|error| {
+                eprintln!("cannot load the keys in {}: {}", option_value, error);
+                process::exit(1);
+            })
+        }
+    }
+
+    let mut book_builder = BookBuilder::new(keys, build_options);
+    let mut skipped_games = 0;
+    for pgn_path in positional_args[1..].iter() {
+        let pgn_reader = PgnReader::open(pgn_path).unwrap_or_else(|error| {
+            eprintln!("cannot open {}: {}", pgn_path, error);
+            process::exit(1);
+        });
+        for game in pgn_reader {
+            match game {
+                Ok(game) => {
+                    if !book_builder.add_game(&game) {
+                        skipped_games += 1;
+                    }
+                },
+                Err(error) => {
+                    eprintln!("{}: {}", pgn_path, error);
+                    skipped_games += 1;
+                }
+            }
+        }
+    }
+    let book = book_builder.build();
+    if let Err(error) = book.save(positional_args[0]) {
+        eprintln!("cannot write {}: {}", positional_args[0], error);
+        process::exit(1);
+    }
+    println!(
+        "wrote {} moves from {} games to {}, skipped {} games without a result or that could not be read",
+        book.entries().len(),
+        book_builder.game_count(),
+        positional_args[0],
+        skipped_games
+    );
+}
+
+fn run_test_suite(args: &[String]) {
+    let (options, positional_args) = split_options(args, &["depth", "movetime", "threads", "hash"]);
+    if positional_args.len() != 1 {
+        eprintln!("usage: testsuite <epd file> [--depth N] [--movetime MS] [--threads N] [--hash MB]");
+        eprintln!("each position is searched with a fresh hash for --movetime milliseconds (default 1000),");
+        eprintln!("or to --depth plies instead when that is given");
+        process::exit(2);
+    }
+    let mut limits = SearchLimits::default();
+    let mut searcher = Searcher::new();
+    for (option_name, option_value) in options {
+        match option_name {
+            "depth" => limits.depth = Some(parse_option(option_name, option_value)),
+            "movetime" => limits.move_time = Some(Duration::from_millis(parse_option(option_name, option_value))),
+            "threads" => searcher.threads = parse_option::<usize>(option_name, option_value).max(1),
+            _ => searcher.set_hash_size(parse_option(option_name, option_value))
+        }
+    }
+    if limits.depth.is_none() && limits.move_time.is_none() {
+        limits.move_time = Some(Duration::from_millis(1000));
+    }
+    let positions = load_epd(positional_args[0]).unwrap_or_else(|error| {
+        eprintln!("cannot read {}: {}", positional_args[0], error);
+        process::exit(1);
+    });
+
+    let mut solved_count = 0;
+    let mut scored_count = 0;
+    let mut total_nodes = 0;
+    let mut total_elapsed = Duration::ZERO;
+    for (position_index, position) in positions.iter().enumerate() {
+        let position_name = position.id().map_or_else(|| format!("#{}", position_index + 1), str::to_string);
+        let outcome = match run_epd_position(&mut searcher, position, &limits) {
+            Ok(outcome) => outcome,
+            Err(message) => {
+                println!("{:<16} skipped, {}", position_name, message);
+                continue;
+            }
+        };
+        total_nodes += outcome.nodes;
+        total_elapsed += outcome.elapsed;
+        let found_move = outcome.best_move.map_or("none".to_string(), |best_move| best_move.to_san(&position.board));
+        let expected = ["bm", "am", "dm"].iter().filter_map(|opcode| {
+            return position.operands(opcode).map(|operands| format!("{} {}", opcode, operands.join(" ")));
+        }).collect::<Vec<String>>();
+        let expected = if expected.is_empty() { "-".to_string() } else { expected.join(", ") };
+        let verdict = match outcome.solved {
+            Some(true) => "solved",
+            Some(false) => "failed",
+            None => "unscored"
+        };
+        if let Some(solved) = outcome.solved {
+            scored_count += 1;
+            solved_count += usize::from(solved);
+        }
+        println!(
+            "{:<16} {:<8} {:<8} {:>7} depth {:<3} {:>10} nodes  expected {}",
+            position_name, verdict, found_move, outcome.score.to_string(), outcome.depth, outcome.nodes, expected
+        );
+    }
+    let nps = (total_nodes as f64 / total_elapsed.as_secs_f64().max(0.001)) as u64;
+    println!("solved {} of {} positions, {} nodes in {:.1}s, {} nps", solved_count, scored_count, total_nodes, total_elapsed.as_secs_f64(), nps);
+}
+
+// "self" for this engine or the path of a uci program, either with :Name=value,Name=value for uci options
+fn start_match_engine(engine_spec: &str) -> MatchEngine {
+    let (engine_name, options) = match engine_spec.rsplit_once(':') {
+        Some((engine_name, option_list)) if option_list.contains('=') => {
+            let options = option_list.split(',').map(|option| {
+                let (option_name, option_value) = option.split_once('=').unwrap_or((option, ""));
+                return (option_name.to_string(), option_value.to_string());
+            }).collect::<Vec<(String, String)>>();
+            (engine_name, options)
+        },
+        _ => (engine_spec, Vec::new())
+    };
+    let match_engine = if engine_name == "self" { MatchEngine::internal(&options) } else { MatchEngine::spawn(engine_name, &options) };
+    return match_engine.unwrap_or_else(|error| {
+        eprintln!("cannot start {}: {}", engine_name, error);
+        process::exit(1);
+    });
+}
+fn run_engine_match(args: &[String]) {
+    let (options, positional_args) = split_options(args, &["games", "openings", "pgn", "depth", "nodes", "movetime", "tc", "sprt"]);
+    if positional_args.len() != 2 {
+        eprintln!("usage: match <engine> <engine> [--games N] [--openings FILE] [--pgn FILE]");
+        eprintln!("             [--depth N | --nodes N | --movetime MS | --tc SECONDS+INCREMENT] [--sprt ELO0,ELO1[,ALPHA,BETA]]");
+        eprintln!("an engine is self or the path of a uci program, followed by :Name=value,... to set uci options");
+        eprintln!("each opening fen in the file is played twice with colors swapped, --games defaults to both colors of every opening");
+        eprintln!("moves get --movetime 100 unless another limit is given, and --sprt stops as soon as the test decides");
+        process::exit(2);
+    }
+    let mut match_options = MatchOptions {
+        games: 0,
+        openings: Vec::new(),
+        limits: MatchLimits::MoveTime(Duration::from_millis(100)),
+        sprt: None
+    };
+    let mut pgn_file = None;
+    for (option_name, option_value) in options {
+        match option_name {
+            "games" => match_options.games = parse_option(option_name, option_value),
+            "openings" => match_options.openings = load_openings(option_value).unwrap_or_else(|error| {
+                eprintln!("cannot read the openings in {}: {}", option_value, error);
+                process::exit(1);
+            }),
+            "pgn" => pgn_file = Some(File::create(option_value).unwrap_or_else(|error| {
+                eprintln!("cannot create {}: {}", option_value, error);
+                process::exit(1);
+            })),
+            "depth" => match_options.limits = MatchLimits::Depth(parse_option(option_name, option_value)),
+            "nodes" => match_options.limits = MatchLimits::Nodes(parse_option(option_name, option_value)),
+            "movetime" => match_options.limits = MatchLimits::MoveTime(Duration::from_millis(parse_option(option_name, option_value))),
+            "tc" => {
+                let (base, increment) = option_value.split_once('+').unwrap_or((option_value, "0"));
+                match_options.limits = MatchLimits::Clock {
+                    base: parse_seconds_option(option_name, base),
+                    increment: parse_seconds_option(option_name, increment)
+                };
+            },
+            _ => {
+                let sprt_values = option_value.split(',').map(|sprt_value| parse_option::<f64>(option_name, sprt_value)).collect::<Vec<f64>>();
+                let mut sprt = Sprt::default();
+                match sprt_values[..] {
+                    [elo0, elo1] => (sprt.elo0, sprt.elo1) = (elo0, elo1),
+                    [elo0, elo1, alpha, beta] => sprt = Sprt { elo0, elo1, alpha, beta },
+                    _ => {
+                        eprintln!("--sprt takes elo0,elo1 or elo0,elo1,alpha,beta");
+                        process::exit(2);
+                    }
+                }
+                match_options.sprt = Some(sprt);
+            }
+        }
+    }
+    if match_options.games == 0 {
+        match_options.games = 2 * match_options.openings.len().max(1) as u32;
+    }
+
+    let mut first_engine = start_match_engine(positional_args[0]);
+    let mut second_engine = start_match_engine(positional_args[1]);
+    println!("{} against {}, {} games", first_engine.name, second_engine.name, match_options.games);
+    let format_elo = |match_score: &MatchScore| match (match_score.elo(), match_score.elo_interval()) {
+        (Some(elo), Some((lower_elo, upper_elo))) => format!("elo {:+.1} +/- {:.1}", elo, (upper_elo - lower_elo) / 2.0),
+        _ => "elo unknown".to_string()
+    };
+    let sprt = match_options.sprt;
+    let match_result = run_match([&mut first_engine, &mut second_engine], &match_options, |game, match_score| {
+        if let Some(pgn_file) = pgn_file.as_mut() {
+            pgn_file.write_all(game_to_pgn(game).as_bytes())?;
+        }
+        let termination = game.tag("Termination").map_or_else(|| game.outcome().map_or("adjudicated".to_string(), |outcome| outcome.to_string()), str::to_string);
+        let mut report = format!(
+            "game {}: {} - {} {} ({}), score {}-{}-{}, {}",
+            match_score.games(),
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            game.result.map_or("*", |result| result.to_pgn()),
+            termination,
+            match_score.wins,
+            match_score.losses,
+            match_score.draws,
+            format_elo(match_score)
+        );
+        if let Some(sprt) = sprt {
+            let (lower_bound, upper_bound) = sprt.bounds();
+            report.push_str(&format!(", llr {:.2} ({:.2}, {:.2})", sprt.llr(match_score), lower_bound, upper_bound));
+        }
+        println!("{}", report);
+        return Ok(());
+    });
+    let match_score = match_result.unwrap_or_else(|error| {
+        eprintln!("the match stopped: {}", error);
+        process::exit(1);
+    });
+    println!(
+        "{} against {}: {} wins, {} losses, {} draws, {}",
+        first_engine.name,
+        second_engine.name,
+        match_score.wins,
+        match_score.losses,
+        match_score.draws,
+        format_elo(&match_score)
+    );
+    if let Some(sprt) = sprt {
+        let verdict = match sprt.status(&match_score) {
+            SprtStatus::AcceptH0 => format!("H0 accepted, at most {} elo better", sprt.elo0),
+            SprtStatus::AcceptH1 => format!("H1 accepted, at least {} elo better", sprt.elo1),
+            SprtStatus::Continue => "no decision yet".to_string()
+        };
+        println!("sprt [{}, {}]: {}", sprt.elo0, sprt.elo1, verdict);
+    }
+}
+
+fn run_bench_command(args: &[String]) {
+    let depth = match args {
+        [] => BENCH_DEPTH,
+        [depth] => parse_option("depth", depth),
+        _ => {
+            eprintln!("usage: bench [depth]");
+            eprintln!("searches {} positions to the depth (default {}) with one thread and a fresh hash each,", BENCH_FENS.len(), BENCH_DEPTH);
+            eprintln!("the node count stays the same until the search or evaluation changes");
+            process::exit(2);
+        }
+    };
+    let bench_result = run_bench(depth, |position_index, fen, result| {
+        println!("position {:>2}/{}: {:>9} nodes  {}", position_index + 1, BENCH_FENS.len(), result.nodes, fen);
+    });
+    println!("===========================");
+    println!("total time (ms) : {}", bench_result.elapsed.as_millis());
+    println!("nodes searched  : {}", bench_result.nodes);
+    println!("nodes/second    : {}", bench_result.nodes_per_second());
+}
+
+fn play_against_engine(args: &[String]) {
+    let (options, positional_args) = split_options(args, &["color", "tc", "depth", "fen", "pgn"]);
+    if !positional_args.is_empty() {
+        eprintln!("usage: play [--color white|black|random] [--tc SECONDS+INCREMENT] [--depth N] [--fen FEN] [--pgn FILE]");
+        eprintln!("the clocks start at 300+3 unless --tc says otherwise, and --depth makes the engine weaker and ignore its clock");
+        eprintln!("the finished game gets written to --pgn when it is given");
+        process::exit(2);
+    }
+    let mut play_options = PlayOptions::default();
+    let mut pgn_path = None;
+    for (option_name, option_value) in options {
+        match option_name {
+            "color" => play_options.human_color = match option_value {
+                "white" => WHITE,
+                "black" => BLACK,
+                "random" => (SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.subsec_nanos()) % 2) as BoardColor,
+                _ => {
+                    eprintln!("--color is white, black or random");
+                    process::exit(2);
+                }
+            },
+            "tc" => {
+                let (base, increment) = option_value.split_once('+').unwrap_or((option_value, "0"));
+                play_options.base_time = parse_seconds_option(option_name, base);
+                play_options.increment = parse_seconds_option(option_name, increment);
+            },
+            "depth" => play_options.engine_depth = Some(parse_option(option_name, option_value)),
+            "fen" => {
+                if !is_valid_fen(option_value) {
+                    eprintln!("{} is not a position", option_value);
+                    process::exit(2);
+                }
+                play_options.starting_fen = option_value.to_string();
+            },
+            _ => pgn_path = Some(option_value)
+        }
+    }
+    let stdin = std::io::stdin();
+    let game = PlaySession::new(stdin.lock(), std::io::stdout(), play_options).play().unwrap_or_else(|error| {
+        eprintln!("the game stopped: {}", error);
+        process::exit(1);
+    });
+    if let Some(pgn_path) = pgn_path {
+        if let Err(error) = std::fs::write(pgn_path, game_to_pgn(&game)) {
+            eprintln!("cannot write {}: {}", pgn_path, error);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,225 @@
+use crate::{
+    board::*,
+    eval::piece_index
+};
+use std::{
+    fs,
+    io,
+    path::Path
+};
+
+/*
+network file format, every number little endian:
+    offset  size                          field
+    0       4                             magic, the bytes "APNN"
+    4       4                             version, u32, currently 1
+    8       4                             king buckets, u32, 1 ignores where the king is and 64 gives every king square its own weights
+    12      4                             hidden size, u32, the width of each perspective's accumulator
+    16      2 * 768 * buckets * hidden    feature weights, i16, feature by feature with hidden weights each
+    ...     2 * hidden                    feature biases, i16
+    ...     2 * hidden                    output weights, i8, for the side to move's accumulator and then the other side's
+    ...     4                             output bias, i32
+
+features are halfka style, seen from each side in turn with its own pieces first and its own king on the bottom:
+    feature = king bucket * 768 + (relative color * 6 + piece index) * 64 + square
+    relative color is 0 for that side's pieces and 1 for the enemy's, squares are flipped vertically for black
+
+inference is quantised, the feature transformer is in units of 1/FEATURE_SCALE and the output weights in units of 1/OUTPUT_SCALE:
+    hidden = clamp(accumulator, 0, FEATURE_SCALE) for both perspectives, side to move first
+    score = (output bias + sum of hidden * output weights) * EVAL_SCALE / (FEATURE_SCALE * OUTPUT_SCALE)
+*/
+pub const NETWORK_MAGIC: [u8; 4] = *b"APNN";
+pub const NETWORK_VERSION: u32 = 1;
+pub const FEATURE_SCALE: i32 = 255;
+pub const OUTPUT_SCALE: i32 = 64;
+// a network output of 1.0 is worth this many centipawns
+pub const EVAL_SCALE: i32 = 400;
+// six piece types of two relative colors on 64 squares, for every king bucket
+const FEATURES_PER_BUCKET: usize = 2 * 6 * 64;
+const NETWORK_HEADER_SIZE: usize = 16;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Network {
+    king_buckets: usize,
+    hidden_size: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i8>,
+    output_bias: i32
+}
+
+// the first layer's sums for both perspectives, indexed by color
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2]
+}
+
+fn invalid_network(message: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+}
+
+impl Network {
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Network> {
+        if bytes.len() < NETWORK_HEADER_SIZE || bytes[0..4] != NETWORK_MAGIC {
+            return Err(invalid_network("not a network file"));
+        }
+        let read_u32 = |offset: usize| u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+        if read_u32(4) != NETWORK_VERSION {
+            return Err(invalid_network("unsupported network version"));
+        }
+        let king_buckets = read_u32(8) as usize;
+        let hidden_size = read_u32(12) as usize;
+        if king_buckets != 1 && king_buckets != 64 {
+            return Err(invalid_network("king buckets have to be 1 or 64"));
+        }
+        if hidden_size == 0 {
+            return Err(invalid_network("hidden size cannot be 0"));
+        }
+        let feature_weight_count = king_buckets * FEATURES_PER_BUCKET * hidden_size;
+        let expected_size = NETWORK_HEADER_SIZE + feature_weight_count * 2 + hidden_size * 2 + hidden_size * 2 + 4;
+        if bytes.len() != expected_size {
+            return Err(invalid_network("network file size does not match its header"));
+        }
+
+        let read_i16s = |offset: usize, count: usize| {
+            return bytes[offset..offset + count * 2].chunks_exact(2).map(|value_bytes| i16::from_le_bytes([value_bytes[0], value_bytes[1]])).collect::<Vec<i16>>();
+        };
+        let feature_biases_offset = NETWORK_HEADER_SIZE + feature_weight_count * 2;
+        let output_weights_offset = feature_biases_offset + hidden_size * 2;
+        let output_bias_offset = output_weights_offset + hidden_size * 2;
+        let feature_weights = read_i16s(NETWORK_HEADER_SIZE, feature_weight_count);
+        let feature_biases = read_i16s(feature_biases_offset, hidden_size);
+        let output_weights = bytes[output_weights_offset..output_bias_offset].iter().map(|weight| *weight as i8).collect::<Vec<i8>>();
+        let output_bias = read_u32(output_bias_offset) as i32;
+        return Ok(Network {
+            king_buckets,
+            hidden_size,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias
+        });
+    }
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Network> {
+        return Network::from_bytes(&fs::read(path)?);
+    }
+    pub const fn hidden_size(&self) -> usize {
+        return self.hidden_size;
+    }
+    fn feature_index(&self, perspective: BoardColor, king_square: &BoardSquare, piece: BoardPiece, square: &BoardSquare) -> usize {
+        // every side sees the board as if it were white
+        let orient = |square: &BoardSquare| if perspective == WHITE { square.pos() } else { square.pos() ^ 0b111000 } as usize;
+        let king_bucket = if self.king_buckets == 1 { 0 } else { orient(king_square) };
+        let relative_color = (get_piece_color(piece) != perspective) as usize;
+        return king_bucket * FEATURES_PER_BUCKET + (relative_color * 6 + piece_index(get_piece_type(piece))) * 64 + orient(square);
+    }
+    fn add_feature(&self, values: &mut [i16], feature_index: usize) {
+        let feature_weights = &self.feature_weights[feature_index * self.hidden_size..(feature_index + 1) * self.hidden_size];
+        for (value, weight) in values.iter_mut().zip(feature_weights) {
+            *value = value.wrapping_add(*weight);
+        }
+    }
+    fn remove_feature(&self, values: &mut [i16], feature_index: usize) {
+        let feature_weights = &self.feature_weights[feature_index * self.hidden_size..(feature_index + 1) * self.hidden_size];
+        for (value, weight) in values.iter_mut().zip(feature_weights) {
+            *value = value.wrapping_sub(*weight);
+        }
+    }
+    fn refresh_perspective(&self, accumulator: &mut Accumulator, piece_grid: &[Option<BoardPiece>; 64], perspective: BoardColor) {
+        let values = &mut accumulator.values[perspective as usize];
+        values.clear();
+        values.extend_from_slice(&self.feature_biases);
+        let king_square = match (0..64u8).find(|square_pos| piece_grid[*square_pos as usize] == Some(perspective | KING)) {
+            Some(king_square_pos) => BoardSquare(king_square_pos),
+            None => return
+        };
+        for (square_pos, possible_piece) in piece_grid.iter().enumerate() {
+            if let Some(piece) = possible_piece {
+                self.add_feature(values, self.feature_index(perspective, &king_square, *piece, &BoardSquare(square_pos as u8)));
+            }
+        }
+    }
+    // builds the accumulator from scratch
+    pub fn new_accumulator(&self, board: &Board) -> Accumulator {
+        let mut accumulator = Accumulator::default();
+        self.refresh(&mut accumulator, board);
+        return accumulator;
+    }
+    pub fn refresh(&self, accumulator: &mut Accumulator, board: &Board) {
+        let piece_grid = board.get_piece_grid();
+        for perspective in [BLACK, WHITE] {
+            self.refresh_perspective(accumulator, &piece_grid, perspective);
+        }
+    }
+    // moves the accumulator of a position over to one a move later, only touching the squares that changed;
+    // a side whose king moved gets refreshed instead when the king square picks the weights
+    pub fn update(&self, previous_accumulator: &Accumulator, previous_board: &Board, accumulator: &mut Accumulator, board: &Board) {
+        let previous_piece_grid = previous_board.get_piece_grid();
+        let piece_grid = board.get_piece_grid();
+        for perspective in [BLACK, WHITE] {
+            let king_square_pos = (0..64u8).find(|square_pos| piece_grid[*square_pos as usize] == Some(perspective | KING));
+            let previous_king_square_pos = (0..64u8).find(|square_pos| previous_piece_grid[*square_pos as usize] == Some(perspective | KING));
+            let has_king_moved = king_square_pos != previous_king_square_pos;
+            let king_square = match king_square_pos {
+                Some(king_square_pos) if self.king_buckets == 1 || !has_king_moved => BoardSquare(king_square_pos),
+                _ => {
+                    self.refresh_perspective(accumulator, &piece_grid, perspective);
+                    continue;
+                }
+            };
+            let values = &mut accumulator.values[perspective as usize];
+            values.clear();
+            values.extend_from_slice(&previous_accumulator.values[perspective as usize]);
+            for square_pos in 0..64 {
+                if previous_piece_grid[square_pos] == piece_grid[square_pos] {
+                    continue;
+                }
+                let square = BoardSquare(square_pos as u8);
+                if let Some(previous_piece) = previous_piece_grid[square_pos] {
+                    self.remove_feature(values, self.feature_index(perspective, &king_square, previous_piece, &square));
+                }
+                if let Some(piece) = piece_grid[square_pos] {
+                    self.add_feature(values, self.feature_index(perspective, &king_square, piece, &square));
+                }
+            }
+        }
+    }
+    // in centipawns, from the side to move's point of view
+    pub fn evaluate(&self, accumulator: &Accumulator, active_color: BoardColor) -> i32 {
+        let mut output = self.output_bias as i64;
+        let perspectives = [active_color, active_color ^ 1];
+        for (perspective_index, perspective) in perspectives.iter().enumerate() {
+            let output_weights = &self.output_weights[perspective_index * self.hidden_size..(perspective_index + 1) * self.hidden_size];
+            for (value, weight) in accumulator.values[*perspective as usize].iter().zip(output_weights) {
+                output += (*value as i32).clamp(0, FEATURE_SCALE) as i64 * *weight as i64;
+            }
+        }
+        return (output * EVAL_SCALE as i64 / (FEATURE_SCALE * OUTPUT_SCALE) as i64) as i32;
+    }
+    pub fn evaluate_board(&self, board: &Board) -> i32 {
+        return self.evaluate(&self.new_accumulator(board), board.active_color());
+    }
+    // the same network in floating point without any quantisation, to check the quantised inference against
+    pub fn evaluate_reference(&self, board: &Board) -> f64 {
+        let piece_grid = board.get_piece_grid();
+        let active_color = board.active_color();
+        let mut output = self.output_bias as f64 / (FEATURE_SCALE * OUTPUT_SCALE) as f64;
+        for (perspective_index, perspective) in [active_color, active_color ^ 1].iter().enumerate() {
+            let mut hidden = self.feature_biases.iter().map(|bias| *bias as f64 / FEATURE_SCALE as f64).collect::<Vec<f64>>();
+            if let Some(king_square_pos) = (0..64u8).find(|square_pos| piece_grid[*square_pos as usize] == Some(*perspective | KING)) {
+                for (square_pos, possible_piece) in piece_grid.iter().enumerate() {
+                    if let Some(piece) = possible_piece {
+                        let feature_index = self.feature_index(*perspective, &BoardSquare(king_square_pos), *piece, &BoardSquare(square_pos as u8));
+                        for (hidden_index, hidden_value) in hidden.iter_mut().enumerate() {
+                            *hidden_value += self.feature_weights[feature_index * self.hidden_size + hidden_index] as f64 / FEATURE_SCALE as f64;
+                        }
+                    }
+                }
+            }
+            for (hidden_index, hidden_value) in hidden.iter().enumerate() {
+                output += hidden_value.clamp(0.0, 1.0) * self.output_weights[perspective_index * self.hidden_size + hidden_index] as f64 / OUTPUT_SCALE as f64;
+            }
+        }
+        return output * EVAL_SCALE as f64;
+    }
+}
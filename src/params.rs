@@ -0,0 +1,113 @@
+use crate::eval::TaperedScore;
+
+// every tunable evaluation weight lives in this one vector, so the tuner can treat them all alike;
+// a tapered weight takes two slots, its middlegame value followed by its endgame value
+pub type EvalParams = [i32; PARAM_COUNT];
+
+// where each group of weights starts in the vector
+pub const PIECE_VALUES: usize = 0;
+// six tables of 64 tapered weights, laid out the way the board is printed from white's point of view
+pub const PIECE_SQUARE_TABLES: usize = PIECE_VALUES + 6 * 2;
+pub const MOBILITY_WEIGHTS: usize = PIECE_SQUARE_TABLES + 6 * 64 * 2;
+pub const CENTER_CONTROL_WEIGHT: usize = MOBILITY_WEIGHTS + 6 * 2;
+pub const SPACE_WEIGHT: usize = CENTER_CONTROL_WEIGHT + 2;
+pub const OUTPOST_BONUSES: usize = SPACE_WEIGHT + 2;
+pub const DOUBLED_PENALTY: usize = OUTPOST_BONUSES + 2 * 2;
+pub const ISOLATED_PENALTY: usize = DOUBLED_PENALTY + 2;
+pub const BACKWARD_PENALTY: usize = ISOLATED_PENALTY + 2;
+// these are indexed by relative rank, the first rank at index 0
+pub const CONNECTED_BONUSES: usize = BACKWARD_PENALTY + 2;
+pub const PASSED_BONUSES: usize = CONNECTED_BONUSES + 8 * 2;
+pub const CANDIDATE_BONUSES: usize = PASSED_BONUSES + 8 * 2;
+pub const PASSED_BLOCKED_PENALTIES: usize = CANDIDATE_BONUSES + 8 * 2;
+pub const PASSED_FREE_PATH_BONUSES: usize = PASSED_BLOCKED_PENALTIES + 8 * 2;
+pub const PASSED_CONTROLLED_STOP_BONUSES: usize = PASSED_FREE_PATH_BONUSES + 8 * 2;
+// king attack units are not tapered, the danger they add up to only counts in the middlegame
+pub const KING_ATTACK_WEIGHTS: usize = PASSED_CONTROLLED_STOP_BONUSES + 8 * 2;
+pub const WEAK_KING_ZONE_WEIGHT: usize = KING_ATTACK_WEIGHTS + 6;
+pub const SAFE_CHECK_WEIGHTS: usize = WEAK_KING_ZONE_WEIGHT + 1;
+// attack units get squared and divided by the divisor, up to the max king danger
+pub const KING_DANGER_DIVISOR: usize = SAFE_CHECK_WEIGHTS + 6;
+pub const MAX_KING_DANGER: usize = KING_DANGER_DIVISOR + 1;
+pub const PAWN_SHIELD_BONUSES: usize = MAX_KING_DANGER + 1;
+pub const MISSING_SHIELD_PENALTY: usize = PAWN_SHIELD_BONUSES + 3 * 2;
+pub const PAWN_STORM_PENALTIES: usize = MISSING_SHIELD_PENALTY + 2;
+pub const HALF_OPEN_KING_FILE_PENALTY: usize = PAWN_STORM_PENALTIES + 5 * 2;
+pub const OPEN_KING_FILE_PENALTY: usize = HALF_OPEN_KING_FILE_PENALTY + 2;
+// how much each piece type but the king counts towards the game phase, not tapered since they make the taper
+pub const PHASE_WEIGHTS: usize = OPEN_KING_FILE_PENALTY + 2;
+// the specialised endgames are not tapered either, there is no middlegame left by then;
+// the known win bonus goes on top of the material, so a won ending is worth heading for but still well below any mate score
+pub const KNOWN_WIN_BONUS: usize = PHASE_WEIGHTS + 5;
+// per step the losing king is from the centre, and per step the winning king closes in
+pub const PUSH_TO_EDGE_WEIGHT: usize = KNOWN_WIN_BONUS + 1;
+pub const KING_PROXIMITY_WEIGHT: usize = PUSH_TO_EDGE_WEIGHT + 1;
+// per step the losing king is closer to a corner the bishop can cover
+pub const PUSH_TO_CORNER_WEIGHT: usize = KING_PROXIMITY_WEIGHT + 1;
+// per rank an unstoppable pawn has already advanced
+pub const UNSTOPPABLE_PAWN_RANK_WEIGHT: usize = PUSH_TO_CORNER_WEIGHT + 1;
+// out of the full endgame scale
+pub const OPPOSITE_BISHOPS_SCALE: usize = UNSTOPPABLE_PAWN_RANK_WEIGHT + 1;
+pub const OPPOSITE_BISHOPS_WITH_PIECES_SCALE: usize = OPPOSITE_BISHOPS_SCALE + 1;
+pub const LESS_THAN_A_ROOK_AHEAD_SCALE: usize = OPPOSITE_BISHOPS_WITH_PIECES_SCALE + 1;
+pub const PARAM_COUNT: usize = LESS_THAN_A_ROOK_AHEAD_SCALE + 1;
+
+pub struct ParamGroup {
+    pub name: &'static str,
+    pub start: usize,
+    pub len: usize,
+    // how many values go on one line when the vector gets written out
+    pub row_len: usize
+}
+pub const PARAM_GROUPS: [ParamGroup; 29] = [
+    ParamGroup { name: "piece values", start: PIECE_VALUES, len: 6 * 2, row_len: 12 },
+    ParamGroup { name: "pawn square table", start: PIECE_SQUARE_TABLES, len: 64 * 2, row_len: 16 },
+    ParamGroup { name: "knight square table", start: PIECE_SQUARE_TABLES + 64 * 2, len: 64 * 2, row_len: 16 },
+    ParamGroup { name: "bishop square table", start: PIECE_SQUARE_TABLES + 2 * 64 * 2, len: 64 * 2, row_len: 16 },
+    ParamGroup { name: "rook square table", start: PIECE_SQUARE_TABLES + 3 * 64 * 2, len: 64 * 2, row_len: 16 },
+    ParamGroup { name: "queen square table", start: PIECE_SQUARE_TABLES + 4 * 64 * 2, len: 64 * 2, row_len: 16 },
+    ParamGroup { name: "king square table", start: PIECE_SQUARE_TABLES + 5 * 64 * 2, len: 64 * 2, row_len: 16 },
+    ParamGroup { name: "mobility weights", start: MOBILITY_WEIGHTS, len: 6 * 2, row_len: 12 },
+    ParamGroup { name: "center control weight", start: CENTER_CONTROL_WEIGHT, len: 2, row_len: 2 },
+    ParamGroup { name: "space weight", start: SPACE_WEIGHT, len: 2, row_len: 2 },
+    ParamGroup { name: "outpost bonuses", start: OUTPOST_BONUSES, len: 2 * 2, row_len: 4 },
+    ParamGroup { name: "doubled penalty", start: DOUBLED_PENALTY, len: 2, row_len: 2 },
+    ParamGroup { name: "isolated penalty", start: ISOLATED_PENALTY, len: 2, row_len: 2 },
+    ParamGroup { name: "backward penalty", start: BACKWARD_PENALTY, len: 2, row_len: 2 },
+    ParamGroup { name: "connected bonuses", start: CONNECTED_BONUSES, len: 8 * 2, row_len: 16 },
+    ParamGroup { name: "passed bonuses", start: PASSED_BONUSES, len: 8 * 2, row_len: 16 },
+    ParamGroup { name: "candidate bonuses", start: CANDIDATE_BONUSES, len: 8 * 2, row_len: 16 },
+    ParamGroup { name: "passed blocked penalties", start: PASSED_BLOCKED_PENALTIES, len: 8 * 2, row_len: 16 },
+    ParamGroup { name: "passed free path bonuses", start: PASSED_FREE_PATH_BONUSES, len: 8 * 2, row_len: 16 },
+    ParamGroup { name: "passed controlled stop bonuses", start: PASSED_CONTROLLED_STOP_BONUSES, len: 8 * 2, row_len: 16 },
+    ParamGroup { name: "king attack weights, weak king zone weight and safe check weights", start: KING_ATTACK_WEIGHTS, len: 6 + 1 + 6, row_len: 13 },
+    ParamGroup { name: "king danger divisor and max king danger", start: KING_DANGER_DIVISOR, len: 2, row_len: 2 },
+    ParamGroup { name: "pawn shield bonuses", start: PAWN_SHIELD_BONUSES, len: 3 * 2, row_len: 6 },
+    ParamGroup { name: "missing shield penalty", start: MISSING_SHIELD_PENALTY, len: 2, row_len: 2 },
+    ParamGroup { name: "pawn storm penalties", start: PAWN_STORM_PENALTIES, len: 5 * 2, row_len: 10 },
+    ParamGroup { name: "half open and open king file penalties", start: HALF_OPEN_KING_FILE_PENALTY, len: 2 * 2, row_len: 4 },
+    ParamGroup { name: "phase weights", start: PHASE_WEIGHTS, len: 5, row_len: 5 },
+    ParamGroup { name: "known win bonus and endgame weights", start: KNOWN_WIN_BONUS, len: 5, row_len: 5 },
+    ParamGroup { name: "endgame scales", start: OPPOSITE_BISHOPS_SCALE, len: 3, row_len: 3 }
+];
+
+pub const fn tapered(params: &EvalParams, index: usize) -> TaperedScore {
+    return TaperedScore(params[index], params[index + 1]);
+}
+
+// the source of eval_params.rs, which is how the tuner hands its results back
+pub fn write_params_source(params: &EvalParams) -> String {
+    let mut source = String::new();
+    source.push_str("// the evaluation weights, laid out the way the tune binary writes them so its output can replace this file\n");
+    source.push_str("use crate::params::EvalParams;\n\n");
+    source.push_str("pub const EVAL_PARAMS: EvalParams = [\n");
+    for param_group in PARAM_GROUPS.iter() {
+        source.push_str(&format!("    // {}\n", param_group.name));
+        for row in params[param_group.start..param_group.start + param_group.len].chunks(param_group.row_len) {
+            let row_values = row.iter().map(|value| value.to_string()).collect::<Vec<String>>();
+            source.push_str(&format!("    {},\n", row_values.join(", ")));
+        }
+    }
+    source.push_str("];\n");
+    return source;
+}
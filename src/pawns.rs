@@ -0,0 +1,154 @@
+use crate::{
+    board::*,
+    eval::*,
+    params::*
+};
+
+pub const DEFAULT_PAWN_TABLE_SIZE_KB: usize = 512;
+
+const FILE_A_MASK: u64 = 0x0101_0101_0101_0101;
+
+pub const fn file_mask(file: u8) -> u64 {
+    return FILE_A_MASK << file;
+}
+const fn adjacent_files_mask(file: u8) -> u64 {
+    let west_file_mask = if file > 0 { file_mask(file - 1) } else { 0 };
+    let east_file_mask = if file < 7 { file_mask(file + 1) } else { 0 };
+    return west_file_mask | east_file_mask;
+}
+// every square on the ranks in front of the square, as seen from the given side
+pub const fn forward_ranks_mask(square: &BoardSquare, color: BoardColor) -> u64 {
+    if color == WHITE {
+        return (1u64 << (square.y() * 8)) - 1;
+    }
+    return match u64::MAX.checked_shl((square.y() as u32 + 1) * 8) {
+        Some(forward_ranks_mask) => forward_ranks_mask,
+        None => 0
+    };
+}
+pub fn pawn_mask(board_pieces: &BoardPieces, color: BoardColor) -> u64 {
+    return board_pieces.pawns[color as usize].iter().fold(0, |pawn_mask, pawn_square| pawn_mask | pawn_square.mask());
+}
+
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub struct PawnStructure {
+    // indexed by color, each from that color's point of view, without the terms that depend on pieces
+    pub scores: [TaperedScore; 2],
+    // of both colors, pawns never share a square so one mask holds them all
+    pub passed_pawns: u64
+}
+
+pub fn eval_pawn_structure(board_pieces: &BoardPieces, params: &EvalParams) -> PawnStructure {
+    let mut pawn_structure = PawnStructure::default();
+    for color in [BLACK, WHITE] {
+        let friendly_pawns = pawn_mask(board_pieces, color);
+        let enemy_pawns = pawn_mask(board_pieces, color ^ 1);
+        let enemy_pawn_attacks = pawn_attacks(board_pieces, color ^ 1);
+        let pawn_dir = Direction::pawn_push(color);
+
+        for pawn_square in board_pieces.pawns[color as usize].iter() {
+            // the rank tables start at the first rank
+            let rank_index = relative_rank(pawn_square, color) as usize - 1;
+            let forward_mask = forward_ranks_mask(pawn_square, color);
+            let own_file_mask = file_mask(pawn_square.x());
+            let adjacent_files_mask = adjacent_files_mask(pawn_square.x());
+            let mut score = TaperedScore::default();
+
+            let is_passed = enemy_pawns & (own_file_mask | adjacent_files_mask) & forward_mask == 0;
+            let is_doubled = friendly_pawns & own_file_mask & forward_mask != 0;
+            let is_isolated = friendly_pawns & adjacent_files_mask == 0;
+            let is_supported = [Direction(-1, -pawn_dir.dy()), Direction(1, -pawn_dir.dy())].iter()
+                .filter_map(|support_direction| pawn_square.get_square_in_direction(support_direction))
+                .any(|support_square| friendly_pawns & support_square.mask() != 0);
+            let is_phalanx = [Direction::W, Direction::E].iter()
+                .filter_map(|phalanx_direction| pawn_square.get_square_in_direction(phalanx_direction))
+                .any(|phalanx_square| friendly_pawns & phalanx_square.mask() != 0);
+            // friendly pawns on the adjacent files that are level with or behind this one
+            let supporters = friendly_pawns & adjacent_files_mask & !forward_mask;
+            let is_stop_square_attacked = pawn_square.get_square_in_direction(&pawn_dir)
+                .is_some_and(|stop_square| enemy_pawn_attacks & stop_square.mask() != 0);
+
+            if is_doubled {
+                score += tapered(params, DOUBLED_PENALTY);
+            }
+            if is_isolated {
+                score += tapered(params, ISOLATED_PENALTY);
+            } else if supporters == 0 && is_stop_square_attacked {
+                score += tapered(params, BACKWARD_PENALTY);
+            }
+            if is_supported || is_phalanx {
+                score += tapered(params, CONNECTED_BONUSES + rank_index * 2);
+            }
+            if is_passed {
+                score += tapered(params, PASSED_BONUSES + rank_index * 2);
+                pawn_structure.passed_pawns |= pawn_square.mask();
+            } else if enemy_pawns & own_file_mask & forward_mask == 0 {
+                let sentries = enemy_pawns & adjacent_files_mask & forward_mask;
+                if supporters.count_ones() >= sentries.count_ones() {
+                    score += tapered(params, CANDIDATE_BONUSES + rank_index * 2);
+                }
+            }
+            pawn_structure.scores[color as usize] += score;
+        }
+    }
+    return pawn_structure;
+}
+
+// scales the passed pawns by whether they can actually run
+pub fn eval_passed_pawns(board_info: &BoardInfo, passed_pawns: u64, params: &EvalParams, trace: &mut EvalTrace) {
+    for color in [BLACK, WHITE] {
+        let pawn_dir = Direction::pawn_push(color);
+        for pawn_square in board_info.board_pieces.pawns[color as usize].iter() {
+            if passed_pawns & pawn_square.mask() == 0 {
+                continue;
+            }
+            let rank_index = relative_rank(pawn_square, color) as usize - 1;
+            let path = pawn_square.get_all_squares_in_direction(&pawn_dir);
+            let stop_square_info = match path.first() {
+                Some(stop_square) => &board_info.square_control[stop_square.pos() as usize],
+                None => continue
+            };
+            if stop_square_info.occupant.is_some() {
+                trace.add(EvalTerm::PassedPawns, color, tapered(params, PASSED_BLOCKED_PENALTIES + rank_index * 2));
+            }
+            let is_path_free = path.iter().all(|path_square| {
+                let path_square_info = &board_info.square_control[path_square.pos() as usize];
+                return path_square_info.occupant.is_none() && path_square_info.visibility[(color ^ 1) as usize] == 0;
+            });
+            if is_path_free {
+                trace.add(EvalTerm::PassedPawns, color, tapered(params, PASSED_FREE_PATH_BONUSES + rank_index * 2));
+            } else if stop_square_info.visibility[color as usize] >= stop_square_info.visibility[(color ^ 1) as usize] && stop_square_info.visibility[color as usize] > 0 {
+                trace.add(EvalTerm::PassedPawns, color, tapered(params, PASSED_CONTROLLED_STOP_BONUSES + rank_index * 2));
+            }
+        }
+    }
+}
+
+// pawn structures repeat a lot within a search, so they are cached by their pawn key
+pub struct PawnTable {
+    entries: Vec<Option<(u64, PawnStructure)>>
+}
+impl PawnTable {
+    pub fn new(size_kb: usize) -> PawnTable {
+        // a power of two, so the index is just the low bits of the key
+        let entry_count = 1usize << (size_kb.max(1) * 1024 / std::mem::size_of::<Option<(u64, PawnStructure)>>()).max(1).ilog2();
+        return PawnTable {
+            entries: vec![None; entry_count]
+        };
+    }
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+    pub fn probe(&mut self, board: &Board, board_pieces: &BoardPieces, params: &EvalParams) -> PawnStructure {
+        let pawn_key = board.pawn_zobrist_key();
+        let entry_index = pawn_key as usize & (self.entries.len() - 1);
+        if let Some((entry_key, pawn_structure)) = self.entries[entry_index] {
+            if entry_key == pawn_key {
+                return pawn_structure;
+            }
+        }
+        let pawn_structure = eval_pawn_structure(board_pieces, params);
+        self.entries[entry_index] = Some((pawn_key, pawn_structure));
+        return pawn_structure;
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{
+    board::*,
+    game::*,
+    game_tree::*,
+    score::Score
+};
+use std::{
+    fs::File,
+    io::{
+        self,
+        BufRead,
+        BufReader
+    },
+    path::Path,
+    time::Duration
+};
+
+// the pieces of movetext
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PgnToken {
+    MoveNumber(u32),
+    San(String),
+    Comment(String),
+    // numeric annotation glyph, with the !? kind of suffixes turned into theirs
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    // 1-0, 0-1, 1/2-1/2 or *
+    Result(String)
+}
+
+const SUFFIX_NAGS: [(&str, u8); 6] = [("!", 1), ("?", 2), ("!!", 3), ("??", 4), ("!?", 5), ("?!", 6)];
+
+// splits movetext one line at a time, since a brace comment can go on over several lines
+#[derive(Debug, Default)]
+struct PgnLexer {
+    open_comment: Option<String>
+}
+impl PgnLexer {
+    fn is_in_comment(&self) -> bool {
+        return self.open_comment.is_some();
+    }
+    fn tokenize_line(&mut self, line: &str, tokens: &mut Vec<PgnToken>) {
+        let mut line_chars = line.char_indices().peekable();
+        while let Some((char_index, line_char)) = line_chars.next() {
+            if let Some(comment) = self.open_comment.as_mut() {
+                if line_char == '}' {
+                    tokens.push(PgnToken::Comment(comment.trim().to_string()));
+                    self.open_comment = None;
+                } else {
+                    comment.push(line_char);
+                }
+                continue;
+            }
+            match line_char {
+                '{' => self.open_comment = Some(String::new()),
+                ';' => {
+                    tokens.push(PgnToken::Comment(line[char_index + 1..].trim().to_string()));
+                    return;
+                },
+                '(' => tokens.push(PgnToken::VariationStart),
+                ')' => tokens.push(PgnToken::VariationEnd),
+                '*' => tokens.push(PgnToken::Result("*".to_string())),
+                _ if line_char.is_whitespace() => {},
+                _ => {
+                    let mut word = String::from(line_char);
+                    while let Some((_, word_char)) = line_chars.peek().filter(|(_, word_char)| !word_char.is_whitespace() && !"{};()".contains(*word_char)) {
+                        word.push(*word_char);
+                        line_chars.next();
+                    }
+                    tokenize_word(&word, tokens);
+                }
+            }
+        }
+        // the line break is part of the comment
+        if let Some(comment) = self.open_comment.as_mut() {
+            comment.push(' ');
+        }
+    }
+}
+fn tokenize_word(word: &str, tokens: &mut Vec<PgnToken>) {
+    if let Some(nag) = word.strip_prefix('$') {
+        if let Ok(nag) = nag.parse() {
+            tokens.push(PgnToken::Nag(nag));
+        }
+        return;
+    }
+    if GameResult::from_pgn(word).is_some() {
+        tokens.push(PgnToken::Result(word.to_string()));
+        return;
+    }
+    // move numbers can be stuck to the move, like 12.e4 or 12...Nf6
+    let number_length = word.find(|word_char: char| !word_char.is_ascii_digit()).unwrap_or(word.len());
+    let mut san_move = word;
+    if number_length > 0 && word[number_length..].starts_with('.') {
+        if let Ok(move_number) = word[..number_length].parse() {
+            tokens.push(PgnToken::MoveNumber(move_number));
+        }
+        san_move = word[number_length..].trim_start_matches('.');
+    }
+    let suffix_start = san_move.find(['!', '?']).unwrap_or(san_move.len());
+    if suffix_start > 0 {
+        tokens.push(PgnToken::San(san_move[..suffix_start].to_string()));
+    }
+    if let Some((_, nag)) = SUFFIX_NAGS.iter().find(|(suffix, _)| *suffix == &san_move[suffix_start..]) {
+        tokens.push(PgnToken::Nag(*nag));
+    }
+}
+
+// [Name "value"], with \" and \\ escapes in the value
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let tag = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (tag_name, quoted_value) = tag.split_once(char::is_whitespace)?;
+    let quoted_value = quoted_value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut tag_value = String::new();
+    let mut value_chars = quoted_value.chars();
+    while let Some(value_char) = value_chars.next() {
+        tag_value.push(if value_char == '\\' { value_chars.next()? } else { value_char });
+    }
+    return Some((tag_name.to_string(), tag_value));
+}
+
+// the tags and movetext of one game
+type GameTokens = (Vec<(String, String)>, Vec<PgnToken>);
+
+// reads games one at a time, so a database never has to fit in memory;
+// a game that cannot be read is an error of its own and the games after it still come
+pub struct PgnReader<R: BufRead> {
+    lines: io::Lines<R>,
+    pending_line: Option<String>,
+    game_number: usize
+}
+impl PgnReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<PgnReader<BufReader<File>>> {
+        return Ok(PgnReader::new(BufReader::new(File::open(path)?)));
+    }
+}
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        return PgnReader {
+            lines: reader.lines(),
+            pending_line: None,
+            game_number: 0
+        };
+    }
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+        if let Some(line) = self.pending_line.take() {
+            return Some(Ok(line));
+        }
+        return self.lines.next();
+    }
+    // the tags and movetext tokens of the next game
+    fn read_game_tokens(&mut self) -> Option<io::Result<GameTokens>> {
+        let mut tags = Vec::new();
+        let mut tokens = Vec::new();
+        let mut lexer = PgnLexer::default();
+        let mut variation_depth = 0usize;
+        while let Some(line) = self.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error))
+            };
+            let trimmed_line = line.trim();
+            // escaped lines are for other programs
+            if trimmed_line.starts_with('%') && !lexer.is_in_comment() {
+                continue;
+            }
+            if trimmed_line.starts_with('[') && !lexer.is_in_comment() {
+                // tags after movetext belong to the next game, which means this one had no result
+                if !tokens.is_empty() {
+                    self.pending_line = Some(line);
+                    break;
+                }
+                if let Some(tag) = parse_tag(trimmed_line) {
+                    tags.push(tag);
+                }
+                continue;
+            }
+            let token_start = tokens.len();
+            lexer.tokenize_line(&line, &mut tokens);
+            for (token_index, token) in tokens.iter().enumerate().skip(token_start) {
+                match token {
+                    PgnToken::VariationStart => variation_depth += 1,
+                    PgnToken::VariationEnd => variation_depth = variation_depth.saturating_sub(1),
+                    PgnToken::Result(_) if variation_depth == 0 => {
+                        tokens.truncate(token_index + 1);
+                        return Some(Ok((tags, tokens)));
+                    },
+                    _ => {}
+                }
+            }
+        }
+        if tags.is_empty() && tokens.is_empty() {
+            return None;
+        }
+        return Some(Ok((tags, tokens)));
+    }
+}
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = io::Result<Game>;
+    fn next(&mut self) -> Option<io::Result<Game>> {
+        let (tags, tokens) = match self.read_game_tokens()? {
+            Ok(game_tokens) => game_tokens,
+            Err(error) => return Some(Err(error))
+        };
+        self.game_number += 1;
+        return Some(game_from_tokens(tags, &tokens).map_err(|message| {
+            return io::Error::new(io::ErrorKind::InvalidData, format!("game {}: {}", self.game_number, message));
+        }));
+    }
+}
+
+// the roster every pgn game has, in this order before any other tags
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+fn roster_default(tag_name: &str) -> &'static str {
+    return match tag_name {
+        "Date" => "????.??.??",
+        "Result" => "*",
+        _ => "?"
+    };
+}
+
+// the movetext played out on the board, with every comment, glyph and side line kept
+pub fn game_from_tokens(tags: Vec<(String, String)>, tokens: &[PgnToken]) -> Result<Game, String> {
+    let fen_tag = tags.iter().find(|(tag_name, _)| tag_name == "FEN").map(|(_, fen)| fen.clone());
+    let mut game = match fen_tag {
+        Some(fen) => {
+            if !is_valid_fen(&fen) {
+                return Err(format!("cannot set up FEN {}", fen));
+            }
+            Game::from_fen(&fen)
+        },
+        None => Game::from_fen(STARTING_FEN)
+    };
+    // the roster first, filled in where it is missing, then the rest as they came
+    for roster_tag in SEVEN_TAG_ROSTER {
+        let tag_value = tags.iter().find(|(tag_name, _)| tag_name == roster_tag).map_or(roster_default(roster_tag), |(_, tag_value)| tag_value.as_str());
+        game.set_tag(roster_tag, tag_value);
+    }
+    game.tags.extend(tags.into_iter().filter(|(tag_name, _)| !SEVEN_TAG_ROSTER.contains(&tag_name.as_str())));
+
+    let mut tokens = tokens.iter().peekable();
+    let root = game.tree().root();
+    read_line(&mut tokens, &mut game, root, 0)?;
+    let mainline_end = game.tree().mainline().last().unwrap_or(root);
+    game.go_to(mainline_end);
+    // the result at the end of the movetext says how the game ended, the tag may not have been kept up
+    let result_token = tokens.find_map(|token| match token {
+        PgnToken::Result(result_token) => Some(result_token.clone()),
+        _ => None
+    });
+    game.result = game.tag("Result").and_then(GameResult::from_pgn);
+    if let Some(result) = result_token.as_deref().and_then(GameResult::from_pgn) {
+        game.result = Some(result);
+        game.set_tag("Result", result.to_pgn());
+    }
+    return Ok(game);
+}
+// moves after the node into the game's tree, up to the end of the variation or the result for the mainline
+fn read_line<'a>(tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a PgnToken>>, game: &mut Game, start_node: NodeId, variation_depth: usize) -> Result<(), String> {
+    let mut last_node = start_node;
+    let mut pending_comments = Vec::new();
+    while let Some(token) = tokens.peek() {
+        match token {
+            PgnToken::Result(_) => break,
+            PgnToken::VariationEnd => {
+                tokens.next();
+                if variation_depth > 0 {
+                    break;
+                }
+            },
+            PgnToken::VariationStart => {
+                tokens.next();
+                // a variation replaces the move before it
+                if last_node == start_node {
+                    return Err("a variation comes before any move".to_string());
+                }
+                let branch_node = game.tree().node(last_node).parent().expect("a node after the start has a parent");
+                read_line(tokens, game, branch_node, variation_depth + 1)?;
+            },
+            PgnToken::San(san_move) => {
+                tokens.next();
+                let board = game.tree().node(last_node).board().clone();
+                let board_move = BoardMove::from_san(&board, san_move).ok_or_else(|| {
+                    let where_in_game = if variation_depth > 0 { " in a variation" } else { "" };
+                    return format!("{} is not a legal move{} after {}", san_move, where_in_game, move_list(game.tree(), start_node, last_node));
+                })?;
+                last_node = game.add_variation(last_node, &board_move).expect("from_san only gives legal moves");
+                let annotation = game.node_annotation_mut(last_node).expect("the node was just added");
+                annotation.comments_before.append(&mut pending_comments);
+            },
+            PgnToken::Comment(comment) => {
+                tokens.next();
+                if last_node == start_node {
+                    pending_comments.push(comment.clone());
+                    continue;
+                }
+                let annotation = game.node_annotation_mut(last_node).expect("the node was added above");
+                let comment = take_comment_commands(comment, annotation);
+                if !comment.is_empty() {
+                    annotation.comments.push(comment);
+                }
+            },
+            PgnToken::Nag(nag) => {
+                tokens.next();
+                if last_node != start_node {
+                    game.node_annotation_mut(last_node).expect("the node was added above").nags.push(*nag);
+                }
+            },
+            PgnToken::MoveNumber(_) => {
+                tokens.next();
+            }
+        }
+    }
+    return Ok(());
+}
+// [%clk] and [%eval] go into the annotation, the rest of the comment is left
+fn take_comment_commands(comment: &str, annotation: &mut MoveAnnotation) -> String {
+    let mut rest = String::new();
+    let mut remaining_comment = comment;
+    while let Some(command_start) = remaining_comment.find("[%") {
+        let Some(command_length) = remaining_comment[command_start..].find(']') else {
+            break;
+        };
+        let command = &remaining_comment[command_start + 2..command_start + command_length];
+        let is_taken = match command.split_once(char::is_whitespace) {
+            Some(("clk", clock)) => parse_clock(clock.trim()).map(|clock| annotation.clock = Some(clock)).is_some(),
+            Some(("eval", eval)) => parse_eval(eval.trim()).map(|eval| annotation.eval = Some(eval)).is_some(),
+            _ => false
+        };
+        let kept_length = if is_taken { command_start } else { command_start + command_length + 1 };
+        rest.push_str(&remaining_comment[..kept_length]);
+        remaining_comment = &remaining_comment[command_start + command_length + 1..];
+    }
+    rest.push_str(remaining_comment);
+    return rest.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+// h:mm:ss, the seconds possibly with a fraction
+fn parse_clock(clock: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    for clock_part in clock.split(':') {
+        seconds = seconds * 60.0 + clock_part.parse::<f64>().ok().filter(|part| *part >= 0.0)?;
+    }
+    return Some(Duration::from_secs_f64(seconds));
+}
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let tenths = clock.subsec_millis() / 100;
+    let clock_text = format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if tenths == 0 {
+        return clock_text;
+    }
+    return format!("{}.{}", clock_text, tenths);
+}
+// pawns like -0.35, or moves to mate like #4 and #-2
+fn parse_eval(eval: &str) -> Option<Score> {
+    if let Some(mate_moves) = eval.strip_prefix('#') {
+        let mate_moves = mate_moves.parse::<i32>().ok()?;
+        if mate_moves > 0 {
+            return Some(Score::mate_in(mate_moves as usize * 2 - 1));
+        }
+        return Some(Score::mated_in(mate_moves.unsigned_abs() as usize * 2));
+    }
+    let pawns = eval.parse::<f64>().ok().filter(|pawns| pawns.is_finite())?;
+    return Some(Score::from_centipawns((pawns * 100.0).round() as i32));
+}
+fn format_eval(eval: Score) -> String {
+    return eval.to_string().trim_start_matches('+').to_string();
+}
+
+// a game's mainline and every variation as pgn, roster first,
+// with a blank line after it so games can be written one after another
+pub fn game_to_pgn(game: &Game) -> String {
+    let result_token = game.adjudicated_result().map_or("*", GameResult::to_pgn);
+    let starting_fen = game.starting_fen();
+    let mut tag_lines = Vec::new();
+    for roster_tag in SEVEN_TAG_ROSTER {
+        let tag_value = if roster_tag == "Result" { result_token } else { game.tag(roster_tag).unwrap_or(roster_default(roster_tag)) };
+        tag_lines.push(tag_line(roster_tag, tag_value));
+    }
+    let is_set_up = starting_fen != STARTING_FEN;
+    if is_set_up {
+        tag_lines.push(tag_line("SetUp", "1"));
+        tag_lines.push(tag_line("FEN", &starting_fen));
+    }
+    for (tag_name, tag_value) in game.tags.iter() {
+        if !SEVEN_TAG_ROSTER.contains(&tag_name.as_str()) && tag_name != "SetUp" && tag_name != "FEN" {
+            tag_lines.push(tag_line(tag_name, tag_value));
+        }
+    }
+
+    let mut words = Vec::new();
+    let fullmove_number = starting_fen.rsplit(' ').next().and_then(|fullmove| fullmove.parse().ok()).unwrap_or(1);
+    let starting_color = game.starting_board.active_color();
+    let move_number = |ply: usize| fullmove_number + ((ply - 1 + usize::from(starting_color == BLACK)) / 2) as u32;
+    if let Some(first_node) = game.tree().node(game.tree().root()).main_child() {
+        write_line(game.tree(), first_node, &move_number, &mut words);
+    }
+    words.push(result_token.to_string());
+    return format!("{}\n\n{}\n\n", tag_lines.join("\n"), wrap_words(&words, 80));
+}
+fn tag_line(tag_name: &str, tag_value: &str) -> String {
+    return format!("[{} \"{}\"]", tag_name, tag_value.replace('\\', "\\\\").replace('"', "\\\""));
+}
+// movetext words for the line from the node on, variations in brackets after the move they replace
+fn write_line(tree: &GameTree, first_node: NodeId, move_number: &dyn Fn(usize) -> u32, words: &mut Vec<String>) {
+    let mut current_node = Some(first_node);
+    // black's moves need their number again after anything that interrupts the movetext
+    let mut needs_number = true;
+    while let Some(node_id) = current_node {
+        let node = tree.node(node_id);
+        let parent = tree.node(node.parent().expect("a line starts after the root"));
+        needs_number = write_move(node, parent.board(), move_number, needs_number, words);
+        // the first move of a variation has the main move as a sibling, which was written already
+        if node_id != first_node || parent.main_child() == Some(node_id) {
+            for variation_node in parent.children().iter().skip(1) {
+                let mut variation_words = Vec::new();
+                write_line(tree, *variation_node, move_number, &mut variation_words);
+                variation_words[0].insert(0, '(');
+                variation_words.last_mut().expect("a variation has a move").push(')');
+                words.extend(variation_words);
+                needs_number = true;
+            }
+        }
+        current_node = node.main_child();
+    }
+}
+// returns whether the next move needs its number again
+fn write_move(node: &GameNode, board_before: &Board, move_number: &dyn Fn(usize) -> u32, needs_number: bool, words: &mut Vec<String>) -> bool {
+    let annotation = &node.annotation;
+    for comment in annotation.comments_before.iter() {
+        push_comment(comment, words);
+    }
+    let needs_number = needs_number || !annotation.comments_before.is_empty();
+    // kept on the same line as its number
+    let san_move = node.board_move().expect("only the root has no move").to_san(board_before);
+    words.push(if board_before.active_color() == WHITE {
+        format!("{}. {}", move_number(node.ply()), san_move)
+    } else if needs_number {
+        format!("{}... {}", move_number(node.ply()), san_move)
+    } else {
+        san_move
+    });
+    for nag in annotation.nags.iter() {
+        words.push(format!("${}", nag));
+    }
+    let mut comment_parts = Vec::new();
+    if let Some(eval) = annotation.eval {
+        comment_parts.push(format!("[%eval {}]", format_eval(eval)));
+    }
+    if let Some(clock) = annotation.clock {
+        comment_parts.push(format!("[%clk {}]", format_clock(clock)));
+    }
+    comment_parts.extend(annotation.comments.iter().cloned());
+    if comment_parts.is_empty() {
+        return false;
+    }
+    push_comment(&comment_parts.join(" "), words);
+    return true;
+}
+// a closing brace would end the comment early, so it cannot stay in
+fn push_comment(comment: &str, words: &mut Vec<String>) {
+    let mut comment_words = comment.split_whitespace().map(|word| word.replace('}', "")).filter(|word| !word.is_empty()).collect::<Vec<String>>();
+    if comment_words.is_empty() {
+        comment_words.push(String::new());
+    }
+    comment_words[0].insert(0, '{');
+    comment_words.last_mut().expect("there is at least one word").push('}');
+    words.extend(comment_words);
+}
+fn wrap_words(words: &[String], line_width: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_line = String::new();
+    for word in words.iter() {
+        if !current_line.is_empty() && current_line.len() + 1 + word.len() > line_width {
+            lines.push(std::mem::take(&mut current_line));
+        }
+        if !current_line.is_empty() {
+            current_line.push(' ');
+        }
+        current_line.push_str(word);
+    }
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+    return lines.join("\n");
+}
+// the moves after the start node down to the end node
+fn move_list(tree: &GameTree, start_node: NodeId, end_node: NodeId) -> String {
+    let start_ply = tree.node(start_node).ply();
+    let san_moves = tree.path_to(end_node).into_iter().skip(start_ply).map(|node_id| {
+        let node = tree.node(node_id);
+        let board_before = tree.node(node.parent().expect("the path starts after the root")).board();
+        return node.board_move().expect("only the root has no move").to_san(board_before);
+    }).collect::<Vec<String>>();
+    if san_moves.is_empty() {
+        return "the start".to_string();
+    }
+    return san_moves.join(" ");
+}
+// from_fen trusts what it is given, so the shape gets checked before it
+pub fn is_valid_fen(fen: &str) -> bool {
+    let fen_parts = fen.split_whitespace().collect::<Vec<&str>>();
+    if fen_parts.len() < 4 {
+        return false;
+    }
+    let ranks = fen_parts[0].split('/').collect::<Vec<&str>>();
+    let is_valid_rank = |rank: &&str| {
+        let mut square_count = 0;
+        for rank_char in rank.chars() {
+            match rank_char {
+                '1'..='8' => square_count += rank_char as u32 - '0' as u32,
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => square_count += 1,
+                _ => return false
+            }
+        }
+        return square_count == 8;
+    };
+    let is_valid_en_passant = fen_parts[3] == "-" || matches!(fen_parts[3].as_bytes(), [b'a'..=b'h', b'3' | b'6']);
+    // the board needs one king a side, and pawns never stand on the first or last rank
+    let king_count = |king_char: char| fen_parts[0].chars().filter(|piece_char| *piece_char == king_char).count();
+    let has_back_rank_pawn = |rank: &str| rank.contains(['p', 'P']);
+    return ranks.len() == 8
+        && ranks.iter().all(is_valid_rank)
+        && king_count('K') == 1
+        && king_count('k') == 1
+        && !has_back_rank_pawn(ranks[0])
+        && !has_back_rank_pawn(ranks[7])
+        && (fen_parts[1] == "w" || fen_parts[1] == "b")
+        && (fen_parts[2] == "-" || fen_parts[2].chars().all(|castling_char| "KQkq".contains(castling_char)))
+        && is_valid_en_passant;
+}
@@ -0,0 +1,244 @@
+use crate::{
+    board::*,
+    game::*,
+    score::Score,
+    search::*
+};
+use std::{
+    io::{
+        self,
+        BufRead,
+        Write
+    },
+    time::{
+        Duration,
+        Instant
+    }
+};
+
+// a hint gets this long when the engine plays on the clock
+const HINT_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct PlayOptions {
+    pub human_color: BoardColor,
+    pub starting_fen: String,
+    // each side's clock, which gains the increment after every move
+    pub base_time: Duration,
+    pub increment: Duration,
+    // a weaker engine that also ignores its clock, when set
+    pub engine_depth: Option<u8>
+}
+impl Default for PlayOptions {
+    fn default() -> PlayOptions {
+        return PlayOptions {
+            human_color: WHITE,
+            starting_fen: STARTING_FEN.to_string(),
+            base_time: Duration::from_secs(300),
+            increment: Duration::from_secs(3),
+            engine_depth: None
+        };
+    }
+}
+
+const PLAY_HELP: &str = "\
+moves are san like Nf3 or uci like g1f3
+    undo        take back your last move and the reply to it
+    hint        ask the engine what it would play
+    flip        turn the board around
+    draw        offer a draw
+    resign      give up the game
+    quit        stop without a result";
+
+// the board as Display draws it, from black's side when flipped
+pub fn render_board(board: &Board, is_flipped: bool) -> String {
+    let board_text = board.to_string();
+    if !is_flipped {
+        return board_text;
+    }
+    let mut board_lines = board_text.lines().collect::<Vec<&str>>();
+    let rank_lines = board_lines.split_off(2);
+    let mut flipped_lines = vec!["  hgfedcba".to_string(), String::new()];
+    for rank_line in rank_lines.iter().rev() {
+        let (rank_label, squares) = rank_line.split_at(2);
+        flipped_lines.push(format!("{}{}", rank_label, squares.chars().rev().collect::<String>()));
+    }
+    return flipped_lines.join("\n") + "\n";
+}
+fn format_clock(clock: Duration) -> String {
+    let tenths = clock.as_millis() / 100;
+    return format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10);
+}
+
+// a game against the engine over a terminal, or anything else that reads and writes lines
+pub struct PlaySession<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    options: PlayOptions,
+    searcher: Searcher,
+    game: Game,
+    clocks: [Duration; 2],
+    is_flipped: bool,
+    // from the engine's side, as of its last move
+    last_engine_score: Option<Score>
+}
+impl<R: BufRead, W: Write> PlaySession<R, W> {
+    pub fn new(input: R, output: W, options: PlayOptions) -> PlaySession<R, W> {
+        return PlaySession {
+            input,
+            output,
+            searcher: Searcher::new(),
+            game: Game::from_fen(&options.starting_fen),
+            clocks: [options.base_time; 2],
+            is_flipped: options.human_color == BLACK,
+            last_engine_score: None,
+            options
+        };
+    }
+    // plays until the game ends or the input does, and hands back the game with its result if it has one
+    pub fn play(mut self) -> io::Result<Game> {
+        let engine_color = self.options.human_color ^ 1;
+        self.game.set_tag("White", if engine_color == WHITE { "apotheosis" } else { "you" });
+        self.game.set_tag("Black", if engine_color == BLACK { "apotheosis" } else { "you" });
+        writeln!(self.output, "type help for the commands")?;
+        self.show_position()?;
+        while self.game.result.is_none() {
+            if let Some(outcome) = self.game.outcome() {
+                self.game.result = Some(outcome.result());
+                writeln!(self.output, "{}, {}", outcome, outcome.result().to_pgn())?;
+                break;
+            }
+            if self.game.current_board.active_color() == engine_color {
+                self.play_engine_move()?;
+                continue;
+            }
+            if !self.play_human_turn()? {
+                break;
+            }
+        }
+        return Ok(self.game);
+    }
+
+    fn show_position(&mut self) -> io::Result<()> {
+        writeln!(self.output)?;
+        write!(self.output, "{}", render_board(&self.game.current_board, self.is_flipped))?;
+        let to_move = if self.game.current_board.active_color() == WHITE { "white" } else { "black" };
+        writeln!(self.output, "white {}  black {}  {} to move", format_clock(self.clocks[WHITE as usize]), format_clock(self.clocks[BLACK as usize]), to_move)?;
+        return self.output.flush();
+    }
+    fn search_limits(&self, time_limit: Option<Duration>) -> SearchLimits {
+        if let Some(engine_depth) = self.options.engine_depth {
+            return SearchLimits::new_depth_limited(engine_depth);
+        }
+        // a hint is a fixed time off nobody's clock
+        let remaining_time = if time_limit.is_some() { [None; 2] } else { [Some(self.clocks[BLACK as usize]), Some(self.clocks[WHITE as usize])] };
+        return SearchLimits {
+            move_time: time_limit,
+            remaining_time,
+            increment: [self.options.increment; 2],
+            ..SearchLimits::default()
+        };
+    }
+    // the clock runs from when the move was asked for, and a side without time left loses
+    fn charge_clock(&mut self, color: BoardColor, move_time: Duration) -> io::Result<bool> {
+        if move_time > self.clocks[color as usize] {
+            self.clocks[color as usize] = Duration::ZERO;
+            self.game.result = Some(if color == WHITE { GameResult::BlackWins } else { GameResult::WhiteWins });
+            self.game.set_tag("Termination", "time forfeit");
+            let loser = if color == self.options.human_color { "you" } else { "the engine" };
+            writeln!(self.output, "{} ran out of time, {}", loser, self.game.result.expect("set above").to_pgn())?;
+            return Ok(false);
+        }
+        self.clocks[color as usize] = self.clocks[color as usize] - move_time + self.options.increment;
+        return Ok(true);
+    }
+
+    fn play_engine_move(&mut self) -> io::Result<()> {
+        let engine_color = self.game.current_board.active_color();
+        let move_start = Instant::now();
+        let limits = self.search_limits(None);
+        let result = self.searcher.search(&self.game.current_board, &self.game.history(), &limits, |_| {});
+        if !self.charge_clock(engine_color, move_start.elapsed())? {
+            return Ok(());
+        }
+        let best_move = result.best_move.expect("the game is not over, so there is a move");
+        self.last_engine_score = Some(result.score);
+        let san_move = best_move.to_san(&self.game.current_board);
+        self.game.play_move(&best_move);
+        let move_index = self.game.moves().len() - 1;
+        let annotation = self.game.annotation_mut(move_index).expect("the move was just played");
+        annotation.eval = Some(if engine_color == WHITE { result.score } else { -result.score });
+        annotation.clock = Some(self.clocks[engine_color as usize]);
+        writeln!(self.output, "the engine plays {} ({})", san_move, result.score)?;
+        return self.show_position();
+    }
+    // returns false once the input ends or the player quits
+    fn play_human_turn(&mut self) -> io::Result<bool> {
+        let human_color = self.options.human_color;
+        let turn_start = Instant::now();
+        loop {
+            write!(self.output, "your move: ")?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                return Ok(false);
+            }
+            let command = line.trim();
+            match command {
+                "" => continue,
+                "help" => writeln!(self.output, "{}", PLAY_HELP)?,
+                "quit" => return Ok(false),
+                "flip" => {
+                    self.is_flipped = !self.is_flipped;
+                    self.show_position()?;
+                },
+                "resign" => {
+                    self.game.result = Some(if human_color == WHITE { GameResult::BlackWins } else { GameResult::WhiteWins });
+                    writeln!(self.output, "you resign, {}", self.game.result.expect("set above").to_pgn())?;
+                    return Ok(true);
+                },
+                "draw" | "offer draw" => {
+                    // the engine takes a draw whenever it does not think it is better
+                    if self.last_engine_score.is_some_and(|engine_score| engine_score <= Score::DRAW) {
+                        self.game.result = Some(GameResult::Draw);
+                        writeln!(self.output, "the engine accepts the draw, 1/2-1/2")?;
+                        return Ok(true);
+                    }
+                    writeln!(self.output, "the engine declines the draw")?;
+                },
+                "hint" => {
+                    let limits = self.search_limits(Some(HINT_TIME));
+                    let result = self.searcher.search(&self.game.current_board, &self.game.history(), &limits, |_| {});
+                    match result.best_move {
+                        Some(best_move) => writeln!(self.output, "hint: {} ({})", best_move.to_san(&self.game.current_board), result.score)?,
+                        None => writeln!(self.output, "there is nothing to play")?
+                    }
+                },
+                "undo" => {
+                    if !self.game.boards_before_moves().any(|board| board.active_color() == human_color) {
+                        writeln!(self.output, "there is nothing of yours to take back")?;
+                        continue;
+                    }
+                    while self.game.undo_move().is_some() && self.game.current_board.active_color() != human_color {}
+                    self.show_position()?;
+                },
+                _ => {
+                    let board = &self.game.current_board;
+                    let Some(board_move) = BoardMove::from_san(board, command).or_else(|| BoardMove::from_uci(board, command)) else {
+                        writeln!(self.output, "{} is not a legal move here, type help for the commands", command)?;
+                        continue;
+                    };
+                    if !self.charge_clock(human_color, turn_start.elapsed())? {
+                        return Ok(true);
+                    }
+                    self.game.play_move(&board_move);
+                    let move_index = self.game.moves().len() - 1;
+                    self.game.annotation_mut(move_index).expect("the move was just played").clock = Some(self.clocks[human_color as usize]);
+                    self.show_position()?;
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
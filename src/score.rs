@@ -0,0 +1,141 @@
+use std::{
+    fmt,
+    ops::{
+        Add,
+        Neg,
+        Sub
+    }
+};
+
+const INFINITE_VALUE: i32 = 32_000;
+const MATE_VALUE: i32 = 31_000;
+// any value past this is a forced mate; the search only sees mates within MAX_PLY,
+// but the tablebases know of ones hundreds of plies away
+const MAX_MATE_PLIES: i32 = 1000;
+const MATE_BOUND: i32 = MATE_VALUE - MAX_MATE_PLIES;
+// wins the tablebases vouch for without knowing the distance to mate, ranked by how soon they are reached
+const TABLEBASE_WIN_VALUE: i32 = MATE_BOUND - 1;
+const TABLEBASE_WIN_BOUND: i32 = TABLEBASE_WIN_VALUE - MAX_MATE_PLIES;
+// evaluations get clamped below the mate and tablebase scores, so they can never be mistaken for one
+const MAX_CENTIPAWNS: i32 = TABLEBASE_WIN_BOUND - 1;
+
+// from the side to move's point of view, either centipawns or a mate some plies from the root;
+// the range is symmetric, so negating never overflows
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Default, Clone, Copy, Hash)]
+pub struct Score(i32);
+impl Score {
+    pub const DRAW: Score = Score(0);
+    // beyond every score a search can return, for the initial window
+    pub const INFINITY: Score = Score(INFINITE_VALUE);
+
+    pub const fn from_centipawns(centipawns: i32) -> Score {
+        return Score(if centipawns > MAX_CENTIPAWNS {
+            MAX_CENTIPAWNS
+        } else if centipawns < -MAX_CENTIPAWNS {
+            -MAX_CENTIPAWNS
+        } else {
+            centipawns
+        });
+    }
+    // the side to move mates this many plies from the root, 0 meaning the enemy king is already gone
+    pub const fn mate_in(ply: usize) -> Score {
+        return Score(MATE_VALUE - ply as i32);
+    }
+    // the side to move is mated this many plies from the root
+    pub const fn mated_in(ply: usize) -> Score {
+        return Score(-MATE_VALUE + ply as i32);
+    }
+    // the side to move reaches a won tablebase position this many plies from the root
+    pub const fn tablebase_win(ply: usize) -> Score {
+        return Score(TABLEBASE_WIN_VALUE - ply as i32);
+    }
+    pub const fn tablebase_loss(ply: usize) -> Score {
+        return Score(-TABLEBASE_WIN_VALUE + ply as i32);
+    }
+    // the raw number, where mates lie past every centipawn score
+    pub const fn value(self) -> i32 {
+        return self.0;
+    }
+    pub const fn is_mate(self) -> bool {
+        return self.0 >= MATE_BOUND || self.0 <= -MATE_BOUND;
+    }
+    // a forced mate, or a win the tablebases know of
+    pub const fn is_decisive(self) -> bool {
+        return self.0 >= TABLEBASE_WIN_BOUND || self.0 <= -TABLEBASE_WIN_BOUND;
+    }
+    pub const fn is_draw(self) -> bool {
+        return self.0 == 0;
+    }
+    // full moves until mate, negative when the side to move is the one getting mated
+    pub const fn mate_moves(self) -> Option<i32> {
+        if !self.is_mate() {
+            return None;
+        }
+        if self.0 > 0 {
+            return Some((MATE_VALUE - self.0 + 1) / 2);
+        }
+        return Some(-(MATE_VALUE + self.0 + 1) / 2);
+    }
+    // mates and tablebase wins are stored in the transposition table counted from the node instead of the root,
+    // so an entry stays right wherever in the tree the position turns up again
+    pub const fn to_tt(self, ply: usize) -> i16 {
+        let value = if self.0 >= TABLEBASE_WIN_BOUND {
+            self.0 + ply as i32
+        } else if self.0 <= -TABLEBASE_WIN_BOUND {
+            self.0 - ply as i32
+        } else {
+            self.0
+        };
+        return value as i16;
+    }
+    pub const fn from_tt(tt_value: i16, ply: usize) -> Score {
+        let value = tt_value as i32;
+        return Score(if value >= TABLEBASE_WIN_BOUND {
+            value - ply as i32
+        } else if value <= -TABLEBASE_WIN_BOUND {
+            value + ply as i32
+        } else {
+            value
+        });
+    }
+    // the score part of a uci info line
+    pub fn to_uci(self) -> String {
+        return match self.mate_moves() {
+            Some(mate_moves) => format!("mate {}", mate_moves),
+            None => format!("cp {}", self.0)
+        };
+    }
+}
+impl Neg for Score {
+    type Output = Score;
+    fn neg(self) -> Score {
+        return Score(-self.0);
+    }
+}
+// centipawn offsets, for search windows; the result stays within infinity
+impl Add<i32> for Score {
+    type Output = Score;
+    fn add(self, centipawns: i32) -> Score {
+        return Score(self.0.saturating_add(centipawns).clamp(-INFINITE_VALUE, INFINITE_VALUE));
+    }
+}
+impl Sub<i32> for Score {
+    type Output = Score;
+    fn sub(self, centipawns: i32) -> Score {
+        return Score(self.0.saturating_sub(centipawns).clamp(-INFINITE_VALUE, INFINITE_VALUE));
+    }
+}
+// pawns with two decimals like +1.23, or moves to mate like #5 and #-3
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(mate_moves) = self.mate_moves() {
+            return write!(f, "#{}", mate_moves);
+        }
+        let sign = match self.0 {
+            0 => "",
+            value if value > 0 => "+",
+            _ => "-"
+        };
+        return write!(f, "{}{}.{:02}", sign, self.0.abs() / 100, self.0.abs() % 100);
+    }
+}
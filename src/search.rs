@@ -0,0 +1,628 @@
+use crate::{
+    board::*,
+    eval::eval_board,
+    nnue::*,
+    pawns::*,
+    score::Score,
+    syzygy::Syzygy,
+    tablebase::*,
+    time::TimeManager,
+    tt::*
+};
+use std::{
+    cmp,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering
+        },
+        Arc
+    },
+    thread,
+    time::{
+        Duration,
+        Instant
+    }
+};
+
+pub const MAX_PLY: usize = 64;
+
+// half width of the first aspiration window, in centipawns
+const ASPIRATION_WINDOW: i32 = 25;
+const ASPIRATION_MIN_DEPTH: u8 = 4;
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub move_time: Option<Duration>,
+    // remaining clock time and increment, indexed by color
+    pub remaining_time: [Option<Duration>; 2],
+    pub increment: [Duration; 2],
+    pub moves_to_go: Option<u32>,
+    // stops after searching this many nodes, which keeps single threaded searches reproducible
+    pub nodes: Option<u64>,
+    // stops as soon as a mate in this many moves is found
+    pub mate: Option<u32>,
+    // only these root moves get searched, when there are any
+    pub search_moves: Vec<BoardMove>,
+    // keeps searching until stopped from the outside
+    pub infinite: bool,
+    // ponders until this gets set, and only then starts the clock on the given time controls
+    pub ponder_hit: Option<Arc<AtomicBool>>
+}
+impl SearchLimits {
+    pub fn new_depth_limited(depth: u8) -> SearchLimits {
+        return SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub board_move: BoardMove,
+    pub score: Score,
+    pub pv: Vec<BoardMove>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<BoardMove>,
+    pub score: Score,
+    pub depth: u8,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<BoardMove>,
+    // every searched root line, best first; only holds more than one line in multi pv mode
+    pub lines: Vec<PvLine>
+}
+
+pub struct Searcher {
+    pub nodes: u64,
+    // how many of the best root moves get their own line
+    pub multi_pv: usize,
+    // lazy smp, every extra thread runs its own search sharing the transposition table
+    pub threads: usize,
+    // evaluates with this network instead of the hand-crafted evaluation when set
+    pub network: Option<Arc<Network>>,
+    // exact results for the endings they cover, at the root and in the tree
+    pub tablebases: Option<Arc<Tablebases>>,
+    // win, draw or loss in the tree, and distance to zeroing to narrow down the root moves
+    pub syzygy: Option<Arc<Syzygy>>,
+    // positions with up to this many pieces get probed in the tree, 0 when the root already settled it
+    syzygy_probe_limit: usize,
+    // what the syzygy tables promise at the root, which the search cannot see unless it finds the mate
+    root_tablebase_score: Option<Score>,
+    transposition_table: Arc<TranspositionTable>,
+    // every thread keeps its own, it is small and cheap to refill
+    pawn_table: PawnTable,
+    // indexed by ply, each built from the one before it when a move is made
+    accumulators: Vec<Accumulator>,
+    stop_signal: Arc<AtomicBool>,
+    // 0 for the main thread
+    helper_index: usize,
+    // triangular pv table, row n holds the best line found from ply n
+    pv_table: [[Option<BoardMove>; MAX_PLY + 1]; MAX_PLY + 1],
+    pv_length: [usize; MAX_PLY + 1],
+    previous_pv: Vec<BoardMove>,
+    excluded_root_moves: Vec<BoardMove>,
+    search_moves: Vec<BoardMove>,
+    killer_moves: [[Option<BoardMove>; 2]; MAX_PLY + 1],
+    position_history: Vec<Board>,
+    start_time: Instant,
+    time_manager: TimeManager,
+    node_limit: Option<u64>,
+    is_stopped: bool
+}
+impl Default for Searcher {
+    fn default() -> Searcher {
+        return Searcher::new();
+    }
+}
+impl Searcher {
+    pub fn new() -> Searcher {
+        return Searcher::new_sharing(Arc::new(TranspositionTable::new(DEFAULT_HASH_SIZE_MB)), Arc::new(AtomicBool::new(false)));
+    }
+    fn new_sharing(transposition_table: Arc<TranspositionTable>, stop_signal: Arc<AtomicBool>) -> Searcher {
+        return Searcher {
+            nodes: 0,
+            multi_pv: 1,
+            threads: 1,
+            network: None,
+            tablebases: None,
+            syzygy: None,
+            syzygy_probe_limit: 0,
+            root_tablebase_score: None,
+            transposition_table,
+            pawn_table: PawnTable::new(DEFAULT_PAWN_TABLE_SIZE_KB),
+            accumulators: Vec::new(),
+            stop_signal,
+            helper_index: 0,
+            pv_table: [[None; MAX_PLY + 1]; MAX_PLY + 1],
+            pv_length: [0; MAX_PLY + 1],
+            previous_pv: Vec::new(),
+            excluded_root_moves: Vec::new(),
+            search_moves: Vec::new(),
+            killer_moves: [[None; 2]; MAX_PLY + 1],
+            position_history: Vec::new(),
+            start_time: Instant::now(),
+            time_manager: TimeManager::unlimited(),
+            node_limit: None,
+            is_stopped: false
+        };
+    }
+    fn new_helper(&self, helper_index: usize) -> Searcher {
+        let mut helper = Searcher::new_sharing(self.transposition_table.clone(), self.stop_signal.clone());
+        helper.helper_index = helper_index;
+        helper.network = self.network.clone();
+        helper.tablebases = self.tablebases.clone();
+        helper.syzygy = self.syzygy.clone();
+        helper.syzygy_probe_limit = self.syzygy_probe_limit;
+        helper.root_tablebase_score = self.root_tablebase_score;
+        helper.position_history = self.position_history.clone();
+        helper.start_time = self.start_time;
+        helper.search_moves = self.search_moves.clone();
+        return helper;
+    }
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.transposition_table = Arc::new(TranspositionTable::new(size_mb));
+    }
+    pub fn clear_hash(&mut self) {
+        self.transposition_table.clear();
+        self.pawn_table.clear();
+    }
+    // setting this stops the running search, which clears it again once it has finished;
+    // if it gets set while no search is running, the next search stops right away
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        return self.stop_signal.clone();
+    }
+    // history holds the positions played before this one, for repetition detection
+    pub fn search(&mut self, board: &Board, history: &[Board], limits: &SearchLimits, mut report: impl FnMut(&SearchResult)) -> SearchResult {
+        self.position_history = history.to_vec();
+        self.start_time = Instant::now();
+        self.time_manager = TimeManager::new(limits, board.active_color());
+        self.node_limit = limits.nodes;
+        let valid_moves = board.get_valid_moves();
+        self.search_moves = limits.search_moves.iter().filter(|search_move| valid_moves.contains(search_move)).copied().collect();
+        self.rank_syzygy_root_moves(board);
+        self.transposition_table.new_search();
+
+        let result = if self.threads <= 1 {
+            self.iterative_deepening(board, limits, &mut report)
+        } else {
+            self.search_threaded(board, limits, &mut report)
+        };
+        self.stop_signal.store(false, Ordering::Relaxed);
+        return result;
+    }
+    // narrows the root moves down to the ones keeping the best result the syzygy tables know of
+    fn rank_syzygy_root_moves(&mut self, board: &Board) {
+        self.syzygy_probe_limit = 0;
+        self.root_tablebase_score = None;
+        let syzygy = match &self.syzygy {
+            Some(syzygy) => syzygy.clone(),
+            None => return
+        };
+        self.syzygy_probe_limit = syzygy.max_pieces();
+        let ranking = match syzygy.rank_root_moves(board, &self.position_history, &self.search_moves) {
+            Some(ranking) => ranking,
+            None => return
+        };
+        let best_rank = ranking.moves.iter().map(|ranked_move| ranked_move.rank).max().unwrap_or(0);
+        let best_moves = ranking.moves.iter().filter(|ranked_move| ranked_move.rank == best_rank).collect::<Vec<_>>();
+        self.search_moves = best_moves.iter().map(|ranked_move| ranked_move.board_move).collect();
+        let root_score = best_moves[0].score;
+        self.root_tablebase_score = Some(root_score);
+        // the distances to zeroing already make sure of progress, and without a win there is nothing to look for
+        if ranking.uses_dtz || root_score <= Score::DRAW {
+            self.syzygy_probe_limit = 0;
+        }
+    }
+    fn search_threaded(&mut self, board: &Board, limits: &SearchLimits, report: &mut impl FnMut(&SearchResult)) -> SearchResult {
+        let helpers: Vec<Searcher> = (1..self.threads).map(|helper_index| self.new_helper(helper_index)).collect();
+        let (main_result, helper_results) = thread::scope(|scope| {
+            let helper_handles: Vec<_> = helpers.into_iter().map(|mut helper| {
+                return scope.spawn(move || helper.iterative_deepening(board, limits, &mut |_| {}));
+            }).collect();
+            let main_result = self.iterative_deepening(board, limits, report);
+            // helpers only stop on their own once they hit the depth limit
+            self.stop_signal.store(true, Ordering::Relaxed);
+            let helper_results: Vec<SearchResult> = helper_handles.into_iter()
+                .map(|helper_handle| helper_handle.join().expect("search helper thread panicked"))
+                .collect();
+            return (main_result, helper_results);
+        });
+
+        let total_nodes = main_result.nodes + helper_results.iter().map(|helper_result| helper_result.nodes).sum::<u64>();
+        let mut result = main_result;
+        if self.multi_pv <= 1 {
+            // trust whichever thread got the furthest, the main thread wins ties
+            for helper_result in helper_results {
+                if helper_result.depth > result.depth && helper_result.best_move.is_some() {
+                    result = helper_result;
+                }
+            }
+        }
+        result.nodes = total_nodes;
+        return result;
+    }
+    fn iterative_deepening(&mut self, board: &Board, limits: &SearchLimits, report: &mut impl FnMut(&SearchResult)) -> SearchResult {
+        self.nodes = 0;
+        self.killer_moves = [[None; 2]; MAX_PLY + 1];
+        self.is_stopped = false;
+        if let Some(network) = &self.network {
+            self.accumulators.resize_with(MAX_PLY + 2, Accumulator::default);
+            network.refresh(&mut self.accumulators[0], board);
+        }
+
+        let root_move_count = if self.search_moves.is_empty() { board.get_valid_moves().len() } else { self.search_moves.len() };
+        let mut result = SearchResult {
+            // something to play even if the first iteration gets cut short
+            best_move: board.get_valid_moves().into_iter().find(|valid_move| self.search_moves.is_empty() || self.search_moves.contains(valid_move)),
+            score: Score::DRAW,
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            pv: Vec::new(),
+            lines: Vec::new()
+        };
+        if root_move_count == 0 {
+            if board.is_in_check() {
+                result.score = Score::mated_in(0);
+            }
+            return result;
+        }
+        if let Some(tablebase_result) = self.probe_root(board) {
+            report(&tablebase_result);
+            return tablebase_result;
+        }
+
+        // a mate in n shows up within 2n plies, so there is no point going deeper
+        let default_depth = limits.mate.map_or(MAX_PLY as u8, |mate_moves| cmp::min(mate_moves * 2, MAX_PLY as u32) as u8);
+        let max_depth = cmp::min(limits.depth.unwrap_or(default_depth), MAX_PLY as u8);
+        let line_count = if self.helper_index == 0 { cmp::min(self.multi_pv.max(1), root_move_count) } else { 1 };
+        // odd helpers stay a ply ahead of the main thread, so the threads do not all search the same tree
+        let first_depth = cmp::min(1 + (self.helper_index % 2) as u8, max_depth);
+        for depth in first_depth..=max_depth {
+            let mut lines: Vec<PvLine> = Vec::with_capacity(line_count);
+            self.excluded_root_moves.clear();
+            for line_index in 0..line_count {
+                // every line gets searched as if the better lines' moves did not exist
+                let previous_line = result.lines.get(line_index);
+                self.previous_pv = previous_line.map(|line| line.pv.clone()).unwrap_or_default();
+                let score = self.aspiration_search(board, depth, previous_line.map_or(Score::DRAW, |line| line.score));
+                if self.is_stopped {
+                    break;
+                }
+                let pv: Vec<BoardMove> = self.pv_table[0][..self.pv_length[0]].iter().flatten().copied().collect();
+                // an empty pv keeps what the last iteration found for this line
+                let (board_move, pv) = match (pv.first(), previous_line) {
+                    (Some(board_move), _) => (*board_move, pv),
+                    (None, Some(previous_line)) => (previous_line.board_move, previous_line.pv.clone()),
+                    (None, None) => break
+                };
+                self.excluded_root_moves.push(board_move);
+                lines.push(PvLine {
+                    board_move,
+                    score,
+                    pv
+                });
+            }
+            if self.is_stopped || lines.is_empty() {
+                // a partial iteration cannot be trusted
+                break;
+            }
+            lines.sort_by_key(|line| -line.score);
+            if let Some(root_tablebase_score) = self.root_tablebase_score {
+                for line in lines.iter_mut().filter(|line| !line.score.is_mate()) {
+                    line.score = root_tablebase_score;
+                }
+            }
+            result = SearchResult {
+                best_move: Some(lines[0].board_move),
+                score: lines[0].score,
+                depth,
+                nodes: self.nodes,
+                elapsed: self.start_time.elapsed(),
+                pv: lines[0].pv.clone(),
+                lines
+            };
+            report(&result);
+
+            if let Some(mate_moves) = result.score.mate_moves() {
+                // a shorter mate cannot show up at a later depth
+                if mate_moves.unsigned_abs() * 2 <= depth as u32 {
+                    break;
+                }
+                if limits.mate.is_some_and(|mate_limit| mate_moves > 0 && mate_moves <= mate_limit as i32) {
+                    break;
+                }
+            }
+            if !self.time_manager.has_time_for_iteration() {
+                break;
+            }
+        }
+        return result;
+    }
+    // a position the tablebases know needs no search, just the line that keeps to the best result
+    fn probe_root(&self, board: &Board) -> Option<SearchResult> {
+        let tablebases = self.tablebases.as_ref()?;
+        let (best_move, tablebase_result) = tablebases.best_move(board, &self.search_moves)?;
+        let mut pv = vec![best_move];
+        let mut pv_board = board.create_board_from_move(&best_move);
+        // a drawn line could go on forever
+        while pv.len() < MAX_PLY && tablebase_result != TablebaseResult::Draw {
+            match tablebases.best_move(&pv_board, &[]) {
+                Some((pv_move, _)) => {
+                    pv.push(pv_move);
+                    pv_board = pv_board.create_board_from_move(&pv_move);
+                },
+                None => break
+            }
+        }
+        let score = tablebase_result.to_score(0);
+        return Some(SearchResult {
+            best_move: Some(best_move),
+            score,
+            depth: 1,
+            nodes: 0,
+            elapsed: self.start_time.elapsed(),
+            lines: vec![PvLine {
+                board_move: best_move,
+                score,
+                pv: pv.clone()
+            }],
+            pv
+        });
+    }
+    fn aspiration_search(&mut self, board: &Board, depth: u8, previous_score: Score) -> Score {
+        if depth < ASPIRATION_MIN_DEPTH || previous_score.is_decisive() {
+            return self.negamax(board, depth, 0, -Score::INFINITY, Score::INFINITY);
+        }
+        let mut window = ASPIRATION_WINDOW;
+        let mut alpha = previous_score - window;
+        let mut beta = previous_score + window;
+        loop {
+            let score = self.negamax(board, depth, 0, alpha, beta);
+            if self.is_stopped {
+                return score;
+            }
+            // widen the side that failed, until the score lands inside the window
+            window *= 2;
+            if score <= alpha {
+                alpha = score - window;
+            } else if score >= beta {
+                beta = score + window;
+            } else {
+                return score;
+            }
+        }
+    }
+    fn check_limits(&mut self) {
+        if let Some(node_limit) = self.node_limit {
+            // checked on every node, so node limited searches always stop at the same spot
+            if self.nodes >= node_limit {
+                self.is_stopped = true;
+            }
+        }
+        if self.nodes & 1023 == 0 {
+            self.check_time();
+        }
+    }
+    fn check_time(&mut self) {
+        if self.stop_signal.load(Ordering::Relaxed) {
+            self.is_stopped = true;
+        }
+        if self.time_manager.is_time_up() {
+            self.is_stopped = true;
+        }
+    }
+    fn update_pv(&mut self, ply: usize, board_move: BoardMove) {
+        self.pv_table[ply][ply] = Some(board_move);
+        for next_ply in (ply + 1)..self.pv_length[ply + 1] {
+            self.pv_table[ply][next_ply] = self.pv_table[ply + 1][next_ply];
+        }
+        self.pv_length[ply] = cmp::max(self.pv_length[ply + 1], ply + 1);
+    }
+    // keeps the accumulator of the next ply in step with the move about to be searched
+    fn make_move(&mut self, board: &Board, moved_board: &Board, ply: usize) {
+        if let Some(network) = &self.network {
+            let (previous_accumulators, next_accumulators) = self.accumulators.split_at_mut(ply + 1);
+            network.update(&previous_accumulators[ply], board, &mut next_accumulators[0], moved_board);
+        }
+    }
+    fn evaluate(&mut self, board: &Board, ply: usize) -> Score {
+        return match &self.network {
+            Some(network) => Score::from_centipawns(network.evaluate(&self.accumulators[ply], board.active_color())),
+            None => eval_board(board, &mut self.pawn_table)
+        };
+    }
+    fn order_moves(&self, board: &Board, moves: &mut [BoardMove], ply: usize, hash_move: Option<BoardMove>) {
+        let piece_value = |piece: Option<BoardPiece>| match piece.map(get_piece_type) {
+            Some(PAWN) => 1,
+            Some(KNIGHT) | Some(BISHOP) => 3,
+            Some(ROOK) => 5,
+            Some(QUEEN) => 9,
+            Some(KING) => 10,
+            _ => 0
+        };
+        let pv_move = self.previous_pv.get(ply).copied();
+        moves.sort_by_cached_key(|board_move| {
+            if Some(*board_move) == hash_move {
+                return -2000;
+            }
+            if Some(*board_move) == pv_move {
+                return -1000;
+            }
+            if board.is_promotion(board_move) && board_move.promotion_target_piece() == QUEEN {
+                return -900;
+            }
+            if board.is_capture(board_move) {
+                // most valuable victim, least valuable attacker
+                let victim = if board_move.is_en_passant() { Some(PAWN) } else { board.get_piece_at(&board_move.dest_square()) };
+                return -100 - piece_value(victim) * 10 + piece_value(board.get_piece_at(&board_move.from_square()));
+            }
+            if let Some(killer_index) = self.killer_moves[ply].iter().position(|killer_move| *killer_move == Some(*board_move)) {
+                return -50 + killer_index as i32;
+            }
+            return 0;
+        });
+    }
+    fn negamax(&mut self, board: &Board, depth: u8, ply: usize, mut alpha: Score, beta: Score) -> Score {
+        self.pv_length[ply] = ply;
+        self.check_limits();
+        if self.is_stopped {
+            return Score::DRAW;
+        }
+        if ply > 0 && self.position_history.contains(board) {
+            return Score::DRAW;
+        }
+        if ply > 0 {
+            if let Some(tablebase_result) = self.tablebases.as_ref().and_then(|tablebases| tablebases.probe(board)) {
+                self.nodes += 1;
+                return tablebase_result.to_score(ply);
+            }
+            // right after a capture, where the fifty move counter starts over
+            let is_after_capture = self.position_history.last().is_some_and(|parent| parent.piece_count() != board.piece_count());
+            if is_after_capture && board.piece_count() as usize <= self.syzygy_probe_limit {
+                if let Some(wdl) = self.syzygy.as_ref().and_then(|syzygy| syzygy.probe_wdl(board)) {
+                    self.nodes += 1;
+                    return wdl.to_score(ply);
+                }
+            }
+        }
+        let is_in_check = board.is_in_check();
+        // look one ply further when in check, so we do not stumble into mates at the horizon
+        let depth = if is_in_check { depth + 1 } else { depth };
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+        self.nodes += 1;
+
+        let position_key = board.zobrist_key();
+        let hash_entry = self.transposition_table.probe(position_key, ply);
+        if let Some(hash_entry) = hash_entry {
+            // pv nodes are searched in full so the pv does not get cut short
+            let is_pv_node = beta > alpha + 1;
+            if ply > 0 && !is_pv_node && hash_entry.depth >= depth {
+                let is_usable = match hash_entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => hash_entry.score >= beta,
+                    Bound::Upper => hash_entry.score <= alpha
+                };
+                if is_usable {
+                    return hash_entry.score;
+                }
+            }
+        }
+        let original_alpha = alpha;
+
+        let mut moves = board.get_valid_moves();
+        if moves.is_empty() {
+            return if is_in_check { Score::mated_in(ply) } else { Score::DRAW };
+        }
+        if ply == 0 {
+            moves.retain(|board_move| {
+                return !self.excluded_root_moves.contains(board_move) 
+                    && (self.search_moves.is_empty() || self.search_moves.contains(board_move));
+            });
+        }
+        self.order_moves(board, &mut moves, ply, hash_entry.and_then(|hash_entry| hash_entry.best_move));
+
+        self.position_history.push(board.clone());
+        let mut best_score = -Score::INFINITY;
+        let mut best_move = None;
+        for (move_index, board_move) in moves.iter().enumerate() {
+            let moved_board = board.create_board_from_move(board_move);
+            self.make_move(board, &moved_board, ply);
+            let score = if move_index == 0 {
+                -self.negamax(&moved_board, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                // prove the move is worse than the pv with a null window, and only re-search if it is not
+                let zero_window_score = -self.negamax(&moved_board, depth - 1, ply + 1, -(alpha + 1), -alpha);
+                if zero_window_score > alpha && zero_window_score < beta {
+                    -self.negamax(&moved_board, depth - 1, ply + 1, -beta, -alpha)
+                } else {
+                    zero_window_score
+                }
+            };
+            if self.is_stopped {
+                break;
+            }
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(*board_move);
+                    self.update_pv(ply, *board_move);
+                    if alpha >= beta {
+                        if !board.is_capture(board_move) && self.killer_moves[ply][0] != Some(*board_move) {
+                            self.killer_moves[ply][1] = self.killer_moves[ply][0];
+                            self.killer_moves[ply][0] = Some(*board_move);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        self.position_history.pop();
+
+        // the root only saw part of its moves when other pv lines are excluded or searchmoves is used
+        if !self.is_stopped && (ply > 0 || (self.excluded_root_moves.is_empty() && self.search_moves.is_empty())) {
+            let bound = if best_score >= beta {
+                Bound::Lower
+            } else if best_score > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.transposition_table.store(position_key, ply, TranspositionEntry {
+                best_move,
+                score: best_score,
+                depth,
+                bound
+            });
+        }
+        return best_score;
+    }
+    fn quiescence(&mut self, board: &Board, ply: usize, mut alpha: Score, beta: Score) -> Score {
+        self.pv_length[ply] = ply;
+        self.check_limits();
+        if self.is_stopped {
+            return Score::DRAW;
+        }
+        self.nodes += 1;
+
+        let moves = board.get_valid_moves();
+        if moves.is_empty() {
+            return if board.is_in_check() { Score::mated_in(ply) } else { Score::DRAW };
+        }
+        let stand_pat = self.evaluate(board, ply);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+        alpha = cmp::max(alpha, stand_pat);
+
+        let mut noisy_moves: Vec<BoardMove> = moves.into_iter()
+            .filter(|board_move| board.is_capture(board_move) || board.is_promotion(board_move))
+            .collect();
+        self.order_moves(board, &mut noisy_moves, ply, None);
+        for board_move in noisy_moves.iter() {
+            let moved_board = board.create_board_from_move(board_move);
+            self.make_move(board, &moved_board, ply);
+            let score = -self.quiescence(&moved_board, ply + 1, -beta, -alpha);
+            if self.is_stopped {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, *board_move);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        return alpha;
+    }
+}
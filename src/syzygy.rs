@@ -0,0 +1,1126 @@
+use crate::{
+    board::*,
+    score::Score
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf
+    },
+    sync::{
+        Arc,
+        OnceLock
+    }
+};
+
+/*
+a reader for the syzygy tablebase files, following the layout the generator writes them in:
+    .rtbw files hold win, draw or loss for both sides to move, .rtbz files the distance to the
+    next capture or pawn move for one side to move, both cut into blocks of huffman coded symbols
+    that each stand for a run of values built up by recursive pairing
+
+the squares here are the files' own, a1 being 0 and h8 63, and so are the pieces,
+1 to 6 from pawn to king for white and 9 to 14 for black
+*/
+pub const WDL_EXTENSION: &str = "rtbw";
+pub const DTZ_EXTENSION: &str = "rtbz";
+pub const MAX_SYZYGY_PIECES: usize = 7;
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// table flags
+const STM_FLAG: u8 = 1;
+const MAPPED_FLAG: u8 = 2;
+const WIN_PLIES_FLAG: u8 = 4;
+const LOSS_PLIES_FLAG: u8 = 8;
+const WIDE_FLAG: u8 = 16;
+const SINGLE_VALUE_FLAG: u8 = 128;
+// file header flags
+const SPLIT_FLAG: u8 = 1;
+const HAS_PAWNS_FLAG: u8 = 2;
+
+// a right hand symbol like this marks a symbol that stands for a value instead of a pair
+const LEAF_SYMBOL: u16 = 0xfff;
+// the ways three unique pieces or just the two kings can stand, once mirrored into the a1-d1-d4 triangle
+const UNIQUE_TRIPLE_COUNT: u64 = 31332;
+const KING_PAIR_COUNT: u64 = 462;
+// cursed wins and blessed losses are draws by the fifty move rule, but only just
+const CURSED_WIN_CENTIPAWNS: i32 = 2;
+const PAWN_CENTIPAWNS: i32 = 100;
+
+// from the side to move's point of view
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum Wdl {
+    Loss = -2,
+    // lost, but the fifty move rule saves it
+    BlessedLoss = -1,
+    Draw = 0,
+    // won, but not before the fifty move rule runs out
+    CursedWin = 1,
+    Win = 2
+}
+impl Wdl {
+    const fn from_value(value: i32) -> Wdl {
+        return match value {
+            i32::MIN..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win
+        };
+    }
+    pub const fn flipped(self) -> Wdl {
+        return Wdl::from_value(-(self as i32));
+    }
+    // for a position this many plies from the root
+    pub const fn to_score(self, ply: usize) -> Score {
+        return match self {
+            Wdl::Loss => Score::tablebase_loss(ply),
+            Wdl::BlessedLoss => Score::from_centipawns(-CURSED_WIN_CENTIPAWNS),
+            Wdl::Draw => Score::DRAW,
+            Wdl::CursedWin => Score::from_centipawns(CURSED_WIN_CENTIPAWNS),
+            Wdl::Win => Score::tablebase_win(ply)
+        };
+    }
+    // the distance to zeroing right before a capture or pawn move into a position with this result
+    const fn dtz_before_zeroing(self) -> i32 {
+        return match self {
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1
+        };
+    }
+}
+
+// how a root move keeps the result the tables promise, higher is better
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct RootMoveRank {
+    pub board_move: BoardMove,
+    pub rank: i32,
+    pub score: Score
+}
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct RootRanking {
+    pub moves: Vec<RootMoveRank>,
+    // ranked by distance to zeroing, which is enough to make progress without any search;
+    // otherwise only by win, draw or loss
+    pub uses_dtz: bool
+}
+
+// square and piece numbering of the files
+const fn file_of(square: u8) -> u8 {
+    return square & 7;
+}
+const fn rank_of(square: u8) -> u8 {
+    return square >> 3;
+}
+const fn off_diagonal(square: u8) -> i32 {
+    return rank_of(square) as i32 - file_of(square) as i32;
+}
+const fn to_table_square(square: &BoardSquare) -> u8 {
+    return square.pos() ^ 56;
+}
+const fn to_table_piece(piece_type: BoardPieceType, color: BoardColor) -> u8 {
+    return piece_type / 2 + if color == BLACK { 8 } else { 0 };
+}
+
+// the lookup tables every index computation shares
+struct Indexing {
+    // a2-h7 to 0..47, highest for the pawn nearest the edge and lowest in rank, which is the one that leads
+    map_pawns: [u64; 64],
+    // below the a1-h8 diagonal to 0..27
+    map_b1h1h7: [u64; 64],
+    // the a1-d1-d4 triangle to 0..9, diagonal squares last
+    map_a1d1d4: [u64; 64],
+    // the legal placements of two kings with the first in the triangle, to 0..461
+    map_kk: [[u64; 64]; 10],
+    // [k][n] ways to choose k of n
+    binomial: [[u64; 64]; MAX_SYZYGY_PIECES],
+    lead_pawn_index: [[u64; 64]; MAX_SYZYGY_PIECES],
+    lead_pawns_size: [[u64; 4]; MAX_SYZYGY_PIECES]
+}
+impl Indexing {
+    fn new() -> Indexing {
+        let mut indexing = Indexing {
+            map_pawns: [0; 64],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; MAX_SYZYGY_PIECES],
+            lead_pawn_index: [[0; 64]; MAX_SYZYGY_PIECES],
+            lead_pawns_size: [[0; 4]; MAX_SYZYGY_PIECES]
+        };
+        let mut code = 0;
+        for square in 0..64u8 {
+            if off_diagonal(square) < 0 {
+                indexing.map_b1h1h7[square as usize] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal = Vec::new();
+        code = 0;
+        for square in 0..28u8 {
+            if off_diagonal(square) < 0 && file_of(square) <= 3 {
+                indexing.map_a1d1d4[square as usize] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 && file_of(square) <= 3 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            indexing.map_a1d1d4[square as usize] = code;
+            code += 1;
+        }
+
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for first_index in 0..10 {
+            for first_square in 0..28u8 {
+                // b1 is mapped to 0 just like every square outside the triangle, so it needs telling apart
+                if indexing.map_a1d1d4[first_square as usize] != first_index || (first_index == 0 && first_square != 1) {
+                    continue;
+                }
+                for second_square in 0..64u8 {
+                    let is_touching = file_of(first_square).abs_diff(file_of(second_square)) <= 1 && rank_of(first_square).abs_diff(rank_of(second_square)) <= 1;
+                    if is_touching || (off_diagonal(first_square) == 0 && off_diagonal(second_square) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(first_square) == 0 && off_diagonal(second_square) == 0 {
+                        both_on_diagonal.push((first_index, second_square));
+                    } else {
+                        indexing.map_kk[first_index as usize][second_square as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (first_index, second_square) in both_on_diagonal {
+            indexing.map_kk[first_index as usize][second_square as usize] = code;
+            code += 1;
+        }
+
+        indexing.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_SYZYGY_PIECES.min(n + 1) {
+                indexing.binomial[k][n] = if k > 0 { indexing.binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { indexing.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut available_squares = 47;
+        for lead_pawn_count in 1..MAX_SYZYGY_PIECES - 1 {
+            for file in 0..4u8 {
+                let mut index = 0;
+                for rank in 1..7u8 {
+                    let square = rank * 8 + file;
+                    if lead_pawn_count == 1 {
+                        indexing.map_pawns[square as usize] = available_squares;
+                        indexing.map_pawns[(square ^ 7) as usize] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+                    indexing.lead_pawn_index[lead_pawn_count][square as usize] = index;
+                    index += indexing.binomial[lead_pawn_count - 1][indexing.map_pawns[square as usize] as usize];
+                }
+                indexing.lead_pawns_size[lead_pawn_count][file as usize] = index;
+            }
+        }
+        return indexing;
+    }
+}
+fn indexing() -> &'static Indexing {
+    static INDEXING: OnceLock<Indexing> = OnceLock::new();
+    return INDEXING.get_or_init(Indexing::new);
+}
+
+fn read_u8(bytes: &[u8], offset: usize) -> Option<u8> {
+    return bytes.get(offset).copied();
+}
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    return bytes.get(offset..offset + 2).map(|word| u16::from_le_bytes([word[0], word[1]]));
+}
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    return bytes.get(offset..offset + 4).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+}
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    return bytes.get(offset..offset + 4).map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]));
+}
+
+// how to decode one table of a file, there is one per side to move and per leading pawn file
+#[derive(Default, Clone, Debug)]
+struct PairsData {
+    flags: u8,
+    min_symbol_length: u8,
+    block_size: usize,
+    // there is a sparse index entry about every this many values
+    span: u64,
+    block_count: usize,
+    block_length_count: usize,
+    sparse_index_count: usize,
+    // offsets into the file
+    lowest_symbols: usize,
+    symbol_tree: usize,
+    sparse_index: usize,
+    block_lengths: usize,
+    data: usize,
+    // base64[l] is the lowest symbol of length l past the minimum, padded to 64 bits
+    base64: Vec<u64>,
+    // how many values past the first each symbol stands for
+    symbol_lengths: Vec<u8>,
+    // the order the pieces are encoded in
+    pieces: [u8; MAX_SYZYGY_PIECES],
+    group_index: [u64; MAX_SYZYGY_PIECES + 1],
+    group_length: [usize; MAX_SYZYGY_PIECES + 1],
+    // where the dtz values of wins, losses, cursed wins and blessed losses are remapped
+    map_index: [usize; 4]
+}
+impl PairsData {
+    fn symbol_pair(&self, bytes: &[u8], symbol: u16) -> Option<(u16, u16)> {
+        let offset = self.symbol_tree + symbol as usize * 3;
+        let pair_bytes = bytes.get(offset..offset + 3)?;
+        let left = ((pair_bytes[1] as u16 & 0xf) << 8) | pair_bytes[0] as u16;
+        let right = ((pair_bytes[2] as u16) << 4) | (pair_bytes[1] as u16 >> 4);
+        return Some((left, right));
+    }
+    fn symbol_length(&self, symbol: u16) -> Option<i64> {
+        return self.symbol_lengths.get(symbol as usize).map(|symbol_length| *symbol_length as i64);
+    }
+    fn block_length(&self, bytes: &[u8], block: usize) -> Option<i64> {
+        if block >= self.block_length_count {
+            return None;
+        }
+        return read_u16_le(bytes, self.block_lengths + block * 2).map(|block_length| block_length as i64);
+    }
+
+    // every symbol stands for its pair of symbols, down to the ones that stand for a single value
+    fn set_symbol_lengths(&mut self, bytes: &[u8], symbol_count: usize) -> Option<()> {
+        self.symbol_lengths = vec![0; symbol_count];
+        let mut is_visited = vec![false; symbol_count];
+        for root_symbol in 0..symbol_count {
+            let mut stack = vec![(root_symbol as u16, false)];
+            while let Some((symbol, is_expanded)) = stack.pop() {
+                let (left, right) = self.symbol_pair(bytes, symbol)?;
+                if is_expanded {
+                    self.symbol_lengths[symbol as usize] = if right == LEAF_SYMBOL {
+                        0
+                    } else {
+                        self.symbol_lengths[left as usize].wrapping_add(self.symbol_lengths[right as usize]).wrapping_add(1)
+                    };
+                    continue;
+                }
+                if is_visited[symbol as usize] {
+                    continue;
+                }
+                is_visited[symbol as usize] = true;
+                stack.push((symbol, true));
+                if right != LEAF_SYMBOL {
+                    if left as usize >= symbol_count || right as usize >= symbol_count {
+                        return None;
+                    }
+                    stack.push((left, false));
+                    stack.push((right, false));
+                }
+            }
+        }
+        return Some(());
+    }
+    // reads the sizes of the huffman code, returning the offset right after them
+    fn set_sizes(&mut self, bytes: &[u8], mut offset: usize) -> Option<usize> {
+        self.flags = read_u8(bytes, offset)?;
+        offset += 1;
+        if self.flags & SINGLE_VALUE_FLAG != 0 {
+            // every position has the same value, which is stored in place of the symbol length
+            self.min_symbol_length = read_u8(bytes, offset)?;
+            return Some(offset + 1);
+        }
+        let group_count = self.group_length.iter().position(|group_length| *group_length == 0)?;
+        let table_size = self.group_index[group_count];
+        self.block_size = 1usize.checked_shl(read_u8(bytes, offset)? as u32)?;
+        self.span = 1u64.checked_shl(read_u8(bytes, offset + 1)? as u32)?;
+        self.sparse_index_count = table_size.div_ceil(self.span) as usize;
+        let padding = read_u8(bytes, offset + 2)? as usize;
+        self.block_count = read_u32_le(bytes, offset + 3)? as usize;
+        // padded so the sparse index never points past the end
+        self.block_length_count = self.block_count + padding;
+        let max_symbol_length = read_u8(bytes, offset + 7)?;
+        self.min_symbol_length = read_u8(bytes, offset + 8)?;
+        offset += 9;
+        if max_symbol_length < self.min_symbol_length || max_symbol_length > 64 {
+            return None;
+        }
+        self.lowest_symbols = offset;
+
+        // longer codes have lower values, so base64[l] >= base64[l + 1] once padded to 64 bits
+        let length_count = (max_symbol_length - self.min_symbol_length + 1) as usize;
+        self.base64 = vec![0; length_count];
+        for length_index in (0..length_count - 1).rev() {
+            let lowest_symbol = read_u16_le(bytes, self.lowest_symbols + length_index * 2)? as u64;
+            let next_lowest_symbol = read_u16_le(bytes, self.lowest_symbols + (length_index + 1) * 2)? as u64;
+            self.base64[length_index] = self.base64[length_index + 1].wrapping_add(lowest_symbol).wrapping_sub(next_lowest_symbol) / 2;
+        }
+        for (length_index, base) in self.base64.iter_mut().enumerate() {
+            *base = base.checked_shl((64 - length_index - self.min_symbol_length as usize) as u32).unwrap_or(0);
+        }
+        offset += length_count * 2;
+
+        let symbol_count = read_u16_le(bytes, offset)? as usize;
+        offset += 2;
+        self.symbol_tree = offset;
+        self.set_symbol_lengths(bytes, symbol_count)?;
+        return Some(offset + symbol_count * 3 + (symbol_count & 1));
+    }
+    // the groups of pieces that get encoded together, and where each group's part of the index starts
+    fn set_groups(&mut self, material: &SyzygyMaterial, order: [usize; 2], file: usize) {
+        let indexing = indexing();
+        let mut group_count = 0;
+        // the leading group is the first three unique pieces, or just the kings, and with pawns the leading pawns
+        let mut first_group_length: i32 = if material.has_pawns { 0 } else if material.has_unique_pieces { 3 } else { 2 };
+        self.group_length[0] = 1;
+        for piece_index in 1..material.piece_count {
+            first_group_length -= 1;
+            if first_group_length > 0 || self.pieces[piece_index] == self.pieces[piece_index - 1] {
+                self.group_length[group_count] += 1;
+            } else {
+                group_count += 1;
+                self.group_length[group_count] = 1;
+            }
+        }
+        group_count += 1;
+        self.group_length[group_count] = 0;
+
+        // the groups are not always encoded in the order they come in
+        let has_both_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+        let mut next_group = if has_both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - self.group_length[0] - if has_both_pawns { self.group_length[1] } else { 0 };
+        let mut index = 1u64;
+        let mut slot = 0;
+        while next_group < group_count || slot == order[0] || slot == order[1] {
+            if slot == order[0] {
+                self.group_index[0] = index;
+                index *= if material.has_pawns {
+                    indexing.lead_pawns_size[self.group_length[0]][file]
+                } else if material.has_unique_pieces {
+                    UNIQUE_TRIPLE_COUNT
+                } else {
+                    KING_PAIR_COUNT
+                };
+            } else if slot == order[1] {
+                self.group_index[1] = index;
+                index *= indexing.binomial[self.group_length[1]][48 - self.group_length[0]];
+            } else {
+                self.group_index[next_group] = index;
+                index *= indexing.binomial[self.group_length[next_group]][free_squares];
+                free_squares -= self.group_length[next_group];
+                next_group += 1;
+            }
+            slot += 1;
+        }
+        self.group_index[group_count] = index;
+    }
+
+    // the value at an index, found by walking the blocks and expanding symbols
+    fn decompress(&self, bytes: &[u8], index: u64) -> Option<i32> {
+        if self.flags & SINGLE_VALUE_FLAG != 0 {
+            return Some(self.min_symbol_length as i32);
+        }
+        // the sparse index knows the block of the value at k * span + span / 2, start from the closest one
+        let sparse_entry = (index / self.span) as usize;
+        if sparse_entry >= self.sparse_index_count {
+            return None;
+        }
+        let mut block = read_u32_le(bytes, self.sparse_index + sparse_entry * 6)? as usize;
+        let mut offset = read_u16_le(bytes, self.sparse_index + sparse_entry * 6 + 4)? as i64;
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += self.block_length(bytes, block)? + 1;
+        }
+        while offset > self.block_length(bytes, block)? {
+            offset -= self.block_length(bytes, block)? + 1;
+            block += 1;
+        }
+
+        // each block starts with a new symbol, skip symbols until the one holding the value
+        let mut data_offset = self.data + block * self.block_size;
+        let mut buffer = ((read_u32_be(bytes, data_offset)? as u64) << 32) | read_u32_be(bytes, data_offset + 4)? as u64;
+        data_offset += 8;
+        let mut buffer_length: usize = 64;
+        let mut symbol;
+        loop {
+            let mut length_index = 0;
+            while buffer < *self.base64.get(length_index)? {
+                length_index += 1;
+            }
+            let symbol_length = length_index + self.min_symbol_length as usize;
+            symbol = (buffer - self.base64[length_index]).checked_shr(64u32.checked_sub(symbol_length as u32)?)? as u16;
+            symbol = symbol.wrapping_add(read_u16_le(bytes, self.lowest_symbols + length_index * 2)?);
+            if offset < self.symbol_length(symbol)? + 1 {
+                break;
+            }
+            offset -= self.symbol_length(symbol)? + 1;
+            buffer = buffer.checked_shl(symbol_length as u32).unwrap_or(0);
+            buffer_length = buffer_length.checked_sub(symbol_length)?;
+            if buffer_length <= 32 {
+                buffer_length += 32;
+                buffer |= (read_u32_be(bytes, data_offset)? as u64) << (64 - buffer_length);
+                data_offset += 4;
+            }
+        }
+
+        // pairs are adjacent runs, so the value is in whichever half the offset falls in
+        while self.symbol_length(symbol)? != 0 {
+            let (left, right) = self.symbol_pair(bytes, symbol)?;
+            if offset < self.symbol_length(left)? + 1 {
+                symbol = left;
+            } else {
+                offset -= self.symbol_length(left)? + 1;
+                symbol = right;
+            }
+        }
+        return self.symbol_pair(bytes, symbol).map(|(left, _)| left as i32);
+    }
+}
+
+// one .rtbw or .rtbz file, read into memory the first time it gets probed
+struct TableFile {
+    bytes: Vec<u8>,
+    // indexed by side to move and then by the file of the leading pawn
+    pairs: Vec<Vec<PairsData>>,
+    // the start of the dtz value maps
+    dtz_map: usize
+}
+impl TableFile {
+    fn read(path: &Path, material: &SyzygyMaterial, is_dtz: bool) -> io::Result<TableFile> {
+        let bytes = fs::read(path)?;
+        return TableFile::parse(bytes, material, is_dtz)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a valid table", path.display())));
+    }
+    fn parse(bytes: Vec<u8>, material: &SyzygyMaterial, is_dtz: bool) -> Option<TableFile> {
+        let magic = if is_dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        if bytes.get(0..4)? != magic {
+            return None;
+        }
+        let header_flags = read_u8(&bytes, 4)?;
+        if (header_flags & HAS_PAWNS_FLAG != 0) != material.has_pawns || (header_flags & SPLIT_FLAG != 0) == material.is_symmetric() {
+            return None;
+        }
+        let mut offset = 5;
+        // a symmetric win draw loss table, and every dtz table, only has one side to move
+        let side_count = if !is_dtz && !material.is_symmetric() { 2 } else { 1 };
+        let file_count = if material.has_pawns { 4 } else { 1 };
+        let has_both_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+        let mut pairs = vec![vec![PairsData::default(); file_count]; side_count];
+        for file in 0..file_count {
+            let order_byte = read_u8(&bytes, offset)? as usize;
+            let pawn_order_byte = if has_both_pawns { read_u8(&bytes, offset + 1)? as usize } else { 0xff };
+            let orders = [[order_byte & 0xf, pawn_order_byte & 0xf], [order_byte >> 4, pawn_order_byte >> 4]];
+            offset += if has_both_pawns { 2 } else { 1 };
+            for piece_index in 0..material.piece_count {
+                let piece_byte = read_u8(&bytes, offset + piece_index)?;
+                for (side, side_pairs) in pairs.iter_mut().enumerate() {
+                    side_pairs[file].pieces[piece_index] = if side == 1 { piece_byte >> 4 } else { piece_byte & 0xf };
+                }
+            }
+            offset += material.piece_count;
+            for (side, side_pairs) in pairs.iter_mut().enumerate() {
+                side_pairs[file].set_groups(material, orders[side], file);
+            }
+        }
+        offset += offset & 1;
+
+        for file in 0..file_count {
+            for side_pairs in pairs.iter_mut() {
+                offset = side_pairs[file].set_sizes(&bytes, offset)?;
+            }
+        }
+        let dtz_map = offset;
+        if is_dtz {
+            for file_pairs in pairs[0].iter_mut() {
+                if file_pairs.flags & MAPPED_FLAG == 0 {
+                    continue;
+                }
+                if file_pairs.flags & WIDE_FLAG != 0 {
+                    offset += offset & 1;
+                    for map_index in file_pairs.map_index.iter_mut() {
+                        *map_index = (offset - dtz_map) / 2 + 1;
+                        offset += 2 * read_u16_le(&bytes, offset)? as usize + 2;
+                    }
+                } else {
+                    for map_index in file_pairs.map_index.iter_mut() {
+                        *map_index = offset - dtz_map + 1;
+                        offset += read_u8(&bytes, offset)? as usize + 1;
+                    }
+                }
+            }
+            offset += offset & 1;
+        }
+        for file in 0..file_count {
+            for side_pairs in pairs.iter_mut() {
+                side_pairs[file].sparse_index = offset;
+                offset += side_pairs[file].sparse_index_count * 6;
+            }
+        }
+        for file in 0..file_count {
+            for side_pairs in pairs.iter_mut() {
+                side_pairs[file].block_lengths = offset;
+                offset += side_pairs[file].block_length_count * 2;
+            }
+        }
+        for file in 0..file_count {
+            for side_pairs in pairs.iter_mut() {
+                // blocks are aligned to 64 bytes
+                offset = (offset + 0x3f) & !0x3f;
+                side_pairs[file].data = offset;
+                offset += side_pairs[file].block_count * side_pairs[file].block_size;
+            }
+        }
+        if offset > bytes.len() {
+            return None;
+        }
+        return Some(TableFile {
+            bytes,
+            pairs,
+            dtz_map
+        });
+    }
+    // dtz values are stored by how often they come up, and some in full moves instead of plies
+    fn map_dtz(&self, file: usize, value: i32, wdl: Wdl) -> Option<i32> {
+        let pairs = &self.pairs[0][file];
+        let mut value = value;
+        if pairs.flags & MAPPED_FLAG != 0 {
+            let map_slot = match wdl {
+                Wdl::Win | Wdl::Draw => 0,
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                Wdl::BlessedLoss => 3
+            };
+            let map_index = pairs.map_index[map_slot] + value as usize;
+            value = if pairs.flags & WIDE_FLAG != 0 {
+                read_u16_le(&self.bytes, self.dtz_map + map_index * 2)? as i32
+            } else {
+                read_u8(&self.bytes, self.dtz_map + map_index)? as i32
+            };
+        }
+        let is_in_moves = match wdl {
+            Wdl::Win => pairs.flags & WIN_PLIES_FLAG == 0,
+            Wdl::Loss => pairs.flags & LOSS_PLIES_FLAG == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false
+        };
+        return Some(if is_in_moves { value * 2 } else { value } + 1);
+    }
+}
+
+// the pieces of a table, named like KRvKP with white's pieces first
+struct SyzygyMaterial {
+    name: String,
+    swapped_name: String,
+    piece_count: usize,
+    has_pawns: bool,
+    // a piece that is the only one of its kind and color, kings aside
+    has_unique_pieces: bool,
+    // the leading color's pawns, then the other color's
+    pawn_counts: [usize; 2]
+}
+impl SyzygyMaterial {
+    fn parse(name: &str) -> Option<SyzygyMaterial> {
+        let (white_name, black_name) = name.split_once('v')?;
+        let is_valid_side = |side_name: &str| {
+            return side_name.starts_with('K') && side_name[1..].chars().all(|piece_char| "QRBNP".contains(piece_char));
+        };
+        if !is_valid_side(white_name) || !is_valid_side(black_name) {
+            return None;
+        }
+        let count = |side_name: &str, piece_char: char| side_name.chars().filter(|side_char| *side_char == piece_char).count();
+        let has_unique_pieces = [white_name, black_name].iter().any(|side_name| "QRBNP".chars().any(|piece_char| count(side_name, piece_char) == 1));
+        let white_pawns = count(white_name, 'P');
+        let black_pawns = count(black_name, 'P');
+        // the side with fewer pawns leads, which compresses better
+        let is_white_leading = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let piece_count = white_name.len() + black_name.len();
+        if piece_count > MAX_SYZYGY_PIECES {
+            return None;
+        }
+        return Some(SyzygyMaterial {
+            name: name.to_string(),
+            swapped_name: format!("{}v{}", black_name, white_name),
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_counts: if is_white_leading { [white_pawns, black_pawns] } else { [black_pawns, white_pawns] }
+        });
+    }
+    fn is_symmetric(&self) -> bool {
+        return self.name == self.swapped_name;
+    }
+}
+
+struct SyzygyTable {
+    material: SyzygyMaterial,
+    wdl_path: PathBuf,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<TableFile>>,
+    dtz: OnceLock<Option<TableFile>>
+}
+impl SyzygyTable {
+    fn wdl_file(&self) -> Option<&TableFile> {
+        return self.wdl.get_or_init(|| TableFile::read(&self.wdl_path, &self.material, false).ok()).as_ref();
+    }
+    fn dtz_file(&self) -> Option<&TableFile> {
+        let dtz_path = self.dtz_path.as_ref()?;
+        return self.dtz.get_or_init(|| TableFile::read(dtz_path, &self.material, true).ok()).as_ref();
+    }
+}
+
+// what a table lookup found
+enum TableValue {
+    Value(i32, usize),
+    // dtz tables only store one side to move, and this is the other one
+    WrongSideToMove
+}
+
+// the result of looking at a position's captures along with the table
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum ProbeState {
+    Ok,
+    // the best move is a capture or pawn move, so the dtz table does not know the position
+    ZeroingBestMove
+}
+
+// the pieces with their squares in the files' numbering, in square order
+fn table_pieces(board_pieces: &BoardPieces) -> Vec<(u8, u8)> {
+    let mut pieces = Vec::with_capacity(MAX_SYZYGY_PIECES);
+    for color in [BLACK, WHITE] {
+        let color_index = color as usize;
+        let piece_lists = [
+            (PAWN, &board_pieces.pawns[color_index]),
+            (KNIGHT, &board_pieces.knights[color_index]),
+            (BISHOP, &board_pieces.bishops[color_index]),
+            (ROOK, &board_pieces.rooks[color_index]),
+            (QUEEN, &board_pieces.queens[color_index])
+        ];
+        for (piece_type, piece_squares) in piece_lists.iter() {
+            pieces.extend(piece_squares.iter().map(|square| (to_table_piece(*piece_type, color), to_table_square(square))));
+        }
+        if let Some(king_square) = &board_pieces.kings[color_index] {
+            pieces.push((to_table_piece(KING, color), to_table_square(king_square)));
+        }
+    }
+    pieces.sort_by_key(|(_, square)| *square);
+    return pieces;
+}
+fn material_name(board_pieces: &BoardPieces) -> String {
+    let side_name = |color: usize| {
+        return format!(
+            "K{}{}{}{}{}",
+            "Q".repeat(board_pieces.queens[color].len()),
+            "R".repeat(board_pieces.rooks[color].len()),
+            "B".repeat(board_pieces.bishops[color].len()),
+            "N".repeat(board_pieces.knights[color].len()),
+            "P".repeat(board_pieces.pawns[color].len())
+        );
+    };
+    return format!("{}v{}", side_name(WHITE as usize), side_name(BLACK as usize));
+}
+fn is_pawn_move(board: &Board, board_move: &BoardMove) -> bool {
+    return board.get_piece_at(&board_move.from_square()).map(get_piece_type) == Some(PAWN);
+}
+// plies since the last capture or pawn move, and whether a position came up twice since then
+fn fifty_move_state(board: &Board, history: &[Board]) -> (i32, bool) {
+    let mut positions = vec![board];
+    for previous_board in history.iter().rev() {
+        let next_board = positions[positions.len() - 1];
+        let is_zeroing = previous_board.piece_count() != next_board.piece_count()
+            || previous_board.get_pieces().pawns != next_board.get_pieces().pawns;
+        if is_zeroing {
+            break;
+        }
+        positions.push(previous_board);
+    }
+    let has_repeated = positions.iter().enumerate().any(|(position_index, position)| positions[position_index + 1..].contains(position));
+    return (positions.len() as i32 - 1, has_repeated);
+}
+
+// the syzygy tables found in a set of directories
+#[derive(Default)]
+pub struct Syzygy {
+    // under both the name and the colour swapped name
+    tables: HashMap<String, Arc<SyzygyTable>>,
+    max_pieces: usize
+}
+impl Syzygy {
+    pub fn new() -> Syzygy {
+        return Syzygy::default();
+    }
+    // every table in the directories, separated like the PATH variable, returning how many there were;
+    // the files only get read once they are needed
+    pub fn load(&mut self, paths: &str) -> io::Result<usize> {
+        let mut file_paths: HashMap<(String, String), PathBuf> = HashMap::new();
+        for directory in paths.split(if cfg!(windows) { ';' } else { ':' }).filter(|directory| !directory.is_empty()) {
+            for dir_entry in fs::read_dir(directory)? {
+                let path = dir_entry?.path();
+                let stem = path.file_stem().and_then(|stem| stem.to_str()).map(|stem| stem.to_string());
+                let extension = path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_string());
+                if let (Some(stem), Some(extension)) = (stem, extension) {
+                    file_paths.entry((stem, extension)).or_insert(path);
+                }
+            }
+        }
+        let mut table_count = 0;
+        for ((stem, extension), wdl_path) in file_paths.iter() {
+            if extension != WDL_EXTENSION {
+                continue;
+            }
+            let material = match SyzygyMaterial::parse(stem) {
+                Some(material) => material,
+                None => continue
+            };
+            self.max_pieces = self.max_pieces.max(material.piece_count);
+            let names = [material.name.clone(), material.swapped_name.clone()];
+            let table = Arc::new(SyzygyTable {
+                material,
+                wdl_path: wdl_path.clone(),
+                dtz_path: file_paths.get(&(stem.clone(), DTZ_EXTENSION.to_string())).cloned(),
+                wdl: OnceLock::new(),
+                dtz: OnceLock::new()
+            });
+            for name in names.iter() {
+                self.tables.insert(name.clone(), table.clone());
+            }
+            table_count += 1;
+        }
+        return Ok(table_count);
+    }
+    // the most pieces, kings included, of any table
+    pub fn max_pieces(&self) -> usize {
+        return self.max_pieces;
+    }
+    fn can_probe(&self, board: &Board) -> bool {
+        return board.piece_count() as usize <= self.max_pieces
+            && !board.castle_availibility().iter().flatten().any(|can_castle| *can_castle);
+    }
+
+    // the index of the position in the table, mirrored to the way it is stored
+    fn table_value(&self, table: &SyzygyTable, table_file: &TableFile, board: &Board, is_dtz: bool) -> Option<TableValue> {
+        let indexing = indexing();
+        let material = &table.material;
+        let board_pieces = board.get_pieces();
+        // tables are stored with white as the stronger side, and symmetric ones only with white to move
+        let is_symmetric_black_to_move = material.is_symmetric() && board.active_color() == BLACK;
+        let is_black_stronger = material_name(&board_pieces) != material.name;
+        let is_flipped = is_symmetric_black_to_move || is_black_stronger;
+        let flip_color = if is_flipped { 8 } else { 0 };
+        let flip_squares = if is_flipped { 56 } else { 0 };
+        let side_to_move = usize::from(is_flipped) ^ usize::from(board.active_color() == BLACK);
+
+        let mut squares = [0u8; MAX_SYZYGY_PIECES];
+        let mut pieces = [0u8; MAX_SYZYGY_PIECES];
+        let mut piece_count = 0;
+        let mut lead_pawn_count = 0;
+        let mut table_pawn_file = 0;
+        let position_pieces = table_pieces(&board_pieces);
+        let lead_pawn = if material.has_pawns { Some(table_file.pairs[0][0].pieces[0] ^ flip_color) } else { None };
+        if let Some(lead_pawn) = lead_pawn {
+            // the tables are split by the file of the leading pawn, the one nearest the edge
+            for (piece, square) in position_pieces.iter().filter(|(piece, _)| *piece == lead_pawn) {
+                squares[piece_count] = square ^ flip_squares;
+                pieces[piece_count] = piece ^ flip_color;
+                piece_count += 1;
+            }
+            lead_pawn_count = piece_count;
+            let lead_index = (0..lead_pawn_count).rev().max_by_key(|pawn_index| indexing.map_pawns[squares[*pawn_index] as usize])?;
+            squares.swap(0, lead_index);
+            table_pawn_file = file_of(squares[0]).min(7 - file_of(squares[0])) as usize;
+        }
+        if is_dtz {
+            let flags = table_file.pairs[0][table_pawn_file].flags;
+            // symmetric pawnless tables are the same either way round
+            let has_one_side = material.has_pawns || !material.is_symmetric();
+            if has_one_side && (flags & STM_FLAG) as usize != side_to_move {
+                return Some(TableValue::WrongSideToMove);
+            }
+        }
+        for (piece, square) in position_pieces.iter().filter(|(piece, _)| Some(*piece) != lead_pawn) {
+            squares[piece_count] = square ^ flip_squares;
+            pieces[piece_count] = piece ^ flip_color;
+            piece_count += 1;
+        }
+        if piece_count != material.piece_count {
+            return None;
+        }
+        let side_pairs = &table_file.pairs[side_to_move % table_file.pairs.len()];
+        let pairs = &side_pairs[table_pawn_file];
+
+        // put the pieces in the order the table encodes them in
+        for piece_index in lead_pawn_count..piece_count.saturating_sub(1) {
+            if let Some(other_index) = (piece_index + 1..piece_count).find(|other_index| pieces[*other_index] == pairs.pieces[piece_index]) {
+                pieces.swap(piece_index, other_index);
+                squares.swap(piece_index, other_index);
+            }
+        }
+        let squares = &mut squares[..piece_count];
+        if file_of(squares[0]) > 3 {
+            for square in squares.iter_mut() {
+                *square ^= 7;
+            }
+        }
+
+        let mut index;
+        if material.has_pawns {
+            index = indexing.lead_pawn_index[lead_pawn_count][squares[0] as usize];
+            squares[1..lead_pawn_count].sort_by_key(|square| indexing.map_pawns[*square as usize]);
+            for (pawn_index, square) in squares.iter().enumerate().take(lead_pawn_count).skip(1) {
+                index += indexing.binomial[pawn_index][indexing.map_pawns[*square as usize] as usize];
+            }
+        } else {
+            if rank_of(squares[0]) > 3 {
+                for square in squares.iter_mut() {
+                    *square ^= 56;
+                }
+            }
+            // the first piece of the leading group off the a1-h8 diagonal goes below it
+            for piece_index in 0..pairs.group_length[0] {
+                let offset = off_diagonal(squares[piece_index]);
+                if offset == 0 {
+                    continue;
+                }
+                if offset > 0 {
+                    for square in squares[piece_index..].iter_mut() {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+                break;
+            }
+            index = if material.has_unique_pieces {
+                let [first, second, third] = [squares[0], squares[1], squares[2]];
+                let adjust1 = u64::from(second > first);
+                let adjust2 = u64::from(third > first) + u64::from(third > second);
+                if off_diagonal(first) != 0 {
+                    (indexing.map_a1d1d4[first as usize] * 63 + (second as u64 - adjust1)) * 62 + third as u64 - adjust2
+                } else if off_diagonal(second) != 0 {
+                    (6 * 63 + rank_of(first) as u64 * 28 + indexing.map_b1h1h7[second as usize]) * 62 + third as u64 - adjust2
+                } else if off_diagonal(third) != 0 {
+                    6 * 63 * 62 + 4 * 28 * 62
+                        + rank_of(first) as u64 * 7 * 28
+                        + (rank_of(second) as u64 - adjust1) * 28
+                        + indexing.map_b1h1h7[third as usize]
+                } else {
+                    6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28
+                        + rank_of(first) as u64 * 7 * 6
+                        + (rank_of(second) as u64 - adjust1) * 6
+                        + (rank_of(third) as u64 - adjust2)
+                }
+            } else {
+                indexing.map_kk[indexing.map_a1d1d4[squares[0] as usize] as usize][squares[1] as usize]
+            };
+        }
+
+        // the other groups by square, each square shifted down past the squares of the groups before it
+        index *= pairs.group_index[0];
+        let mut group_start = pairs.group_length[0];
+        let mut has_remaining_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+        for group in 1..=MAX_SYZYGY_PIECES {
+            let group_length = pairs.group_length[group];
+            if group_length == 0 {
+                break;
+            }
+            squares[group_start..group_start + group_length].sort_unstable();
+            let mut group_index = 0;
+            for piece_index in 0..group_length {
+                let square = squares[group_start + piece_index];
+                let adjust = squares[..group_start].iter().filter(|other_square| square > **other_square).count();
+                let free_square = (square as usize).checked_sub(adjust + if has_remaining_pawns { 8 } else { 0 })?;
+                group_index += indexing.binomial[piece_index + 1][free_square];
+            }
+            has_remaining_pawns = false;
+            index += group_index * pairs.group_index[group];
+            group_start += group_length;
+        }
+        return pairs.decompress(&table_file.bytes, index).map(|value| TableValue::Value(value, table_pawn_file));
+    }
+    fn probe_wdl_table(&self, board: &Board) -> Option<Wdl> {
+        if board.piece_count() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let table = self.tables.get(&material_name(&board.get_pieces()))?;
+        return match self.table_value(table, table.wdl_file()?, board, false)? {
+            TableValue::Value(value, _) => Some(Wdl::from_value(value - 2)),
+            TableValue::WrongSideToMove => None
+        };
+    }
+    fn probe_dtz_table(&self, board: &Board, wdl: Wdl) -> Option<Option<i32>> {
+        let table = self.tables.get(&material_name(&board.get_pieces()))?;
+        let dtz_file = table.dtz_file()?;
+        return match self.table_value(table, dtz_file, board, true)? {
+            TableValue::Value(value, file) => Some(Some(dtz_file.map_dtz(file, value, wdl)?)),
+            TableValue::WrongSideToMove => Some(None)
+        };
+    }
+    // the tables may store anything for positions a capture decides, so the captures get looked at too;
+    // with zeroing moves checked, pawn moves count like captures, since dtz tables do not know those either
+    fn search(&self, board: &Board, is_checking_zeroing_moves: bool) -> Option<(Wdl, ProbeState)> {
+        let valid_moves = board.get_valid_moves();
+        let mut best_wdl = Wdl::Loss;
+        let mut searched_count = 0;
+        for valid_move in valid_moves.iter() {
+            let is_zeroing = board.is_capture(valid_move) || (is_checking_zeroing_moves && is_pawn_move(board, valid_move));
+            if !is_zeroing {
+                continue;
+            }
+            searched_count += 1;
+            let (child_wdl, _) = self.search(&board.create_board_from_move(valid_move), false)?;
+            let wdl = child_wdl.flipped();
+            if wdl > best_wdl {
+                best_wdl = wdl;
+                if wdl == Wdl::Win {
+                    return Some((wdl, ProbeState::ZeroingBestMove));
+                }
+            }
+        }
+        let has_no_other_moves = searched_count > 0 && searched_count == valid_moves.len();
+        let wdl = if has_no_other_moves { best_wdl } else { self.probe_wdl_table(board)? };
+        if best_wdl >= wdl {
+            let state = if best_wdl > Wdl::Draw || has_no_other_moves { ProbeState::ZeroingBestMove } else { ProbeState::Ok };
+            return Some((best_wdl, state));
+        }
+        return Some((wdl, ProbeState::Ok));
+    }
+
+    // win, draw or loss for the side to move, ignoring the fifty move counter of the position itself
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        return self.search(board, false).map(|(wdl, _)| wdl);
+    }
+    // plies to the next capture or pawn move that keeps the result, positive when winning;
+    // rounded up for the few tables that count full moves
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        let (wdl, state) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if state == ProbeState::ZeroingBestMove {
+            return Some(wdl.dtz_before_zeroing());
+        }
+        let sign = (wdl as i32).signum();
+        if let Some(dtz) = self.probe_dtz_table(board, wdl)? {
+            let cursed_offset = if wdl == Wdl::CursedWin || wdl == Wdl::BlessedLoss { 100 } else { 0 };
+            return Some((dtz + cursed_offset) * sign);
+        }
+
+        // the table only has the other side to move, so look one move further
+        let mut min_dtz = i32::MAX;
+        for valid_move in board.get_valid_moves() {
+            let is_zeroing = board.is_capture(&valid_move) || is_pawn_move(board, &valid_move);
+            let child_board = board.create_board_from_move(&valid_move);
+            let mut dtz = if is_zeroing {
+                -self.search(&child_board, false)?.0.dtz_before_zeroing()
+            } else {
+                -self.probe_dtz(&child_board)?
+            };
+            if dtz == 1 && child_board.is_in_check() && child_board.get_valid_moves().is_empty() {
+                min_dtz = 1;
+            }
+            if !is_zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == sign {
+                min_dtz = dtz;
+            }
+        }
+        // without any moves it is mate
+        return Some(if min_dtz == i32::MAX { -1 } else { min_dtz });
+    }
+
+    // ranks the root moves by the result they keep, so the search only needs to look at the best ones;
+    // history is the positions played before, which tells how close the fifty move rule is
+    pub fn rank_root_moves(&self, board: &Board, history: &[Board], allowed_moves: &[BoardMove]) -> Option<RootRanking> {
+        if !self.can_probe(board) {
+            return None;
+        }
+        let root_moves = board.get_valid_moves().into_iter()
+            .filter(|valid_move| allowed_moves.is_empty() || allowed_moves.contains(valid_move))
+            .collect::<Vec<BoardMove>>();
+        if root_moves.is_empty() {
+            return None;
+        }
+        if let Some(moves) = self.rank_root_moves_by_dtz(board, history, &root_moves) {
+            return Some(RootRanking {
+                moves,
+                uses_dtz: true
+            });
+        }
+        // a win, draw or loss is the most the tables can say without the dtz files
+        let moves = root_moves.iter().map(|root_move| {
+            let wdl = self.probe_wdl(&board.create_board_from_move(root_move))?.flipped();
+            let rank = match wdl {
+                Wdl::Loss => -1000,
+                Wdl::BlessedLoss => -899,
+                Wdl::Draw => 0,
+                Wdl::CursedWin => 899,
+                Wdl::Win => 1000
+            };
+            return Some(RootMoveRank {
+                board_move: *root_move,
+                rank,
+                score: wdl.to_score(0)
+            });
+        }).collect::<Option<Vec<RootMoveRank>>>()?;
+        return Some(RootRanking {
+            moves,
+            uses_dtz: false
+        });
+    }
+    fn rank_root_moves_by_dtz(&self, board: &Board, history: &[Board], root_moves: &[BoardMove]) -> Option<Vec<RootMoveRank>> {
+        let (plies_since_zeroing, has_repeated) = fifty_move_state(board, history);
+        let mut ranked_moves = Vec::with_capacity(root_moves.len());
+        for root_move in root_moves.iter() {
+            let child_board = board.create_board_from_move(root_move);
+            let is_zeroing = board.is_capture(root_move) || is_pawn_move(board, root_move);
+            let mut dtz = if is_zeroing {
+                self.probe_wdl(&child_board)?.flipped().dtz_before_zeroing()
+            } else {
+                let child_dtz = -self.probe_dtz(&child_board)?;
+                child_dtz + child_dtz.signum()
+            };
+            if dtz == 2 && child_board.is_in_check() && child_board.get_valid_moves().is_empty() {
+                dtz = 1;
+            }
+            // wins within the fifty move rule are all the same, losses only differ when a fifty move draw is in sight
+            let rank = if dtz > 0 {
+                if dtz + plies_since_zeroing <= 99 && !has_repeated { 1000 } else { 1000 - (dtz + plies_since_zeroing) }
+            } else if dtz < 0 {
+                if -dtz * 2 + plies_since_zeroing < 100 { -1000 } else { -1000 + (-dtz + plies_since_zeroing) }
+            } else {
+                0
+            };
+            // cursed wins get at least a little, growing as the real win comes closer
+            let score = if rank >= 900 {
+                Score::tablebase_win(0)
+            } else if rank > 0 {
+                Score::from_centipawns((rank - 800).max(3) * PAWN_CENTIPAWNS / 200)
+            } else if rank == 0 {
+                Score::DRAW
+            } else if rank > -900 {
+                Score::from_centipawns((rank + 800).min(-3) * PAWN_CENTIPAWNS / 200)
+            } else {
+                Score::tablebase_loss(0)
+            };
+            ranked_moves.push(RootMoveRank {
+                board_move: *root_move,
+                rank,
+                score
+            });
+        }
+        return Some(ranked_moves);
+    }
+}
@@ -0,0 +1,573 @@
+use crate::{
+    board::*,
+    score::Score
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::Path
+};
+
+// kings included; every table stores one byte for each placement of its pieces, so four pieces already take
+// up to 16 MB and a fifth would multiply that by 64, well past what generating in memory can afford
+pub const MAX_TABLEBASE_PIECES: usize = 4;
+pub const TABLEBASE_EXTENSION: &str = "atb";
+const TABLEBASE_MAGIC: [u8; 4] = *b"APTB";
+const TABLEBASE_VERSION: u32 = 1;
+
+/*
+table file format, every number little endian:
+    offset  size    field
+    0       4       magic, the bytes "APTB"
+    4       4       version, u32, currently 1
+    8       4       name length, u32
+    12      ...     material name, like KRKP with white's pieces first
+    ...     4       value count, u32
+    ...     ...     one value byte per index
+
+a value byte is the distance to mate in plies plus one, wins being odd distances and losses even ones;
+0 is a draw and 255 an index that is not a legal position, or is only stored under a symmetric index
+
+index = ((king region index * 64 + square of every other piece in table order...) * 2 + side to move
+    table order is the white king, the black king, white's pieces and then black's, most valuable first
+    boards are mirrored so the white king lands in its region; pawnless tables use all eight symmetries,
+    which leaves the ten squares a8 b8 c8 d8 b7 c7 d7 c6 d6 d5, and tables with pawns only flip files
+    identical pieces are sorted by square, so every position has exactly one index
+*/
+const DRAW_VALUE: u8 = 0;
+// distances past this do not fit in a value byte
+const MAX_MATE_PLIES: u8 = 252;
+// only while generating
+const UNKNOWN_VALUE: u8 = 254;
+const INVALID_VALUE: u8 = 255;
+
+const PIECE_ORDER: [BoardPieceType; 5] = [QUEEN, ROOK, BISHOP, KNIGHT, PAWN];
+const PAWNLESS_KING_SQUARES: [u8; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+// children that are not positions of the table being generated have a known result, kept in the low byte
+const KNOWN_CHILD_FLAG: u32 = 1 << 31;
+
+// from the side to move's point of view, with the plies until mate
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TablebaseResult {
+    Win(u8),
+    Draw,
+    Loss(u8)
+}
+impl TablebaseResult {
+    const fn from_value(value: u8) -> Option<TablebaseResult> {
+        return match value {
+            DRAW_VALUE => Some(TablebaseResult::Draw),
+            UNKNOWN_VALUE | INVALID_VALUE => None,
+            _ if (value - 1) % 2 == 1 => Some(TablebaseResult::Win(value - 1)),
+            _ => Some(TablebaseResult::Loss(value - 1))
+        };
+    }
+    const fn to_value(self) -> u8 {
+        return match self {
+            TablebaseResult::Win(plies) | TablebaseResult::Loss(plies) => plies + 1,
+            TablebaseResult::Draw => DRAW_VALUE
+        };
+    }
+    // for a position this many plies from the root
+    pub const fn to_score(self, ply: usize) -> Score {
+        return match self {
+            TablebaseResult::Win(plies) => Score::mate_in(ply + plies as usize),
+            TablebaseResult::Draw => Score::DRAW,
+            TablebaseResult::Loss(plies) => Score::mated_in(ply + plies as usize)
+        };
+    }
+    // how much the side to move likes a move into a position with this result, higher is better
+    fn preference(child_result: TablebaseResult) -> i32 {
+        return match child_result {
+            TablebaseResult::Loss(plies) => 1000 - plies as i32,
+            TablebaseResult::Draw => 0,
+            TablebaseResult::Win(plies) => -1000 + plies as i32
+        };
+    }
+}
+// the result of a position with at least one move, from the results of the positions its moves lead to
+fn best_of(child_results: impl Iterator<Item = TablebaseResult>) -> Option<TablebaseResult> {
+    let best_child_result = child_results.max_by_key(|child_result| TablebaseResult::preference(*child_result))?;
+    return Some(match best_child_result {
+        TablebaseResult::Loss(plies) => TablebaseResult::Win(plies + 1),
+        TablebaseResult::Draw => TablebaseResult::Draw,
+        TablebaseResult::Win(plies) => TablebaseResult::Loss(plies + 1)
+    });
+}
+
+// the pieces besides the kings, indexed by color, most valuable first
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Material {
+    pieces: [Vec<BoardPieceType>; 2]
+}
+impl Material {
+    // from a name like KRKP, white's pieces coming first
+    pub fn parse(name: &str) -> Option<Material> {
+        let name = name.to_ascii_uppercase();
+        let side_names = name.strip_prefix('K')?.split('K').collect::<Vec<&str>>();
+        if side_names.len() != 2 {
+            return None;
+        }
+        let mut pieces = [Vec::new(), Vec::new()];
+        for (side_name, color) in side_names.iter().zip([WHITE, BLACK]) {
+            for piece_char in side_name.chars() {
+                let piece_type = match piece_char {
+                    'Q' => QUEEN,
+                    'R' => ROOK,
+                    'B' => BISHOP,
+                    'N' => KNIGHT,
+                    'P' => PAWN,
+                    _ => return None
+                };
+                pieces[color as usize].push(piece_type);
+            }
+        }
+        return Some(Material::new(pieces));
+    }
+    fn new(mut pieces: [Vec<BoardPieceType>; 2]) -> Material {
+        for color_pieces in pieces.iter_mut() {
+            color_pieces.sort_by_key(|piece_type| PIECE_ORDER.iter().position(|ordered_type| ordered_type == piece_type));
+        }
+        return Material {
+            pieces
+        };
+    }
+    pub fn from_board(board_pieces: &BoardPieces) -> Material {
+        let color_pieces = |color: usize| {
+            let piece_lists = [
+                (QUEEN, &board_pieces.queens[color]),
+                (ROOK, &board_pieces.rooks[color]),
+                (BISHOP, &board_pieces.bishops[color]),
+                (KNIGHT, &board_pieces.knights[color]),
+                (PAWN, &board_pieces.pawns[color])
+            ];
+            return piece_lists.iter().flat_map(|(piece_type, piece_squares)| vec![*piece_type; piece_squares.len()]).collect::<Vec<BoardPieceType>>();
+        };
+        return Material {
+            pieces: [color_pieces(BLACK as usize), color_pieces(WHITE as usize)]
+        };
+    }
+    pub fn name(&self) -> String {
+        let side_name = |color: BoardColor| self.pieces[color as usize].iter().map(|piece_type| match *piece_type {
+            QUEEN => 'Q',
+            ROOK => 'R',
+            BISHOP => 'B',
+            KNIGHT => 'N',
+            _ => 'P'
+        }).collect::<String>();
+        return format!("K{}K{}", side_name(WHITE), side_name(BLACK));
+    }
+    // kings included
+    pub fn piece_count(&self) -> usize {
+        return 2 + self.pieces[0].len() + self.pieces[1].len();
+    }
+    fn swapped(&self) -> Material {
+        return Material {
+            pieces: [self.pieces[1].clone(), self.pieces[0].clone()]
+        };
+    }
+    fn has_pawns(&self) -> bool {
+        return self.pieces.iter().flatten().any(|piece_type| *piece_type == PAWN);
+    }
+    // nobody can ever be mated, so there is no need for a table
+    fn is_trivial_draw(&self) -> bool {
+        let all_pieces = self.pieces.iter().flatten().collect::<Vec<&BoardPieceType>>();
+        return all_pieces.is_empty() || (all_pieces.len() == 1 && (*all_pieces[0] == KNIGHT || *all_pieces[0] == BISHOP));
+    }
+    // every material a capture or a promotion leads to
+    fn successors(&self) -> Vec<Material> {
+        let mut successors: Vec<Material> = Vec::new();
+        for color in [BLACK, WHITE] {
+            for (piece_index, piece_type) in self.pieces[color as usize].iter().enumerate() {
+                let mut captured_pieces = self.pieces.clone();
+                captured_pieces[color as usize].remove(piece_index);
+                successors.push(Material::new(captured_pieces));
+                if *piece_type == PAWN {
+                    for promotion_type in [QUEEN, ROOK, BISHOP, KNIGHT] {
+                        let mut promoted_pieces = self.pieces.clone();
+                        promoted_pieces[color as usize][piece_index] = promotion_type;
+                        successors.push(Material::new(promoted_pieces));
+                    }
+                }
+            }
+        }
+        successors.dedup();
+        return successors;
+    }
+    // every piece in table order
+    fn piece_list(&self) -> Vec<BoardPiece> {
+        let mut piece_list = vec![WHITE | KING, BLACK | KING];
+        piece_list.extend(self.pieces[WHITE as usize].iter().map(|piece_type| WHITE | piece_type));
+        piece_list.extend(self.pieces[BLACK as usize].iter().map(|piece_type| BLACK | piece_type));
+        return piece_list;
+    }
+    fn king_squares(&self) -> Vec<u8> {
+        if self.has_pawns() {
+            return (0..64).filter(|square_pos| square_pos % 8 < 4).collect();
+        }
+        return PAWNLESS_KING_SQUARES.to_vec();
+    }
+    pub fn table_size(&self) -> usize {
+        return self.king_squares().len() * 64usize.pow(self.piece_count() as u32 - 1) * 2;
+    }
+}
+
+// bit 0 flips files, bit 1 flips ranks and bit 2 mirrors along the diagonal
+const fn transform_square(square_pos: u8, transform: u8) -> u8 {
+    let (mut x, mut y) = (square_pos % 8, square_pos / 8);
+    if transform & 4 != 0 {
+        (x, y) = (y, x);
+    }
+    if transform & 1 != 0 {
+        x = 7 - x;
+    }
+    if transform & 2 != 0 {
+        y = 7 - y;
+    }
+    return y * 8 + x;
+}
+// the smallest of the symmetric versions of the squares, which is the one that gets stored
+fn canonical_squares(material: &Material, piece_list: &[BoardPiece], squares: &[u8]) -> Vec<u8> {
+    let transform_count = if material.has_pawns() { 2 } else { 8 };
+    let mut best_squares: Option<Vec<u8>> = None;
+    for transform in 0..transform_count {
+        let mut transformed_squares = squares.iter().map(|square_pos| transform_square(*square_pos, transform)).collect::<Vec<u8>>();
+        let mut run_start = 0;
+        while run_start < piece_list.len() {
+            let run_end = (run_start..piece_list.len()).find(|piece_index| piece_list[*piece_index] != piece_list[run_start]).unwrap_or(piece_list.len());
+            transformed_squares[run_start..run_end].sort_unstable();
+            run_start = run_end;
+        }
+        if best_squares.as_ref().is_none_or(|best_squares| transformed_squares < *best_squares) {
+            best_squares = Some(transformed_squares);
+        }
+    }
+    return best_squares.unwrap_or_default();
+}
+fn table_index(king_squares: &[u8], canonical_squares: &[u8], active_color: BoardColor) -> usize {
+    let mut index = king_squares.iter().position(|king_square_pos| *king_square_pos == canonical_squares[0]).unwrap_or(0);
+    for square_pos in canonical_squares[1..].iter() {
+        index = index * 64 + *square_pos as usize;
+    }
+    return index * 2 + active_color as usize;
+}
+fn table_squares(king_squares: &[u8], piece_count: usize, mut index: usize) -> (Vec<u8>, BoardColor) {
+    let active_color = (index % 2) as BoardColor;
+    index /= 2;
+    let mut squares = vec![0; piece_count];
+    for square_pos in squares[1..].iter_mut().rev() {
+        *square_pos = (index % 64) as u8;
+        index /= 64;
+    }
+    squares[0] = king_squares[index];
+    return (squares, active_color);
+}
+fn board_from_squares(piece_list: &[BoardPiece], squares: &[u8], active_color: BoardColor) -> Board {
+    let pieces = piece_list.iter().zip(squares).map(|(piece, square_pos)| (*piece, BoardSquare(*square_pos))).collect::<Vec<(BoardPiece, BoardSquare)>>();
+    return Board::from_pieces(&pieces, active_color);
+}
+// the squares of the pieces in table order
+fn board_squares(board_pieces: &BoardPieces) -> Option<Vec<u8>> {
+    let mut squares = vec![board_pieces.kings[WHITE as usize]?.pos(), board_pieces.kings[BLACK as usize]?.pos()];
+    for color in [WHITE as usize, BLACK as usize] {
+        for piece_squares in [&board_pieces.queens[color], &board_pieces.rooks[color], &board_pieces.bishops[color], &board_pieces.knights[color], &board_pieces.pawns[color]] {
+            squares.extend(piece_squares.iter().map(|square| square.pos()));
+        }
+    }
+    return Some(squares);
+}
+fn has_en_passant_capture(board: &Board) -> bool {
+    return board.en_passant_target_square().is_some() && board.get_valid_moves().iter().any(|valid_move| valid_move.is_en_passant());
+}
+
+#[derive(Clone)]
+struct Table {
+    material: Material,
+    values: Vec<u8>
+}
+impl Table {
+    fn value_at(&self, squares: &[u8], active_color: BoardColor) -> u8 {
+        let canonical_squares = canonical_squares(&self.material, &self.material.piece_list(), squares);
+        return self.values[table_index(&self.material.king_squares(), &canonical_squares, active_color)];
+    }
+}
+
+// win, draw or loss with the distance to mate for every position of a few small material sets,
+// generated by retrograde analysis without any outside files
+#[derive(Default, Clone)]
+pub struct Tablebases {
+    tables: HashMap<String, Table>
+}
+impl Tablebases {
+    pub fn new() -> Tablebases {
+        return Tablebases::default();
+    }
+    pub fn has_table(&self, material: &Material) -> bool {
+        return self.tables.contains_key(&material.name()) || self.tables.contains_key(&material.swapped().name());
+    }
+    pub fn table_names(&self) -> Vec<String> {
+        let mut table_names = self.tables.keys().cloned().collect::<Vec<String>>();
+        table_names.sort();
+        return table_names;
+    }
+    // the most pieces, kings included, of any table
+    pub fn max_pieces(&self) -> usize {
+        return self.tables.values().map(|table| table.material.piece_count()).max().unwrap_or(0);
+    }
+
+    pub fn probe(&self, board: &Board) -> Option<TablebaseResult> {
+        if board.piece_count() as usize > self.max_pieces().max(3) {
+            return None;
+        }
+        if board.castle_availibility().iter().flatten().any(|can_castle| *can_castle) {
+            return None;
+        }
+        let board_pieces = board.get_pieces();
+        let material = Material::from_board(&board_pieces);
+        // the tables assume nobody can capture en passant, so look one move further
+        if has_en_passant_capture(board) {
+            let child_results = board.get_valid_moves().iter()
+                .map(|valid_move| self.probe(&board.create_board_from_move(valid_move)))
+                .collect::<Option<Vec<TablebaseResult>>>()?;
+            return best_of(child_results.into_iter());
+        }
+        if material.is_trivial_draw() {
+            return Some(TablebaseResult::Draw);
+        }
+        let squares = board_squares(&board_pieces)?;
+        if let Some(table) = self.tables.get(&material.name()) {
+            return TablebaseResult::from_value(table.value_at(&squares, board.active_color()));
+        }
+        // the same table with the colors swapped, read upside down
+        let table = self.tables.get(&material.swapped().name())?;
+        let white_piece_count = 2 + material.pieces[WHITE as usize].len();
+        let mut swapped_squares = vec![squares[1] ^ 0b111000, squares[0] ^ 0b111000];
+        swapped_squares.extend(squares[white_piece_count..].iter().map(|square_pos| square_pos ^ 0b111000));
+        swapped_squares.extend(squares[2..white_piece_count].iter().map(|square_pos| square_pos ^ 0b111000));
+        return TablebaseResult::from_value(table.value_at(&swapped_squares, board.active_color() ^ 1));
+    }
+    // the move that wins fastest, draws, or loses slowest, along with the result of the position
+    pub fn best_move(&self, board: &Board, allowed_moves: &[BoardMove]) -> Option<(BoardMove, TablebaseResult)> {
+        let result = self.probe(board)?;
+        let mut best_move = None;
+        for valid_move in board.get_valid_moves() {
+            if !allowed_moves.is_empty() && !allowed_moves.contains(&valid_move) {
+                continue;
+            }
+            let child_result = self.probe(&board.create_board_from_move(&valid_move))?;
+            let preference = TablebaseResult::preference(child_result);
+            if best_move.is_none_or(|(_, best_preference)| preference > best_preference) {
+                best_move = Some((valid_move, preference));
+            }
+        }
+        return best_move.map(|(best_move, _)| (best_move, result));
+    }
+
+    // the table and every table its captures and promotions lead to, skipping the ones already there
+    pub fn generate(&mut self, material: &Material) -> Result<(), String> {
+        if material.piece_count() > MAX_TABLEBASE_PIECES {
+            return Err(format!("{} has more than {} pieces", material.name(), MAX_TABLEBASE_PIECES));
+        }
+        if self.has_table(material) || material.is_trivial_draw() {
+            return Ok(());
+        }
+        for successor in material.successors() {
+            self.generate(&successor)?;
+        }
+        let values = self.generate_values(material);
+        self.tables.insert(material.name(), Table {
+            material: material.clone(),
+            values
+        });
+        return Ok(());
+    }
+    /*
+    every position is a node with its moves as children, found once with the move generator;
+    moves out of the table are looked up in the tables generated before this one, and a double push
+    that allows an en passant capture leads to an extra node that does not get stored
+
+    then pass n finds the positions mated in n plies: on odd passes the ones with a move into a loss in n - 1,
+    on even passes the ones whose moves all go into wins, the longest of them in n - 1
+    */
+    fn generate_values(&self, material: &Material) -> Vec<u8> {
+        let piece_list = material.piece_list();
+        let king_squares = material.king_squares();
+        let table_size = material.table_size();
+        let mut values = vec![INVALID_VALUE; table_size];
+        let mut child_offsets = Vec::with_capacity(table_size + 1);
+        let mut children: Vec<u32> = Vec::new();
+        let mut en_passant_boards: Vec<Board> = Vec::new();
+        let mut max_known_plies = 0;
+        child_offsets.push(0);
+
+        let mut node_index = 0;
+        while node_index < table_size + en_passant_boards.len() {
+            let board = if node_index < table_size {
+                let (squares, active_color) = table_squares(&king_squares, piece_list.len(), node_index);
+                let is_legal = squares.iter().enumerate().all(|(piece_index, square_pos)| !squares[..piece_index].contains(square_pos))
+                    && squares.iter().zip(piece_list.iter()).all(|(square_pos, piece)| get_piece_type(*piece) != PAWN || (8..56).contains(square_pos))
+                    && canonical_squares(material, &piece_list, &squares) == squares;
+                let board = is_legal.then(|| board_from_squares(&piece_list, &squares, active_color));
+                // the side that just moved cannot have left its king in check
+                let passive_king_square = BoardSquare(squares[if active_color == WHITE { 1 } else { 0 }]);
+                match board.filter(|board| !board.is_square_attacked(&passive_king_square, active_color)) {
+                    Some(board) => board,
+                    None => {
+                        child_offsets.push(children.len());
+                        node_index += 1;
+                        continue;
+                    }
+                }
+            } else {
+                en_passant_boards[node_index - table_size].clone()
+            };
+            let valid_moves = board.get_valid_moves();
+            let node_value = if valid_moves.is_empty() {
+                if board.is_in_check() { TablebaseResult::Loss(0).to_value() } else { DRAW_VALUE }
+            } else {
+                UNKNOWN_VALUE
+            };
+            if node_index < table_size {
+                values[node_index] = node_value;
+            } else {
+                values.push(node_value);
+            }
+
+            // quiet moves are worked out on the squares alone, which is a lot faster than making them on a board
+            let squares = board_squares(&board.get_pieces()).unwrap_or_default();
+            for valid_move in valid_moves.iter() {
+                let from_square = valid_move.from_square();
+                let dest_square = valid_move.dest_square();
+                let moving_piece_index = squares.iter().position(|square_pos| *square_pos == from_square.pos()).unwrap_or(0);
+                let is_pawn_move = get_piece_type(piece_list[moving_piece_index]) == PAWN;
+                let is_capture = valid_move.is_en_passant() || squares.contains(&dest_square.pos());
+                let is_promotion = is_pawn_move && (dest_square.y() == 0 || dest_square.y() == 7);
+                // only a double push can give the other side an en passant capture
+                let is_double_push = is_pawn_move && from_square.y().abs_diff(dest_square.y()) == 2;
+                if !is_capture && !is_promotion && !is_double_push {
+                    let mut child_squares = squares.clone();
+                    child_squares[moving_piece_index] = dest_square.pos();
+                    let canonical_child_squares = canonical_squares(material, &piece_list, &child_squares);
+                    children.push(table_index(&king_squares, &canonical_child_squares, board.active_color() ^ 1) as u32);
+                    continue;
+                }
+                let child_board = board.create_board_from_move(valid_move);
+                if is_double_push && has_en_passant_capture(&child_board) {
+                    children.push((table_size + en_passant_boards.len()) as u32);
+                    en_passant_boards.push(child_board);
+                } else if !is_capture && !is_promotion {
+                    let child_squares = board_squares(&child_board.get_pieces()).unwrap_or_default();
+                    let canonical_child_squares = canonical_squares(material, &piece_list, &child_squares);
+                    children.push(table_index(&king_squares, &canonical_child_squares, child_board.active_color()) as u32);
+                } else {
+                    // every table a move can lead out to was generated first
+                    let child_result = self.probe(&child_board).unwrap_or(TablebaseResult::Draw);
+                    if let TablebaseResult::Win(plies) | TablebaseResult::Loss(plies) = child_result {
+                        max_known_plies = max_known_plies.max(plies);
+                    }
+                    children.push(KNOWN_CHILD_FLAG | child_result.to_value() as u32);
+                }
+            }
+            child_offsets.push(children.len());
+            node_index += 1;
+        }
+
+        let child_result = |values: &[u8], child: u32| {
+            if child & KNOWN_CHILD_FLAG != 0 {
+                return TablebaseResult::from_value(child as u8);
+            }
+            return TablebaseResult::from_value(values[child as usize]);
+        };
+        let mut idle_passes = 0;
+        for plies in 1..=MAX_MATE_PLIES {
+            let mut resolved_count = 0;
+            for node_index in 0..values.len() {
+                if values[node_index] != UNKNOWN_VALUE {
+                    continue;
+                }
+                let node_children = &children[child_offsets[node_index]..child_offsets[node_index + 1]];
+                let is_resolved = if plies % 2 == 1 {
+                    node_children.iter().any(|child| child_result(&values, *child) == Some(TablebaseResult::Loss(plies - 1)))
+                } else {
+                    let mut longest_win = None;
+                    let are_all_wins = node_children.iter().all(|child| match child_result(&values, *child) {
+                        Some(TablebaseResult::Win(child_plies)) if child_plies < plies => {
+                            longest_win = longest_win.max(Some(child_plies));
+                            true
+                        },
+                        _ => false
+                    });
+                    are_all_wins && longest_win == Some(plies - 1)
+                };
+                if is_resolved {
+                    values[node_index] = plies + 1;
+                    resolved_count += 1;
+                }
+            }
+            idle_passes = if resolved_count == 0 { idle_passes + 1 } else { 0 };
+            // nothing changes after two empty passes, once no longer mate from another table can come in
+            if idle_passes >= 2 && plies > max_known_plies + 1 {
+                break;
+            }
+        }
+        values.truncate(table_size);
+        for value in values.iter_mut().filter(|value| **value == UNKNOWN_VALUE) {
+            *value = DRAW_VALUE;
+        }
+        return values;
+    }
+
+    pub fn save(&self, directory: impl AsRef<Path>) -> io::Result<()> {
+        for (table_name, table) in self.tables.iter() {
+            let mut bytes = Vec::with_capacity(table.values.len() + 32);
+            bytes.extend_from_slice(&TABLEBASE_MAGIC);
+            bytes.extend_from_slice(&TABLEBASE_VERSION.to_le_bytes());
+            bytes.extend_from_slice(&(table_name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(table_name.as_bytes());
+            bytes.extend_from_slice(&(table.values.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&table.values);
+            fs::write(directory.as_ref().join(format!("{}.{}", table_name, TABLEBASE_EXTENSION)), bytes)?;
+        }
+        return Ok(());
+    }
+    // every table in the directory, returning how many there were
+    pub fn load(&mut self, directory: impl AsRef<Path>) -> io::Result<usize> {
+        let mut table_count = 0;
+        for dir_entry in fs::read_dir(directory)? {
+            let path = dir_entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(TABLEBASE_EXTENSION) {
+                continue;
+            }
+            let table = Tablebases::read_table(&fs::read(&path)?)?;
+            self.tables.insert(table.material.name(), table);
+            table_count += 1;
+        }
+        return Ok(table_count);
+    }
+    fn read_table(bytes: &[u8]) -> io::Result<Table> {
+        let invalid_table = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let read_u32 = |offset: usize| bytes.get(offset..offset + 4).map(|u32_bytes| u32::from_le_bytes([u32_bytes[0], u32_bytes[1], u32_bytes[2], u32_bytes[3]]) as usize);
+        if bytes.get(0..4) != Some(&TABLEBASE_MAGIC[..]) {
+            return Err(invalid_table("not a tablebase file"));
+        }
+        if read_u32(4) != Some(TABLEBASE_VERSION as usize) {
+            return Err(invalid_table("unsupported tablebase version"));
+        }
+        let name_length = read_u32(8).ok_or_else(|| invalid_table("tablebase file is cut short"))?;
+        let material = bytes.get(12..12 + name_length)
+            .and_then(|name_bytes| std::str::from_utf8(name_bytes).ok())
+            .and_then(Material::parse)
+            .filter(|material| material.piece_count() <= MAX_TABLEBASE_PIECES)
+            .ok_or_else(|| invalid_table("unknown tablebase material"))?;
+        let value_count = read_u32(12 + name_length).ok_or_else(|| invalid_table("tablebase file is cut short"))?;
+        let values = &bytes[(16 + name_length).min(bytes.len())..];
+        if value_count != material.table_size() || values.len() != value_count {
+            return Err(invalid_table("tablebase size does not match its material"));
+        }
+        return Ok(Table {
+            material,
+            values: values.to_vec()
+        });
+    }
+}
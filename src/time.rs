@@ -0,0 +1,84 @@
+use crate::{
+    board::*,
+    search::SearchLimits
+};
+use std::{
+    cmp,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering
+        },
+        Arc
+    },
+    time::{
+        Duration,
+        Instant
+    }
+};
+
+// moves we expect to still have to play when the gui does not tell us
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// kept in reserve so lag between us and the gui never loses on time
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+// the time we can afford to spend on this move, if the search is timed at all
+pub fn allocate_time(limits: &SearchLimits, color: BoardColor) -> Option<Duration> {
+    if limits.infinite {
+        return None;
+    }
+    if let Some(move_time) = limits.move_time {
+        return Some(move_time);
+    }
+    let remaining_time = limits.remaining_time[color as usize]?;
+    let moves_to_go = limits.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let allocated_time = remaining_time / moves_to_go + limits.increment[color as usize] * 3 / 4;
+    return Some(cmp::min(allocated_time, remaining_time.saturating_sub(MOVE_OVERHEAD)));
+}
+
+pub struct TimeManager {
+    clock_start: Instant,
+    allocated_time: Option<Duration>,
+    // while this is unset we are pondering, and our clock is not running yet
+    ponder_hit: Option<Arc<AtomicBool>>
+}
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, color: BoardColor) -> TimeManager {
+        return TimeManager {
+            clock_start: Instant::now(),
+            allocated_time: allocate_time(limits, color),
+            ponder_hit: limits.ponder_hit.clone()
+        };
+    }
+    pub fn unlimited() -> TimeManager {
+        return TimeManager {
+            clock_start: Instant::now(),
+            allocated_time: None,
+            ponder_hit: None
+        };
+    }
+    pub fn is_pondering(&mut self) -> bool {
+        if let Some(ponder_hit) = &self.ponder_hit {
+            if !ponder_hit.load(Ordering::Relaxed) {
+                return true;
+            }
+            // the opponent played the move we pondered on, so our clock starts now
+            self.ponder_hit = None;
+            self.clock_start = Instant::now();
+        }
+        return false;
+    }
+    pub fn is_time_up(&mut self) -> bool {
+        if self.is_pondering() {
+            return false;
+        }
+        return self.allocated_time.is_some_and(|allocated_time| self.clock_start.elapsed() >= allocated_time);
+    }
+    // the next iteration usually takes longer than all previous ones together
+    pub fn has_time_for_iteration(&mut self) -> bool {
+        if self.is_pondering() {
+            return true;
+        }
+        return self.allocated_time.is_none_or(|allocated_time| self.clock_start.elapsed() * 2 <= allocated_time);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    board::*,
+    score::Score
+};
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering
+};
+
+pub const DEFAULT_HASH_SIZE_MB: usize = 16;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Bound {
+    Exact,
+    // the score is at least this much (fail high)
+    Lower,
+    // the score is at most this much (fail low)
+    Upper
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TranspositionEntry {
+    pub best_move: Option<BoardMove>,
+    pub score: Score,
+    pub depth: u8,
+    pub bound: Bound
+}
+
+/*
+entry data format:
+    0000 0000 0000 00gg gggg ggbb dddd dddd ssss ssss ssss ssss mmmm mmmm mmmm mmmm
+    m - best move, all zeroes when there is none (a8 to a8 is never a move)
+    s - score, as an i16
+    d - depth
+    b - bound (00 - empty, 01 - exact, 10 - lower, 11 - upper)
+    g - generation of the search that stored the entry
+*/
+struct TranspositionSlot {
+    // key ^ data, so a slot torn by two threads writing at once fails the key check on probe
+    checked_key: AtomicU64,
+    data: AtomicU64
+}
+
+// shared between search threads without locking
+pub struct TranspositionTable {
+    slots: Vec<TranspositionSlot>,
+    generation: AtomicU64
+}
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        // a power of two, so the index is just the low bits of the key
+        let slot_count = 1usize << (size_mb.max(1) * 1024 * 1024 / std::mem::size_of::<TranspositionSlot>()).ilog2();
+        let mut slots = Vec::with_capacity(slot_count);
+        slots.resize_with(slot_count, || TranspositionSlot {
+            checked_key: AtomicU64::new(0),
+            data: AtomicU64::new(0)
+        });
+        return TranspositionTable {
+            slots,
+            generation: AtomicU64::new(0)
+        };
+    }
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.checked_key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+    // entries from older searches get replaced first
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+    fn slot(&self, key: u64) -> &TranspositionSlot {
+        return &self.slots[key as usize & (self.slots.len() - 1)];
+    }
+    // mate scores are stored relative to the node instead of the root, so they stay valid at any ply
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TranspositionEntry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if slot.checked_key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        let bound = match (data >> 40) & 0b11 {
+            0b01 => Bound::Exact,
+            0b10 => Bound::Lower,
+            0b11 => Bound::Upper,
+            _ => return None
+        };
+        let move_bits = data as u16;
+        return Some(TranspositionEntry {
+            best_move: if move_bits == 0 { None } else { Some(BoardMove::from_bits(move_bits)) },
+            score: Score::from_tt((data >> 16) as u16 as i16, ply),
+            depth: (data >> 32) as u8,
+            bound
+        });
+    }
+    pub fn store(&self, key: u64, ply: usize, entry: TranspositionEntry) {
+        let slot = self.slot(key);
+        let generation = self.generation.load(Ordering::Relaxed) & 0xff;
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let is_same_position = slot.checked_key.load(Ordering::Relaxed) ^ old_data == key;
+        let is_old_generation = (old_data >> 42) & 0xff != generation;
+        // keep deeper results of this search around, unless the new one is exact
+        if !is_same_position && !is_old_generation && (old_data >> 32) as u8 > entry.depth && entry.bound != Bound::Exact {
+            return;
+        }
+        let mut move_bits = entry.best_move.map_or(0, |best_move| best_move.to_bits());
+        if move_bits == 0 && is_same_position {
+            // a fail low has no best move, but the previous one is still a good guess
+            move_bits = old_data as u16;
+        }
+        let bound_bits = match entry.bound {
+            Bound::Exact => 0b01,
+            Bound::Lower => 0b10,
+            Bound::Upper => 0b11
+        };
+        let data = move_bits as u64
+            | (entry.score.to_tt(ply) as u16 as u64) << 16
+            | (entry.depth as u64) << 32
+            | bound_bits << 40
+            | generation << 42;
+        slot.checked_key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    board::*,
+    eval::trace_with_params,
+    params::*,
+    pgn::is_valid_fen
+};
+use std::thread;
+
+pub struct LabelledPosition {
+    pub board: Board,
+    // the result of the game the position came from, 1 for a white win, 0.5 for a draw and 0 for a black win
+    pub result: f64
+}
+
+// a fen followed by the result, either as 1-0, 0-1 or 1/2-1/2 or as 1.0, 0.5 or 0.0, in quotes or brackets or not;
+// numeric results need their decimal point, or the fullmove number of a bare fen would pass as one
+pub fn parse_labelled_position(line: &str) -> Option<LabelledPosition> {
+    let line_parts = line.split_whitespace().collect::<Vec<&str>>();
+    // one broken line should not take a whole dataset down with it
+    if line_parts.len() < 5 || !is_valid_fen(&line_parts[..4].join(" ")) {
+        return None;
+    }
+    let result_part = line_parts.last()?.trim_matches(|c: char| c == '"' || c == ';' || c == '[' || c == ']' || c == '(' || c == ')');
+    let result = match result_part {
+        "1-0" => 1.0,
+        "0-1" => 0.0,
+        "1/2-1/2" => 0.5,
+        numeric_result if numeric_result.contains('.') => numeric_result.parse::<f64>().ok().filter(|result| (0.0..=1.0).contains(result))?,
+        _ => return None
+    };
+    let board = Board::from_fen(&line_parts[..4].join(" "));
+    return Some(LabelledPosition {
+        board,
+        result
+    });
+}
+
+// the expected result for a score from white's point of view, scaled so that it fits the data
+pub fn sigmoid(score: f64, scaling: f64) -> f64 {
+    return 1.0 / (1.0 + 10f64.powf(-scaling * score / 400.0));
+}
+
+fn white_score(board: &Board, params: &EvalParams) -> i32 {
+    let score = trace_with_params(board, params).score().value();
+    return if board.active_color() == WHITE { score } else { -score };
+}
+
+pub fn mean_squared_error(positions: &[LabelledPosition], params: &EvalParams, scaling: f64) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+    let thread_count = thread::available_parallelism().map_or(1, |thread_count| thread_count.get());
+    let chunk_size = positions.len().div_ceil(thread_count);
+    let squared_error_sum: f64 = thread::scope(|scope| {
+        let workers = positions.chunks(chunk_size).map(|position_chunk| scope.spawn(move || {
+            return position_chunk.iter()
+                .map(|position| (position.result - sigmoid(white_score(&position.board, params) as f64, scaling)).powi(2))
+                .sum::<f64>();
+        })).collect::<Vec<_>>();
+        return workers.into_iter().map(|worker| worker.join().expect("tuning thread panicked")).sum();
+    });
+    return squared_error_sum / positions.len() as f64;
+}
+
+// the scaling that makes the current weights predict the results best, the error is convex in it
+pub fn fit_scaling(positions: &[LabelledPosition], params: &EvalParams) -> f64 {
+    let (mut low, mut high) = (0.0f64, 4.0f64);
+    while high - low > 0.001 {
+        let low_third = low + (high - low) / 3.0;
+        let high_third = high - (high - low) / 3.0;
+        if mean_squared_error(positions, params, low_third) < mean_squared_error(positions, params, high_third) {
+            high = high_third;
+        } else {
+            low = low_third;
+        }
+    }
+    return (low + high) / 2.0;
+}
+
+// texel style local search, nudging every weight by one until no nudge lowers the error anymore;
+// report gets the pass number, the error after it and the weights, and returns false to stop early
+pub fn tune_params(positions: &[LabelledPosition], params: &mut EvalParams, scaling: f64, max_passes: usize, mut report: impl FnMut(usize, f64, &EvalParams) -> bool) -> f64 {
+    let mut best_error = mean_squared_error(positions, params, scaling);
+    for pass in 1..=max_passes {
+        let mut is_improved = false;
+        for param_index in 0..PARAM_COUNT {
+            params[param_index] += 1;
+            let raised_error = mean_squared_error(positions, params, scaling);
+            if raised_error < best_error {
+                best_error = raised_error;
+                is_improved = true;
+                continue;
+            }
+            params[param_index] -= 2;
+            // tapering rounds, so lowering a weight can matter even where raising it did not
+            let lowered_error = mean_squared_error(positions, params, scaling);
+            if lowered_error < best_error {
+                best_error = lowered_error;
+                is_improved = true;
+                continue;
+            }
+            params[param_index] += 1;
+        }
+        if !report(pass, best_error, params) || !is_improved {
+            break;
+        }
+    }
+    return best_error;
+}
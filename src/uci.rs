@@ -0,0 +1,391 @@
+use crate::{
+    board::*,
+    book::*,
+    eval,
+    nnue::Network,
+    search::*,
+    syzygy::Syzygy,
+    tablebase::Tablebases,
+    tt::DEFAULT_HASH_SIZE_MB
+};
+use std::{
+    io::{
+        self,
+        BufRead,
+        Write
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering
+        },
+        Arc,
+        Mutex
+    },
+    thread,
+    time::{
+        Duration,
+        SystemTime
+    }
+};
+
+pub const ENGINE_NAME: &str = "apotheosis";
+pub const ENGINE_AUTHOR: &str = "hydro";
+pub const MAX_THREADS: usize = 256;
+
+pub fn format_pv(board: &Board, pv: &[BoardMove]) -> String {
+    let mut pv_board = board.clone();
+    let mut uci_moves = Vec::with_capacity(pv.len());
+    for pv_move in pv {
+        uci_moves.push(pv_move.to_uci(&pv_board));
+        pv_board = pv_board.create_board_from_move(pv_move);
+    }
+    return uci_moves.join(" ");
+}
+pub fn format_info(board: &Board, result: &SearchResult) -> String {
+    let elapsed_millis = result.elapsed.as_millis();
+    let nodes_per_second = result.nodes as u128 * 1000 / elapsed_millis.max(1);
+    let mut info_lines = Vec::with_capacity(result.lines.len());
+    for (line_index, line) in result.lines.iter().enumerate() {
+        let multi_pv_part = if result.lines.len() > 1 { format!(" multipv {}", line_index + 1) } else { String::new() };
+        info_lines.push(format!(
+            "info depth {}{} score {} nodes {} nps {} time {} pv {}",
+            result.depth,
+            multi_pv_part,
+            line.score.to_uci(),
+            result.nodes,
+            nodes_per_second,
+            elapsed_millis,
+            format_pv(board, &line.pv)
+        ));
+    }
+    return info_lines.join("\n");
+}
+
+pub type UciOutput = Arc<Mutex<dyn Write + Send>>;
+
+pub struct UciEngine {
+    board: Board,
+    history: Vec<Board>,
+    // handed over to the search thread while a search is running
+    searcher: Option<Searcher>,
+    search_thread: Option<thread::JoinHandle<Searcher>>,
+    search_stop_signal: Arc<AtomicBool>,
+    // set by stop, infinite searches hold back their bestmove until then
+    stop_requested: Arc<AtomicBool>,
+    // set by ponderhit, for the ponder search that is running
+    ponder_hit: Option<Arc<AtomicBool>>,
+    is_search_infinite: bool,
+    // loaded from EvalFile, and only handed to the searcher while UseNNUE is on
+    network: Option<Arc<Network>>,
+    use_nnue: bool,
+    // the book only gets used with OwnBook on; its keys are polyglot's unless BookKeysFile says otherwise
+    book: Option<Book>,
+    book_keys: PolyglotKeys,
+    own_book: bool,
+    book_selection_is_best: bool,
+    // xorshift, for picking among the book moves
+    book_random_state: u64,
+    output: UciOutput
+}
+impl UciEngine {
+    pub fn new(output: UciOutput) -> UciEngine {
+        let searcher = Searcher::new();
+        return UciEngine {
+            board: Board::from_fen(STARTING_FEN),
+            history: Vec::new(),
+            search_stop_signal: searcher.stop_handle(),
+            searcher: Some(searcher),
+            search_thread: None,
+            stop_requested: Arc::new(AtomicBool::new(false)),
+            ponder_hit: None,
+            is_search_infinite: false,
+            network: None,
+            use_nnue: false,
+            book: None,
+            book_keys: PolyglotKeys::default(),
+            own_book: false,
+            book_selection_is_best: false,
+            // never zero, or xorshift would stay there
+            book_random_state: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos() as u64) | 1,
+            output
+        };
+    }
+    fn searcher(&mut self) -> &mut Searcher {
+        self.stop_search();
+        return self.searcher.as_mut().expect("the searcher is back once the search thread is joined");
+    }
+    pub fn stop_search(&mut self) {
+        self.stop_requested.store(true, Ordering::Relaxed);
+        self.search_stop_signal.store(true, Ordering::Relaxed);
+        self.wait_for_search();
+    }
+    // lets a limited search run to the end, an infinite or pondering one gets stopped since it would never end
+    pub fn finish_search(&mut self) {
+        let is_pondering = self.ponder_hit.as_ref().is_some_and(|ponder_hit| !ponder_hit.load(Ordering::Relaxed));
+        if self.is_search_infinite || is_pondering {
+            self.stop_search();
+        } else {
+            self.wait_for_search();
+        }
+    }
+    fn wait_for_search(&mut self) {
+        if let Some(search_thread) = self.search_thread.take() {
+            self.searcher = Some(search_thread.join().expect("search thread panicked"));
+        }
+        // the search may have finished before the stop came in
+        self.search_stop_signal.store(false, Ordering::Relaxed);
+        self.stop_requested.store(false, Ordering::Relaxed);
+        self.ponder_hit = None;
+    }
+    // returns false once the engine should quit
+    pub fn handle_command(&mut self, command: &str) -> io::Result<bool> {
+        let mut command_parts = command.split_whitespace();
+        match command_parts.next() {
+            Some("uci") => {
+                let mut output = self.output.lock().unwrap();
+                writeln!(output, "id name {}", ENGINE_NAME)?;
+                writeln!(output, "id author {}", ENGINE_AUTHOR)?;
+                writeln!(output, "option name Hash type spin default {} min 1 max 65536", DEFAULT_HASH_SIZE_MB)?;
+                writeln!(output, "option name Threads type spin default 1 min 1 max {}", MAX_THREADS)?;
+                writeln!(output, "option name MultiPV type spin default 1 min 1 max {}", MAX_PLY)?;
+                // the gui decides whether we get to ponder, the option just tells it we can
+                writeln!(output, "option name Ponder type check default false")?;
+                writeln!(output, "option name UseNNUE type check default false")?;
+                writeln!(output, "option name EvalFile type string default <empty>")?;
+                writeln!(output, "option name TablebasePath type string default <empty>")?;
+                writeln!(output, "option name SyzygyPath type string default <empty>")?;
+                writeln!(output, "option name OwnBook type check default false")?;
+                writeln!(output, "option name BookFile type string default <empty>")?;
+                writeln!(output, "option name BookKeysFile type string default <empty>")?;
+                writeln!(output, "option name BookBestMove type check default false")?;
+                writeln!(output, "uciok")?;
+            },
+            Some("isready") => writeln!(self.output.lock().unwrap(), "readyok")?,
+            Some("ucinewgame") => {
+                self.board = Board::from_fen(STARTING_FEN);
+                self.history.clear();
+                self.searcher().clear_hash();
+            },
+            Some("setoption") => self.set_option(&command_parts.collect::<Vec<&str>>())?,
+            Some("position") => {
+                self.stop_search();
+                self.set_position(&command_parts.collect::<Vec<&str>>());
+            },
+            Some("go") => {
+                self.stop_search();
+                let limits = self.parse_go(&command_parts.collect::<Vec<&str>>());
+                match self.book_move(&limits) {
+                    Some(book_move) => writeln!(self.output.lock().unwrap(), "bestmove {}", book_move.to_uci(&self.board))?,
+                    None => self.start_search(limits)
+                }
+            },
+            Some("ponderhit") => {
+                if let Some(ponder_hit) = &self.ponder_hit {
+                    ponder_hit.store(true, Ordering::Relaxed);
+                }
+            },
+            // not part of uci, prints how the evaluation of the current position breaks down
+            Some("eval") => writeln!(self.output.lock().unwrap(), "{}", eval::trace(&self.board))?,
+            Some("stop") => self.stop_search(),
+            Some("quit") => {
+                self.stop_search();
+                return Ok(false);
+            },
+            _ => {}
+        }
+        self.output.lock().unwrap().flush()?;
+        return Ok(true);
+    }
+    fn start_search(&mut self, limits: SearchLimits) {
+        self.is_search_infinite = limits.infinite;
+        self.ponder_hit = limits.ponder_hit.clone();
+        let mut searcher = self.searcher.take().expect("only one search runs at a time");
+        let board = self.board.clone();
+        let history = self.history.clone();
+        let stop_requested = self.stop_requested.clone();
+        let output = self.output.clone();
+        self.search_thread = Some(thread::spawn(move || {
+            let result = searcher.search(&board, &history, &limits, |result| {
+                // the report is best effort, the bestmove line is what matters
+                let _ = writeln!(output.lock().unwrap(), "{}", format_info(&board, result));
+            });
+            // a search that ended early still has to wait for the gui to stop it or end the ponder
+            let is_waiting = || {
+                let is_pondering = limits.ponder_hit.as_ref().is_some_and(|ponder_hit| !ponder_hit.load(Ordering::Relaxed));
+                return (limits.infinite || is_pondering) && !stop_requested.load(Ordering::Relaxed);
+            };
+            while is_waiting() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let mut output = output.lock().unwrap();
+            let _ = match (result.best_move, result.pv.get(1)) {
+                (Some(best_move), Some(ponder_move)) => {
+                    // the reply we expect is the one we would like to ponder on
+                    let ponder_board = board.create_board_from_move(&best_move);
+                    writeln!(output, "bestmove {} ponder {}", best_move.to_uci(&board), ponder_move.to_uci(&ponder_board))
+                },
+                (Some(best_move), None) => writeln!(output, "bestmove {}", best_move.to_uci(&board)),
+                (None, _) => writeln!(output, "bestmove 0000")
+            };
+            let _ = output.flush();
+            return searcher;
+        }));
+    }
+    // a move from the book instead of searching, unless the gui wants to see a search going
+    fn book_move(&mut self, limits: &SearchLimits) -> Option<BoardMove> {
+        if !self.own_book || limits.infinite || limits.ponder_hit.is_some() {
+            return None;
+        }
+        let book = self.book.as_ref()?;
+        let selection = if self.book_selection_is_best {
+            BookSelection::BestWeight
+        } else {
+            self.book_random_state ^= self.book_random_state << 13;
+            self.book_random_state ^= self.book_random_state >> 7;
+            self.book_random_state ^= self.book_random_state << 17;
+            BookSelection::WeightedRandom(self.book_random_state)
+        };
+        return book.select_move(&self.book_keys, &self.board, selection)
+            .filter(|book_move| limits.search_moves.is_empty() || limits.search_moves.contains(book_move));
+    }
+    fn set_option(&mut self, option_parts: &[&str]) -> io::Result<()> {
+        // option names and values can have spaces in them
+        let value_index = option_parts.iter().position(|part| *part == "value").unwrap_or(option_parts.len());
+        let option_name = option_parts.get(1..value_index).unwrap_or(&[]).join(" ");
+        let option_value = option_parts.get(value_index + 1..).unwrap_or(&[]).join(" ");
+        let numeric_value = option_value.parse::<usize>().ok();
+        match (option_name.to_ascii_lowercase().as_str(), numeric_value) {
+            ("hash", Some(numeric_value)) => self.searcher().set_hash_size(numeric_value.clamp(1, 65536)),
+            ("threads", Some(numeric_value)) => self.searcher().threads = numeric_value.clamp(1, MAX_THREADS),
+            ("multipv", Some(numeric_value)) => self.searcher().multi_pv = numeric_value.clamp(1, MAX_PLY),
+            ("usennue", _) => {
+                self.use_nnue = option_value.eq_ignore_ascii_case("true");
+                if self.use_nnue && self.network.is_none() {
+                    writeln!(self.output.lock().unwrap(), "info string no network loaded, set EvalFile first")?;
+                }
+            },
+            ("evalfile", _) => {
+                self.network = None;
+                if !option_value.is_empty() && option_value != "<empty>" {
+                    match Network::load(&option_value) {
+                        Ok(network) => self.network = Some(Arc::new(network)),
+                        Err(error) => writeln!(self.output.lock().unwrap(), "info string cannot load network {}: {}", option_value, error)?
+                    }
+                }
+            },
+            ("tablebasepath", _) => {
+                self.searcher().tablebases = None;
+                if !option_value.is_empty() && option_value != "<empty>" {
+                    let mut tablebases = Tablebases::new();
+                    match tablebases.load(&option_value) {
+                        Ok(table_count) => {
+                            writeln!(self.output.lock().unwrap(), "info string loaded {} tablebase tables", table_count)?;
+                            self.searcher().tablebases = Some(Arc::new(tablebases));
+                        },
+                        Err(error) => writeln!(self.output.lock().unwrap(), "info string cannot load tablebases {}: {}", option_value, error)?
+                    }
+                }
+            },
+            ("syzygypath", _) => {
+                self.searcher().syzygy = None;
+                if !option_value.is_empty() && option_value != "<empty>" {
+                    let mut syzygy = Syzygy::new();
+                    match syzygy.load(&option_value) {
+                        Ok(table_count) => {
+                            writeln!(self.output.lock().unwrap(), "info string found {} syzygy tables", table_count)?;
+                            self.searcher().syzygy = Some(Arc::new(syzygy));
+                        },
+                        Err(error) => writeln!(self.output.lock().unwrap(), "info string cannot load syzygy tables {}: {}", option_value, error)?
+                    }
+                }
+            },
+            ("ownbook", _) => {
+                self.own_book = option_value.eq_ignore_ascii_case("true");
+                if self.own_book && self.book.is_none() {
+                    writeln!(self.output.lock().unwrap(), "info string no book loaded, set BookFile first")?;
+                }
+            },
+            ("bookfile", _) => {
+                self.book = None;
+                if !option_value.is_empty() && option_value != "<empty>" {
+                    match Book::load(&option_value) {
+                        Ok(book) => self.book = Some(book),
+                        Err(error) => writeln!(self.output.lock().unwrap(), "info string cannot load book {}: {}", option_value, error)?
+                    }
+                }
+            },
+            ("bookkeysfile", _) => {
+                self.book_keys = PolyglotKeys::default();
+                if !option_value.is_empty() && option_value != "<empty>" {
+                    match PolyglotKeys::load(&option_value) {
+                        Ok(book_keys) => self.book_keys = book_keys,
+                        Err(error) => writeln!(self.output.lock().unwrap(), "info string cannot load book keys {}: {}", option_value, error)?
+                    }
+                }
+            },
+            ("bookbestmove", _) => self.book_selection_is_best = option_value.eq_ignore_ascii_case("true"),
+            _ => {}
+        }
+        let network = if self.use_nnue { self.network.clone() } else { None };
+        self.searcher().network = network;
+        return Ok(());
+    }
+    fn set_position(&mut self, position_parts: &[&str]) {
+        let moves_index = position_parts.iter().position(|part| *part == "moves").unwrap_or(position_parts.len());
+        self.board = match position_parts.first() {
+            Some(&"fen") => Board::from_fen(&position_parts[1..moves_index].join(" ")),
+            _ => Board::from_fen(STARTING_FEN)
+        };
+        self.history.clear();
+        for uci_move in position_parts.iter().skip(moves_index + 1) {
+            match BoardMove::from_uci(&self.board, uci_move) {
+                Some(board_move) => {
+                    self.history.push(self.board.clone());
+                    self.board = self.board.create_board_from_move(&board_move);
+                },
+                None => break
+            }
+        }
+    }
+    fn parse_go(&self, go_parts: &[&str]) -> SearchLimits {
+        let mut limits = SearchLimits::default();
+        let mut go_parts = go_parts.iter().peekable();
+        let millis = |value: Option<&&str>| Duration::from_millis(value.and_then(|value| value.parse::<i64>().ok()).unwrap_or(0).max(0) as u64);
+        while let Some(go_part) = go_parts.next() {
+            match *go_part {
+                "depth" => limits.depth = go_parts.next().and_then(|value| value.parse().ok()),
+                "movetime" => limits.move_time = Some(millis(go_parts.next())),
+                "wtime" => limits.remaining_time[WHITE as usize] = Some(millis(go_parts.next())),
+                "btime" => limits.remaining_time[BLACK as usize] = Some(millis(go_parts.next())),
+                "winc" => limits.increment[WHITE as usize] = millis(go_parts.next()),
+                "binc" => limits.increment[BLACK as usize] = millis(go_parts.next()),
+                "movestogo" => limits.moves_to_go = go_parts.next().and_then(|value| value.parse().ok()),
+                "nodes" => limits.nodes = go_parts.next().and_then(|value| value.parse().ok()),
+                "mate" => limits.mate = go_parts.next().and_then(|value| value.parse().ok()),
+                "infinite" => limits.infinite = true,
+                "ponder" => limits.ponder_hit = Some(Arc::new(AtomicBool::new(false))),
+                "searchmoves" => {
+                    // the move list runs until the next keyword
+                    while let Some(search_move) = go_parts.peek().and_then(|uci_move| BoardMove::from_uci(&self.board, uci_move)) {
+                        limits.search_moves.push(search_move);
+                        go_parts.next();
+                    }
+                },
+                _ => {}
+            }
+        }
+        return limits;
+    }
+}
+
+pub fn run_uci_loop() -> io::Result<()> {
+    let mut engine = UciEngine::new(Arc::new(Mutex::new(io::stdout())));
+    for line in io::stdin().lock().lines() {
+        if !engine.handle_command(&line?)? {
+            return Ok(());
+        }
+    }
+    engine.finish_search();
+    return Ok(());
+}
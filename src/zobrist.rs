@@ -0,0 +1,58 @@
+use crate::board::*;
+
+// splitmix64, so the keys are fixed at compile time and identical on every platform
+const fn generate_keys<const KEY_COUNT: usize>(seed: u64) -> [u64; KEY_COUNT] {
+    let mut keys = [0u64; KEY_COUNT];
+    let mut state = seed;
+    let mut key_index = 0;
+    while key_index < KEY_COUNT {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut mixed_state = state;
+        mixed_state = (mixed_state ^ (mixed_state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        mixed_state = (mixed_state ^ (mixed_state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[key_index] = mixed_state ^ (mixed_state >> 31);
+        key_index += 1;
+    }
+    return keys;
+}
+
+// indexed by piece * 64 + square, only the indices of real pieces are used
+const PIECE_KEYS: [u64; 16 * 64] = generate_keys(0x6170_6f74_6865_6f73);
+// indexed by the castling rights as (bk, bq, wk, wq) bits
+const CASTLING_KEYS: [u64; 16] = generate_keys(0x6361_7374_6c65_7321);
+const EN_PASSANT_FILE_KEYS: [u64; 8] = generate_keys(0x656e_7061_7373_616e);
+const WHITE_TO_MOVE_KEY: u64 = generate_keys::<1>(0x7475_726e_7475_726e)[0];
+
+fn castling_rights_index(board: &Board) -> usize {
+    let [[black_king, black_queen], [white_king, white_queen]] = board.castle_availibility();
+    return (black_king as usize) << 3 | (black_queen as usize) << 2 | (white_king as usize) << 1 | white_queen as usize;
+}
+
+impl Board {
+    pub fn zobrist_key(&self) -> u64 {
+        let mut key = 0u64;
+        for (square_pos, possible_piece) in self.get_piece_grid().iter().enumerate() {
+            if let Some(piece) = possible_piece {
+                key ^= PIECE_KEYS[*piece as usize * 64 + square_pos];
+            }
+        }
+        key ^= CASTLING_KEYS[castling_rights_index(self)];
+        if let Some(en_passant_target_square) = self.en_passant_target_square() {
+            key ^= EN_PASSANT_FILE_KEYS[en_passant_target_square.x() as usize];
+        }
+        if self.active_color() == WHITE {
+            key ^= WHITE_TO_MOVE_KEY;
+        }
+        return key;
+    }
+    // only the pawns, for caching pawn structure evaluation
+    pub fn pawn_zobrist_key(&self) -> u64 {
+        let mut key = 0u64;
+        for (square_pos, possible_piece) in self.get_piece_grid().iter().enumerate() {
+            if let Some(piece) = possible_piece.filter(|piece| get_piece_type(*piece) == PAWN) {
+                key ^= PIECE_KEYS[piece as usize * 64 + square_pos];
+            }
+        }
+        return key;
+    }
+}
@@ -0,0 +1,24 @@
+use apotheosis::{
+    bench::*,
+    board::*,
+    pgn::is_valid_fen
+};
+
+#[test]
+fn bench_positions_are_legal() {
+    assert_eq!(BENCH_FENS.len(), 50);
+    for fen in BENCH_FENS {
+        assert!(is_valid_fen(fen), "{}", fen);
+        assert!(!Board::from_fen(fen).get_valid_moves().is_empty(), "{}", fen);
+    }
+}
+
+#[test]
+fn bench_counts_the_same_nodes_every_run() {
+    let mut position_nodes = Vec::new();
+    let first_run = run_bench(1, |_, _, result| position_nodes.push(result.nodes));
+    assert_eq!(position_nodes.len(), BENCH_FENS.len());
+    assert_eq!(first_run.nodes, position_nodes.iter().sum::<u64>());
+    let second_run = run_bench(1, |_, _, _| {});
+    assert_eq!(first_run.nodes, second_run.nodes);
+}
@@ -2,13 +2,7 @@ use apotheosis::board::{
     Board,
     *
 };
-use std::{
-    collections::HashSet, 
-    hash::Hash,
-    iter::FromIterator,
-    clone::Clone,
-    fmt
-};
+use std::collections::HashSet;
 
 #[test]
 fn board_move_test() {
@@ -33,7 +27,7 @@ fn board_move_test() {
         // castling
         ("r3k2r/Rp5p/pP5P/P7/1p6/pPp2p1p/PrP2P1P/R3K2R w KQq - 0 1", [
                 vec![
-                    ["b2", "a1"],
+                    ["b2", "a2"],
                     ["b2", "b1"],
                     ["b2", "b3"],
                     ["b2", "c2"],
@@ -61,7 +55,9 @@ fn board_move_test() {
                     ["a7", "b7"],
                     ["a7", "a6"],
                     ["a7", "a8"],
-                    ["c", "wk"]
+                    ["c", "wk"],
+                    // b1 being attacked does not matter, since the king does not pass through it
+                    ["c", "wq"]
                 ]
             ]
         )
@@ -78,7 +74,7 @@ fn board_move_test() {
 }
 
 fn boardmove(s1: &str, s2: &str) -> BoardMove {
-    match s1.chars().nth(0).unwrap() {
+    match s1.chars().next().unwrap() {
         'c' => match s2 {
             "bk" => BoardMove::CASTLE_BK,
             "bq" => BoardMove::CASTLE_BQ,
@@ -99,4 +95,66 @@ fn assert_consists_of_same_moves(v1: Vec<BoardMove>, v2: Vec<BoardMove>) {
         println!("difference: {}", diff);
     }
     assert_eq!(sdiff, [] as [&BoardMove; 0]);
-}
\ No newline at end of file
+}
+fn perft(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    board.get_valid_moves().iter().map(|board_move| perft(&board.create_board_from_move(board_move), depth - 1)).sum()
+}
+
+#[test]
+fn perft_test() {
+    let perft_tests = [
+        (STARTING_FEN, 3, 8902),
+        // castling, promotions and pins
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2, 2039),
+        // en passant discovering a check along the rank
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+        ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 2, 1486)
+    ];
+    for (test_fen, depth, expected_nodes) in perft_tests {
+        assert_eq!(perft(&Board::from_fen(test_fen), depth), expected_nodes, "perft {} of {}", depth, test_fen);
+    }
+}
+
+#[test]
+fn san_test() {
+    let san_tests = [
+        // knights that need a file, rooks that need a rank
+        ("4k3/8/8/8/8/8/8/RN2KN1R w - - 0 1", "b1d2", "Nbd2"),
+        ("4k3/R7/8/8/8/8/8/R3K3 w - - 0 1", "a1a4", "R1a4"),
+        ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", "O-O+"),
+        ("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8q", "bxa8=Q+"),
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", "exd6"),
+        ("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8", "Ra8#")
+    ];
+    for (test_fen, uci_move, expected_san) in san_tests {
+        let board = Board::from_fen(test_fen);
+        let board_move = BoardMove::from_uci(&board, uci_move).unwrap();
+        assert_eq!(board_move.to_san(&board), expected_san);
+        assert_eq!(BoardMove::from_san(&board, expected_san), Some(board_move));
+    }
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/RN2KN1R w - - 0 1");
+    // more than it needs is fine, too little is not
+    assert_eq!(BoardMove::from_san(&board, "Nb1d2!?"), BoardMove::from_uci(&board, "b1d2"));
+    assert_eq!(BoardMove::from_san(&board, "Nd2"), None);
+    assert_eq!(BoardMove::from_san(&board, "Qd2"), None);
+    for board in [Board::from_fen(STARTING_FEN), Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")] {
+        for valid_move in board.get_valid_moves() {
+            assert_eq!(BoardMove::from_san(&board, &valid_move.to_san(&board)), Some(valid_move));
+        }
+    }
+}
+
+#[test]
+fn to_fen_test() {
+    for fen in [
+        STARTING_FEN,
+        "r3k2r/1P6/8/8/8/8/8/R3K2R w Kq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "8/8/8/8/6Q1/8/3k4/7K w - - 0 1"
+    ] {
+        assert_eq!(Board::from_fen(fen).to_fen(), fen);
+    }
+}
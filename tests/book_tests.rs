@@ -0,0 +1,224 @@
+use apotheosis::{
+    board::*,
+    book::*,
+    pgn::PgnReader,
+    uci::UciEngine
+};
+use std::sync::{
+    Arc,
+    Mutex
+};
+
+// not polyglot's own table, but any keys do as long as the book was written with them
+fn test_key_values() -> Vec<u64> {
+    let mut state = 0x626f_6f6bu64;
+    (0..POLYGLOT_KEY_COUNT).map(|_| {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mixed_state = (state ^ (state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        let mixed_state = (mixed_state ^ (mixed_state >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        mixed_state ^ (mixed_state >> 31)
+    }).collect()
+}
+fn test_keys() -> PolyglotKeys {
+    PolyglotKeys::from_keys(test_key_values()).unwrap()
+}
+// the way polyglot's source has the table
+fn key_table_text(key_values: &[u64]) -> String {
+    let key_lines = key_values.iter().map(|key| format!("0x{:016X}ULL", key)).collect::<Vec<_>>();
+    format!("const uint64 Random64[781] = {{\n    {}\n}};\n", key_lines.join(",\n    "))
+}
+
+fn book_entry(keys: &PolyglotKeys, board: &Board, uci_move: &str, weight: u16) -> BookEntry {
+    BookEntry {
+        key: keys.key(board),
+        book_move: encode_book_move(board, &BoardMove::from_uci(board, uci_move).unwrap()),
+        weight,
+        learn: 0
+    }
+}
+
+#[test]
+fn loads_keys_from_text_and_bytes() {
+    let key_values = test_key_values();
+    let text_keys = PolyglotKeys::from_text(&key_table_text(&key_values)).unwrap();
+    assert_eq!(text_keys, test_keys());
+    let bytes = key_values.iter().flat_map(|key| key.to_be_bytes()).collect::<Vec<u8>>();
+    assert_eq!(PolyglotKeys::from_bytes(&bytes), Some(text_keys));
+    assert!(PolyglotKeys::from_text("0x1, 0x2").is_none());
+    assert!(PolyglotKeys::from_bytes(&bytes[8..]).is_none());
+}
+
+#[test]
+fn default_keys_are_polyglots() {
+    // the hashes from the polyglot book format description
+    let keys = PolyglotKeys::default();
+    let key = |fen: &str| keys.key(&Board::from_fen(fen));
+    assert_eq!(key(STARTING_FEN), 0x463b_9618_1691_fc9c);
+    assert_eq!(key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"), 0x823c_9b50_fd11_4196);
+    assert_eq!(key("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"), 0x0756_b944_61c5_0fb0);
+    assert_eq!(key("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"), 0x22a4_8b5a_8e47_ff78);
+    assert_eq!(key("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4"), 0x00fd_d303_c946_bdd9);
+    assert_eq!(key("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4"), 0x5c3f_9b82_9b27_9560);
+}
+
+#[test]
+fn keys_follow_castling_side_to_move_and_en_passant() {
+    let keys = test_keys();
+    let key = |fen: &str| keys.key(&Board::from_fen(fen));
+    let starting_key = key(STARTING_FEN);
+    assert_ne!(starting_key, key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"));
+    assert_ne!(starting_key, key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1"));
+    // en passant only counts when there is a pawn to take with
+    assert_eq!(
+        key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    // the same position reached by different move orders
+    let after_moves = |uci_moves: &[&str]| {
+        let mut board = Board::from_fen(STARTING_FEN);
+        for uci_move in uci_moves {
+            board = board.create_board_from_move(&BoardMove::from_uci(&board, uci_move).unwrap());
+        }
+        keys.key(&board)
+    };
+    assert_eq!(after_moves(&["g1f3", "g8f6", "b1c3"]), after_moves(&["b1c3", "g8f6", "g1f3"]));
+}
+
+#[test]
+fn converts_moves_both_ways() {
+    let board = Board::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    // the king takes its own rook, e1h1 and e1a1
+    let kingside_castle = BoardMove::from_uci(&board, "e1g1").unwrap();
+    assert_eq!(encode_book_move(&board, &kingside_castle), 4 << 6 | 7);
+    assert_eq!(decode_book_move(&board, 4 << 6), BoardMove::from_uci(&board, "e1c1"));
+    // b7 takes a8 and becomes a knight
+    assert_eq!(decode_book_move(&board, 1 << 12 | (6 * 8 + 1) << 6 | (7 * 8)), BoardMove::from_uci(&board, "b7a8n"));
+    for fen in [STARTING_FEN, "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1", "r3k2r/8/8/8/3pP3/8/8/R3K2R b KQkq e3 0 1"] {
+        let board = Board::from_fen(fen);
+        for valid_move in board.get_valid_moves() {
+            assert_eq!(decode_book_move(&board, encode_book_move(&board, &valid_move)), Some(valid_move));
+        }
+    }
+    // a book for some other position
+    assert_eq!(decode_book_move(&Board::from_fen(STARTING_FEN), 4 << 6 | 7), None);
+}
+
+#[test]
+fn selects_moves_by_weight() {
+    let keys = test_keys();
+    let board = Board::from_fen(STARTING_FEN);
+    let after_e4 = board.create_board_from_move(&BoardMove::from_uci(&board, "e2e4").unwrap());
+    let book = Book::from_entries(vec![
+        book_entry(&keys, &after_e4, "c7c5", 5),
+        book_entry(&keys, &board, "e2e4", 6),
+        book_entry(&keys, &board, "d2d4", 3),
+        book_entry(&keys, &board, "g1f3", 1),
+        book_entry(&keys, &board, "b1a3", 0),
+        // an entry whose move is not legal gets skipped
+        BookEntry { key: keys.key(&board), book_move: 4 << 6 | 7, weight: 50, learn: 0 }
+    ]);
+    let loaded_book = Book::from_bytes(&book.to_bytes()).unwrap();
+    assert_eq!(loaded_book.entries(), book.entries());
+    assert!(Book::from_bytes(&book.to_bytes()[1..]).is_err());
+
+    assert_eq!(book.moves(&keys, &board).len(), 4);
+    assert_eq!(book.moves(&keys, &after_e4), vec![(BoardMove::from_uci(&after_e4, "c7c5").unwrap(), 5)]);
+    assert!(book.moves(&keys, &Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1")).is_empty());
+    assert_eq!(book.select_move(&keys, &board, BookSelection::BestWeight), BoardMove::from_uci(&board, "e2e4"));
+
+    let mut counts = [0; 3];
+    for random_number in 0..1000 {
+        let book_move = book.select_move(&keys, &board, BookSelection::WeightedRandom(random_number)).unwrap();
+        let move_index = ["e2e4", "d2d4", "g1f3"].iter().position(|uci_move| *uci_move == book_move.to_uci(&board)).unwrap();
+        counts[move_index] += 1;
+    }
+    assert_eq!(counts, [600, 300, 100]);
+}
+
+// the moves of a game in uci notation from the starting position
+fn uci_line(uci_moves: &[&str]) -> Vec<BoardMove> {
+    let mut board = Board::from_fen(STARTING_FEN);
+    uci_moves.iter().map(|uci_move| {
+        let board_move = BoardMove::from_uci(&board, uci_move).unwrap();
+        board = board.create_board_from_move(&board_move);
+        board_move
+    }).collect()
+}
+
+#[test]
+fn builds_books_from_lines() {
+    let board = Board::from_fen(STARTING_FEN);
+    let lines = [(uci_line(&["e2e4", "e7e5"]), 1.0), (uci_line(&["e2e4", "c7c5"]), 0.0), (uci_line(&["d2d4", "d7d5"]), 0.5)];
+    let build = |options: BookBuildOptions| {
+        let mut book_builder = BookBuilder::new(test_keys(), options);
+        for (moves, white_points) in lines.iter() {
+            book_builder.add_line(&board, moves, *white_points);
+        }
+        assert_eq!(book_builder.game_count(), 3);
+        book_builder.build()
+    };
+    let keys = test_keys();
+    let book_moves = |book: &Book, board: &Board| {
+        book.moves(&keys, board).iter().map(|(board_move, weight)| (board_move.to_uci(board), *weight)).collect::<Vec<_>>()
+    };
+    let first_moves = BookBuildOptions { max_ply: 1, ..BookBuildOptions::default() };
+    let book = build(first_moves);
+    // two for a win and one for a draw, most first
+    assert_eq!(book_moves(&book, &board), vec![("e2e4".to_string(), 2), ("d2d4".to_string(), 1)]);
+    assert_eq!(book.entries().len(), 2);
+    let book = build(BookBuildOptions { min_games: 2, ..first_moves });
+    assert_eq!(book_moves(&book, &board), vec![("e2e4".to_string(), 2)]);
+    assert!(build(BookBuildOptions { min_score: 0.6, ..first_moves }).entries().is_empty());
+
+    // black's losing reply is not worth playing
+    let book = build(BookBuildOptions::default());
+    let after_e4 = board.create_board_from_move(&BoardMove::from_uci(&board, "e2e4").unwrap());
+    assert_eq!(book_moves(&book, &after_e4), vec![("c7c5".to_string(), 2)]);
+}
+
+#[test]
+fn builds_books_from_pgn_games() {
+    let pgn = "1. e4 e5 1-0\n\n1. d4 d5 0-1\n\n1. c4 e5 *\n";
+    let mut book_builder = BookBuilder::new(test_keys(), BookBuildOptions::default());
+    // the unfinished game has no result to score its moves with
+    let used_games = PgnReader::new(pgn.as_bytes()).filter(|game| book_builder.add_game(game.as_ref().unwrap())).count();
+    assert_eq!(used_games, 2);
+    let book = book_builder.build();
+    let board = Board::from_fen(STARTING_FEN);
+    let after_d4 = board.create_board_from_move(&BoardMove::from_uci(&board, "d2d4").unwrap());
+    assert_eq!(book.moves(&test_keys(), &board), vec![(BoardMove::from_uci(&board, "e2e4").unwrap(), 2)]);
+    assert_eq!(book.moves(&test_keys(), &after_d4), vec![(BoardMove::from_uci(&after_d4, "d7d5").unwrap(), 2)]);
+}
+
+#[test]
+fn uci_plays_book_moves_with_own_book() {
+    let board = Board::from_fen(STARTING_FEN);
+    let directory = std::env::temp_dir().join(format!("apotheosis_book_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("keys.txt"), key_table_text(&test_key_values())).unwrap();
+    Book::from_entries(vec![book_entry(&test_keys(), &board, "b1a3", 1)]).save(directory.join("book.bin")).unwrap();
+
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = UciEngine::new(output.clone());
+    let mut send = |command: String| engine.handle_command(&command).unwrap();
+    send(format!("setoption name BookFile value {}", directory.join("book.bin").display()));
+    send(format!("setoption name BookKeysFile value {}", directory.join("keys.txt").display()));
+    send("setoption name OwnBook value true".to_string());
+    send("position startpos".to_string());
+    send("go depth 1".to_string());
+    // out of book, so it searches
+    send("position startpos moves b1a3".to_string());
+    send("go depth 1".to_string());
+    engine.finish_search();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    let best_moves = output.lines().filter(|line| line.starts_with("bestmove")).collect::<Vec<_>>();
+    assert_eq!(best_moves.len(), 2);
+    assert_eq!(best_moves[0], "bestmove b1a3");
+    assert!(output.lines().any(|line| line.starts_with("info depth 1")));
+}
@@ -0,0 +1,81 @@
+use apotheosis::{
+    board::*,
+    endgame::*,
+    eval::*
+};
+
+fn white_eval(fen: &str) -> i32 {
+    let board = Board::from_fen(fen);
+    let side_multiplier = if board.active_color() == WHITE { 1 } else { -1 };
+    side_multiplier * eval_board_basic(&board).value()
+}
+
+#[test]
+fn material_signature_counts_pieces() {
+    let board = Board::from_fen("8/8/4k3/2p1b3/8/1PP2B2/4K3/8 w - - 0 1");
+    let signature = MaterialSignature::new(&board.get_pieces());
+    assert_eq!(signature.count(WHITE, PAWN), 2);
+    assert_eq!(signature.count(BLACK, BISHOP), 1);
+    assert_eq!(signature.piece_count(WHITE), 1);
+    assert!(!signature.is_lone_king(BLACK));
+    assert!(!signature.can_force_mate(WHITE));
+    assert!(MaterialSignature::new(&Board::from_fen("8/8/4k3/8/8/8/4K3/8 w - - 0 1").get_pieces()).is_lone_king(WHITE));
+}
+
+#[test]
+fn bare_king_is_driven_to_the_edge() {
+    let cornered = white_eval("k7/8/1K6/8/8/8/8/7Q b - - 0 1");
+    let centralised = white_eval("8/8/8/3k4/8/1K6/8/7Q b - - 0 1");
+    assert!(cornered > centralised, "{} vs {}", cornered, centralised);
+    assert!(centralised > 2000);
+    // and the same for black
+    assert_eq!(white_eval("7q/8/8/8/8/1k6/8/K7 w - - 0 1"), -cornered);
+}
+
+#[test]
+fn bishop_and_knight_mate_in_the_bishops_corner() {
+    // a light squared bishop mates on a8 or h1
+    let right_corner = white_eval("k7/8/1K6/8/8/8/8/4NB2 b - - 0 1");
+    let wrong_corner = white_eval("7k/8/6K1/8/8/8/8/4NB2 b - - 0 1");
+    assert!(right_corner > wrong_corner + 100, "{} vs {}", right_corner, wrong_corner);
+}
+
+#[test]
+fn pawn_outside_the_square_is_winning() {
+    let unstoppable = white_eval("8/8/8/8/7k/8/P7/4K3 b - - 0 1");
+    let caught = white_eval("8/8/8/8/2k5/8/P7/4K3 b - - 0 1");
+    assert!(unstoppable > 2000, "{}", unstoppable);
+    assert!(caught < 500, "{}", caught);
+    // the defending king is one move short of the square unless it is its move
+    assert!(white_eval("8/8/8/8/8/7k/1P6/4K3 w - - 0 1") > 2000);
+    assert!(white_eval("8/8/8/8/8/7k/1P6/4K3 b - - 0 1") < 500);
+}
+
+#[test]
+fn drawish_endings_are_scaled_down() {
+    let draws = [
+        // bare kings, or a lone minor piece
+        "8/8/4k3/8/8/8/4K3/8 w - - 0 1",
+        "8/8/4k3/8/8/8/4K3/4N3 w - - 0 1",
+        "8/8/4k3/8/8/8/4K3/3NN3 w - - 0 1",
+        // a rook pawn with a bishop that does not cover the corner the king holds
+        "7k/8/8/8/8/8/7P/4KB2 w - - 0 1",
+        "7k/8/8/8/7P/8/7P/4KB2 w - - 0 1",
+        // a rook pawn whose corner the king holds
+        "k7/8/8/8/8/8/P7/4K3 w - - 0 1"
+    ];
+    for fen in draws {
+        assert_eq!(trace(&Board::from_fen(fen)).endgame_scale, 0, "{}", fen);
+        assert_eq!(white_eval(fen), 0, "{}", fen);
+    }
+    // the right bishop wins
+    assert_eq!(trace(&Board::from_fen("7k/8/8/8/8/8/7P/4K1B1 w - - 0 1")).endgame_scale, FULL_ENDGAME_SCALE);
+
+    let rook_against_bishop = trace(&Board::from_fen("8/8/8/4k3/8/8/8/3RK1b1 w - - 0 1"));
+    assert!(rook_against_bishop.endgame_scale < FULL_ENDGAME_SCALE / 2);
+    let opposite_bishops = trace(&Board::from_fen("8/8/4k3/2p1b3/8/1PP2B2/4K3/8 w - - 0 1"));
+    let same_bishops = trace(&Board::from_fen("8/8/4k3/2p1b3/8/1PP1B3/4K3/8 w - - 0 1"));
+    assert!(opposite_bishops.endgame_scale < FULL_ENDGAME_SCALE / 2);
+    assert_eq!(same_bishops.endgame_scale, FULL_ENDGAME_SCALE);
+    assert!(opposite_bishops.score() < same_bishops.score());
+}
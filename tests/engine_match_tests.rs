@@ -0,0 +1,76 @@
+use apotheosis::{
+    board::*,
+    engine_match::*,
+    game::*
+};
+
+#[test]
+fn works_out_elo_and_sprt() {
+    let match_score = MatchScore { wins: 60, draws: 0, losses: 40 };
+    assert_eq!(match_score.games(), 100);
+    let elo = match_score.elo().unwrap();
+    assert!((elo - 70.4).abs() < 0.1, "{}", elo);
+    let (lower_elo, upper_elo) = match_score.elo_interval().unwrap();
+    assert!(lower_elo < elo && elo < upper_elo);
+    assert!((score_from_elo(elo) - 0.6).abs() < 1e-9);
+    assert_eq!(MatchScore { wins: 3, draws: 0, losses: 0 }.elo(), None);
+    assert_eq!(MatchScore { wins: 5, draws: 10, losses: 5 }.elo(), Some(0.0));
+
+    let mut match_score = MatchScore::default();
+    match_score.add_result(GameResult::BlackWins, BLACK);
+    match_score.add_result(GameResult::BlackWins, WHITE);
+    match_score.add_result(GameResult::Draw, WHITE);
+    assert_eq!(match_score, MatchScore { wins: 1, draws: 1, losses: 1 });
+
+    let sprt = Sprt::default();
+    let (lower_bound, upper_bound) = sprt.bounds();
+    assert!((lower_bound + 2.944).abs() < 0.001 && (upper_bound - 2.944).abs() < 0.001);
+    assert_eq!(sprt.status(&MatchScore { wins: 10, draws: 20, losses: 10 }), SprtStatus::Continue);
+    assert_eq!(sprt.status(&MatchScore { wins: 700, draws: 1000, losses: 500 }), SprtStatus::AcceptH1);
+    assert_eq!(sprt.status(&MatchScore { wins: 500, draws: 1000, losses: 700 }), SprtStatus::AcceptH0);
+}
+
+#[test]
+fn plays_both_colors_of_every_opening() {
+    let mut first_engine = MatchEngine::internal(&[]).unwrap();
+    let mut second_engine = MatchEngine::internal(&[("Hash".to_string(), "4".to_string())]).unwrap();
+    assert_eq!(second_engine.name, "apotheosis Hash=4");
+    let options = MatchOptions {
+        games: 4,
+        // Ra6! bxa6 b7#, then a pawn ending that neither side can win
+        openings: vec!["kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1".to_string(), "8/8/4k3/8/8/4K3/8/8 w - - 0 1".to_string()],
+        limits: MatchLimits::Depth(4),
+        sprt: None
+    };
+    let mut games = Vec::new();
+    let match_score = run_match([&mut first_engine, &mut second_engine], &options, |game, _| {
+        games.push(game.clone());
+        Ok(())
+    }).unwrap();
+    assert_eq!(match_score, MatchScore { wins: 1, draws: 2, losses: 1 });
+    assert_eq!(games.len(), 4);
+    assert_eq!(games[0].tag("White"), Some("apotheosis"));
+    assert_eq!(games[1].tag("White"), Some("apotheosis Hash=4"));
+    assert_eq!(games[0].outcome(), Some(GameOutcome::Checkmate(WHITE)));
+    assert_eq!(games[0].moves().len(), 3);
+    // the engine's score for the move, from white's side
+    assert_eq!(games[0].annotations()[0].eval.and_then(|eval| eval.mate_moves()), Some(2));
+    assert_eq!(games[2].outcome(), Some(GameOutcome::InsufficientMaterial));
+    assert_eq!(games[3].tag("Round"), Some("4"));
+}
+
+#[test]
+fn talks_to_engines_run_as_programs() {
+    let mut internal_engine = MatchEngine::internal(&[]).unwrap();
+    let mut external_engine = MatchEngine::spawn(env!("CARGO_BIN_EXE_apotheosis"), &[("Hash".to_string(), "4".to_string())]).unwrap();
+    assert_eq!(external_engine.name, "apotheosis");
+    let game = play_game([&mut internal_engine, &mut external_engine], "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", MatchLimits::Depth(4)).unwrap();
+    assert_eq!(game.result, Some(GameResult::WhiteWins));
+    assert_eq!(game.tag("Termination"), None);
+
+    // a clock too short for anything loses on time
+    let clock = MatchLimits::Clock { base: std::time::Duration::ZERO, increment: std::time::Duration::ZERO };
+    let game = play_game([&mut internal_engine, &mut external_engine], STARTING_FEN, clock).unwrap();
+    assert_eq!(game.result, Some(GameResult::BlackWins));
+    assert_eq!(game.tag("Termination"), Some("time forfeit"));
+}
@@ -0,0 +1,45 @@
+use apotheosis::{
+    board::*,
+    epd::*,
+    search::*
+};
+
+#[test]
+fn parses_operations() {
+    let position = EpdPosition::parse(r#"8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - bm Rxb2; id "WAC.002"; c0 "quoted; with a semicolon";"#).unwrap();
+    assert_eq!(position.board, Board::from_fen("8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - 0 1"));
+    assert_eq!(position.id(), Some("WAC.002"));
+    assert_eq!(position.comment(0), Some("quoted; with a semicolon"));
+    assert_eq!(position.best_moves().unwrap(), vec![BoardMove::from_uci(&position.board, "b3b2").unwrap()]);
+    assert!(position.avoid_moves().unwrap().is_empty());
+
+    // several operands, uci moves and a last operation without its semicolon
+    let position = EpdPosition::parse("kbK5/pp6/1P6/8/8/8/8/R7 w - - am a1a2 Kd7; dm 2").unwrap();
+    assert_eq!(position.avoid_moves().unwrap().len(), 2);
+    assert_eq!(position.mate_moves(), Some(2));
+    assert_eq!(position.operands("dm"), Some(&["2".to_string()][..]));
+
+    assert!(EpdPosition::parse("kbK5/pp6/1P6/8/8/8/8/R7 w - - bm Qh1;").unwrap().best_moves().is_err());
+    assert!(EpdPosition::parse("kbK5/pp6/1P6/8/8/8 w - - bm Ra6;").is_err());
+    assert!(EpdPosition::parse("kbK5/pp6/1P6/8/8/8/8/R6p w - - bm Ra6;").is_err());
+    assert!(EpdPosition::parse(r#"kbK5/pp6/1P6/8/8/8/8/R7 w - - id "open;"#).is_err());
+    let error = parse_epd("# comment\n\nkbK5/pp6/1P6/8/8/8/8/R7 w - - dm 2;\nnonsense\n").unwrap_err();
+    assert!(error.to_string().contains("line 4"), "{}", error);
+}
+
+#[test]
+fn scores_searches_against_the_suite() {
+    let mut searcher = Searcher::new();
+    let limits = SearchLimits::new_depth_limited(4);
+    let run = |searcher: &mut Searcher, epd_line: &str| {
+        run_epd_position(searcher, &EpdPosition::parse(epd_line).unwrap(), &limits).unwrap()
+    };
+    // Ra6! bxa6 b7#
+    let outcome = run(&mut searcher, "kbK5/pp6/1P6/8/8/8/8/R7 w - - bm Ra6; dm 2;");
+    assert_eq!(outcome.solved, Some(true));
+    assert_eq!(outcome.score.mate_moves(), Some(2));
+    assert_eq!(run(&mut searcher, "kbK5/pp6/1P6/8/8/8/8/R7 w - - am Ra6;").solved, Some(false));
+    assert_eq!(run(&mut searcher, "kbK5/pp6/1P6/8/8/8/8/R7 w - - bm Ra2;").solved, Some(false));
+    assert_eq!(run(&mut searcher, "kbK5/pp6/1P6/8/8/8/8/R7 w - - dm 1;").solved, Some(false));
+    assert_eq!(run(&mut searcher, "kbK5/pp6/1P6/8/8/8/8/R7 w - - id \"no answer\";").solved, None);
+}
@@ -0,0 +1,157 @@
+use apotheosis::{
+    board::*,
+    eval::*,
+    eval_params::EVAL_PARAMS,
+    pawns::*,
+    score::Score
+};
+
+// flips the board vertically and swaps the colors of everything on it
+fn mirror_fen(fen: &str) -> String {
+    let fen_parts = fen.split_whitespace().collect::<Vec<&str>>();
+    let swap_case = |fen_part: &str| fen_part.chars().map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() }).collect::<String>();
+    let ranks = fen_parts[0].split('/').rev().collect::<Vec<&str>>().join("/");
+    let active_color = if fen_parts[1] == "w" { "b" } else { "w" };
+    let mut castling = swap_case(fen_parts[2]).chars().collect::<Vec<char>>();
+    castling.sort_unstable();
+    let en_passant = match fen_parts[3] {
+        "-" => "-".to_string(),
+        square => format!("{}{}", &square[0..1], 9 - square[1..].parse::<u8>().unwrap())
+    };
+    format!("{} {} {} {} {} {}", swap_case(&ranks), active_color, castling.iter().collect::<String>(), en_passant, fen_parts[4], fen_parts[5])
+}
+
+#[test]
+fn mirrored_positions_evaluate_the_same() {
+    let fens = [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "4k3/8/8/8/8/8/3Q4/4K3 b - - 0 1",
+        "r1bq1rk1/pp3ppp/2n5/3Np3/4P3/8/PPP2PPP/R2QKB1R w KQ - 0 1"
+    ];
+    for fen in fens {
+        let mirrored_fen = mirror_fen(fen);
+        assert_eq!(eval_board_basic(&Board::from_fen(fen)), eval_board_basic(&Board::from_fen(&mirrored_fen)), "{} and {}", fen, mirrored_fen);
+    }
+}
+
+#[test]
+fn starting_position_is_balanced() {
+    assert_eq!(eval_board_basic(&Board::from_fen(STARTING_FEN)), Score::DRAW);
+}
+
+#[test]
+fn extra_material_is_good_for_its_owner() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/3Q4/4K3 w - - 0 1");
+    assert!(eval_board_basic(&board).value() > 800);
+    let board = Board::from_fen("4k3/8/8/8/8/8/3Q4/4K3 b - - 0 1");
+    assert!(eval_board_basic(&board).value() < -800);
+}
+
+#[test]
+fn game_phase_goes_from_opening_to_endgame() {
+    assert_eq!(game_phase(&Board::from_fen(STARTING_FEN).get_pieces(), &EVAL_PARAMS), 0);
+    assert_eq!(game_phase(&Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").get_pieces(), &EVAL_PARAMS), MAX_GAME_PHASE);
+}
+
+#[test]
+fn knight_outposts_are_rewarded() {
+    // the same knight, once on a square the c pawn can still chase it from
+    let outpost_board = Board::from_fen("4k3/p5pp/8/3N4/4P3/8/6PP/4K3 w - - 0 1");
+    let chased_board = Board::from_fen("4k3/2p3pp/8/3N4/4P3/8/6PP/4K3 w - - 0 1");
+    assert!(eval_board_basic(&outpost_board) > eval_board_basic(&chased_board));
+}
+
+#[test]
+fn passed_pawns_are_found() {
+    // b5 is passed, d4 has the e6 pawn in front of its neighbouring file and h2 runs into h7
+    let board = Board::from_fen("4k3/7p/4p3/1P6/3P4/8/7P/4K3 w - - 0 1");
+    let pawn_structure = eval_pawn_structure(&board.get_pieces(), &EVAL_PARAMS);
+    assert_eq!(pawn_structure.passed_pawns, BoardSquare::from("b5").mask());
+}
+
+#[test]
+fn passed_pawn_gains_by_advancing() {
+    let pawn_terms = |fen: &str| {
+        let eval_trace = trace(&Board::from_fen(fen));
+        eval_trace.term(EvalTerm::PawnStructure, WHITE) + eval_trace.term(EvalTerm::PassedPawns, WHITE)
+    };
+    let sixth_rank_terms = pawn_terms("4k3/8/P7/8/8/8/8/4K3 w - - 0 1");
+    let seventh_rank_terms = pawn_terms("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    assert!(seventh_rank_terms.mg() > sixth_rank_terms.mg() && seventh_rank_terms.eg() > sixth_rank_terms.eg());
+}
+
+#[test]
+fn broken_pawn_structure_is_penalised() {
+    let healthy_board = Board::from_fen("4k3/8/8/8/8/8/PPP5/4K3 w - - 0 1");
+    let doubled_isolated_board = Board::from_fen("4k3/8/8/8/8/P7/P1P5/4K3 w - - 0 1");
+    let healthy_score = eval_pawn_structure(&healthy_board.get_pieces(), &EVAL_PARAMS).scores[WHITE as usize];
+    let broken_score = eval_pawn_structure(&doubled_isolated_board.get_pieces(), &EVAL_PARAMS).scores[WHITE as usize];
+    assert!(broken_score.mg() < healthy_score.mg() && broken_score.eg() < healthy_score.eg());
+}
+
+#[test]
+fn pawn_key_only_depends_on_pawns() {
+    let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let moved_pieces_board = Board::from_fen("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3");
+    let moved_pawn_board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/3PP3/5N2/PPP2PPP/RNBQKB1R b KQkq - 0 3");
+    assert_eq!(board.pawn_zobrist_key(), moved_pieces_board.pawn_zobrist_key());
+    assert_ne!(board.pawn_zobrist_key(), moved_pawn_board.pawn_zobrist_key());
+}
+
+#[test]
+fn pawn_table_matches_uncached_evaluation() {
+    let mut pawn_table = PawnTable::new(DEFAULT_PAWN_TABLE_SIZE_KB);
+    let fens = [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"
+    ];
+    // the second round is answered from the table
+    for _ in 0..2 {
+        for fen in fens {
+            let board = Board::from_fen(fen);
+            assert_eq!(eval_board(&board, &mut pawn_table), eval_board_basic(&board), "{}", fen);
+        }
+    }
+}
+
+#[test]
+fn king_without_a_pawn_shield_is_penalised() {
+    // the same pawns on the same ranks, once in front of the king and once on the other wing
+    let sheltered_board = Board::from_fen("3r2k1/5ppp/8/8/3q4/8/5PPP/3Q1RK1 w - - 0 1");
+    let exposed_board = Board::from_fen("3r2k1/5ppp/8/8/3q4/8/PPP5/3Q1RK1 w - - 0 1");
+    assert!(eval_board_basic(&sheltered_board) > eval_board_basic(&exposed_board) + 50);
+}
+
+#[test]
+fn trace_adds_up_to_the_evaluation() {
+    let fens = [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "3r2k1/5ppp/8/8/3q4/8/PPP5/3Q1RK1 w - - 0 1"
+    ];
+    for fen in fens {
+        let board = Board::from_fen(fen);
+        let eval_trace = trace(&board);
+        let mut total = TaperedScore::default();
+        for term in EvalTerm::ALL {
+            total += eval_trace.term(term, WHITE) - eval_trace.term(term, BLACK);
+        }
+        let side_multiplier = if board.active_color() == WHITE { 1 } else { -1 };
+        assert_eq!(side_multiplier * total.taper(eval_trace.game_phase), eval_board_basic(&board).value(), "{}", fen);
+        assert_eq!(eval_trace.score(), eval_board_basic(&board), "{}", fen);
+    }
+}
+
+#[test]
+fn trace_prints_every_term() {
+    let printed_trace = trace(&Board::from_fen(STARTING_FEN)).to_string();
+    for term in EvalTerm::ALL {
+        assert!(printed_trace.contains(term.name()), "{} is missing", term.name());
+    }
+}
@@ -0,0 +1,68 @@
+use apotheosis::{
+    board::*,
+    game::*
+};
+
+fn play_uci_moves(game: &mut Game, uci_moves: &[&str]) {
+    for uci_move in uci_moves {
+        let board_move = BoardMove::from_uci(&game.current_board, uci_move).unwrap();
+        assert!(game.play_move(&board_move));
+    }
+}
+
+#[test]
+fn counts_moves_and_undoes_them() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 7 30");
+    assert_eq!((game.halfmove, game.fullmove_number), (7, 30));
+    play_uci_moves(&mut game, &["e1d1", "e8d8", "e2e4"]);
+    assert_eq!((game.halfmove, game.fullmove_number), (0, 31));
+    assert_eq!(game.moves().len(), 3);
+    assert_eq!(game.history().len(), 3);
+    let illegal_move = BoardMove::from_uci(&Board::from_fen(STARTING_FEN), "e2e4").unwrap();
+    assert!(!game.play_move(&illegal_move));
+    let pawn_push = BoardMove::from_uci(&game.history()[2], "e2e4");
+    assert_eq!(game.undo_move(), pawn_push);
+    assert_eq!((game.halfmove, game.fullmove_number), (9, 31));
+    game.undo_move();
+    game.undo_move();
+    assert_eq!(game.current_board, game.starting_board);
+    assert_eq!(game.undo_move(), None);
+}
+
+#[test]
+fn ends_by_the_rules() {
+    let mut game = Game::from_fen(STARTING_FEN);
+    play_uci_moves(&mut game, &["f2f3", "e7e5", "g2g4"]);
+    assert_eq!(game.outcome(), None);
+    play_uci_moves(&mut game, &["d8h4"]);
+    assert_eq!(game.outcome(), Some(GameOutcome::Checkmate(BLACK)));
+    assert_eq!(game.outcome().unwrap().result(), GameResult::BlackWins);
+
+    assert_eq!(Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").outcome(), Some(GameOutcome::Stalemate));
+    assert_eq!(Game::from_fen("4k3/8/8/8/8/8/8/4KB2 w - - 0 1").outcome(), Some(GameOutcome::InsufficientMaterial));
+    assert_eq!(Game::from_fen("2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1").outcome(), Some(GameOutcome::InsufficientMaterial));
+    assert_eq!(Game::from_fen("1b2k3/8/8/8/8/8/8/4KB2 w - - 0 1").outcome(), None);
+    assert_eq!(Game::from_fen("4k3/8/8/8/8/8/8/4KR2 w - - 100 80").outcome(), Some(GameOutcome::FiftyMoves));
+
+    let mut game = Game::from_fen(STARTING_FEN);
+    play_uci_moves(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"]);
+    assert_eq!(game.outcome(), None);
+    play_uci_moves(&mut game, &["f6g8"]);
+    assert_eq!(game.outcome(), Some(GameOutcome::Repetition));
+    game.undo_move();
+    assert_eq!(game.outcome(), None);
+}
+
+#[test]
+fn keeps_the_starting_counters() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 7 30");
+    play_uci_moves(&mut game, &["e8d8", "e1d1", "d8e8"]);
+    assert_eq!(game.starting_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 7 30");
+    assert_eq!(game.fen(), "4k3/8/8/8/8/8/4P3/3K4 w - - 10 32");
+    // fool's mate, with nothing recorded
+    let mut game = Game::from_fen(STARTING_FEN);
+    play_uci_moves(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+    assert_eq!(game.adjudicated_result(), Some(GameResult::BlackWins));
+    game.result = Some(GameResult::Draw);
+    assert_eq!(game.adjudicated_result(), Some(GameResult::Draw));
+}
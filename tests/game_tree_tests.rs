@@ -0,0 +1,78 @@
+use apotheosis::{
+    board::*,
+    game::*,
+    game_tree::*,
+    pgn::game_to_pgn
+};
+
+fn add_uci_moves(tree: &mut GameTree, node_id: NodeId, uci_moves: &[&str]) -> NodeId {
+    uci_moves.iter().fold(node_id, |parent_id, uci_move| {
+        let board_move = BoardMove::from_uci(tree.node(parent_id).board(), uci_move).unwrap();
+        tree.add_move(parent_id, &board_move).unwrap()
+    })
+}
+
+#[test]
+fn adds_promotes_and_deletes_variations() {
+    let mut tree = GameTree::new(Board::from_fen(STARTING_FEN));
+    let root = tree.root();
+    let mainline_end = add_uci_moves(&mut tree, root, &["e2e4", "e7e5", "g1f3"]);
+    let e4 = tree.node(root).main_child().unwrap();
+    let sicilian = add_uci_moves(&mut tree, e4, &["c7c5"]);
+    let french_end = add_uci_moves(&mut tree, e4, &["e7e6", "d2d4"]);
+    // the same move twice is the same node
+    assert_eq!(add_uci_moves(&mut tree, e4, &["c7c5"]), sicilian);
+    assert!(tree.add_move(e4, &BoardMove::from_uci(tree.node(root).board(), "d2d4").unwrap()).is_none());
+
+    assert_eq!(tree.mainline().collect::<Vec<_>>().last(), Some(&mainline_end));
+    assert_eq!(tree.node(e4).children().len(), 3);
+    assert_eq!(tree.node(mainline_end).ply(), 3);
+    assert_eq!(tree.node(sicilian).parent(), Some(e4));
+    assert_eq!(tree.path_to(french_end).len(), 3);
+    assert!(!tree.is_on_mainline(french_end));
+
+    assert!(tree.promote_variation(sicilian));
+    assert_eq!(tree.node(e4).main_child(), Some(sicilian));
+    assert_eq!(tree.mainline().count(), 2);
+    assert!(tree.promote_to_mainline(french_end));
+    assert!(tree.is_on_mainline(french_end));
+    assert_eq!(tree.mainline().last(), Some(french_end));
+    assert!(!tree.promote_variation(root));
+
+    let e6 = tree.node(french_end).parent().unwrap();
+    assert!(tree.delete_variation(e6));
+    assert!(!tree.contains(e6) && !tree.contains(french_end));
+    assert_eq!(tree.mainline().last(), Some(sicilian));
+    assert!(!tree.delete_variation(root));
+    assert!(!tree.delete_variation(e6));
+}
+
+#[test]
+fn games_move_around_their_tree() {
+    let mut game = Game::from_fen(STARTING_FEN);
+    for uci_move in ["e2e4", "e7e5", "g1f3"] {
+        assert!(game.play_move(&BoardMove::from_uci(&game.current_board, uci_move).unwrap()));
+    }
+    let nf3 = game.current_node();
+    assert_eq!(game.go_back().map(|board_move| board_move.to_uci(&game.current_board)), Some("g1f3".to_string()));
+    assert!(game.play_move(&BoardMove::from_uci(&game.current_board, "f1c4").unwrap()));
+    let bc4 = game.current_node();
+    assert_eq!(game.tree().mainline().last(), Some(nf3));
+
+    assert!(game.go_to(nf3));
+    assert_eq!(game.moves().len(), 3);
+    assert_eq!(game.fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+    game.node_annotation_mut(bc4).unwrap().comments.push("the Bishop's Opening".to_string());
+    assert!(game_to_pgn(&game).contains("2. Nf3 (2. Bc4 {the Bishop's Opening}) *"));
+
+    // deleting the line the game is on takes the game back to before it
+    let e5 = game.tree().node(nf3).parent().unwrap();
+    assert!(game.delete_variation(e5));
+    assert_eq!(game.moves().len(), 1);
+    assert_eq!(game.fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    assert_eq!(game.tree().mainline().count(), 1);
+
+    // an undone move is gone for good
+    assert!(game.undo_move().is_some());
+    assert!(game.tree().node(game.tree().root()).children().is_empty());
+}
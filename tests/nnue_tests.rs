@@ -0,0 +1,108 @@
+use apotheosis::{
+    board::*,
+    nnue::*,
+    search::*
+};
+use std::sync::Arc;
+
+const TEST_POSITIONS: [&str; 5] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 3 3",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3Q2K1 b - - 0 1"
+];
+
+fn load_tiny_network() -> Network {
+    Network::load("tests/data/tiny.nnue").unwrap()
+}
+
+// a network with a set of weights for every king square, to exercise the refresh when a king moves
+fn generate_bucketed_network() -> Network {
+    let hidden_size = 2;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&NETWORK_MAGIC);
+    bytes.extend_from_slice(&NETWORK_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&64u32.to_le_bytes());
+    bytes.extend_from_slice(&(hidden_size as u32).to_le_bytes());
+    let mut state = 0x6275_636bu64;
+    let mut next_value = |range: i64| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (((state >> 33) as i64) % (range * 2 + 1) - range) as i16
+    };
+    for _ in 0..64 * 768 * hidden_size {
+        bytes.extend_from_slice(&next_value(40).to_le_bytes());
+    }
+    for _ in 0..hidden_size {
+        bytes.extend_from_slice(&(next_value(30) + 30).to_le_bytes());
+    }
+    for _ in 0..hidden_size * 2 {
+        bytes.push(next_value(50) as i8 as u8);
+    }
+    bytes.extend_from_slice(&(next_value(2000) as i32).to_le_bytes());
+    Network::from_bytes(&bytes).unwrap()
+}
+
+fn assert_updates_match_refresh(network: &Network, fen: &str, uci_moves: &[&str]) {
+    let mut board = Board::from_fen(fen);
+    let mut accumulator = network.new_accumulator(&board);
+    for uci_move in uci_moves {
+        let board_move = BoardMove::from_uci(&board, uci_move).unwrap();
+        assert!(board.get_valid_moves().contains(&board_move), "{} is not valid", uci_move);
+        let moved_board = board.create_board_from_move(&board_move);
+        let mut moved_accumulator = Accumulator::default();
+        network.update(&accumulator, &board, &mut moved_accumulator, &moved_board);
+        assert_eq!(moved_accumulator, network.new_accumulator(&moved_board), "after {}", uci_move);
+        assert_eq!(network.evaluate(&moved_accumulator, moved_board.active_color()), network.evaluate_board(&moved_board));
+        board = moved_board;
+        accumulator = moved_accumulator;
+    }
+}
+
+#[test]
+fn quantised_eval_matches_reference() {
+    let network = load_tiny_network();
+    assert_eq!(network.hidden_size(), 8);
+    for fen in TEST_POSITIONS.iter() {
+        let board = Board::from_fen(fen);
+        let quantised_eval = network.evaluate_board(&board);
+        let reference_eval = network.evaluate_reference(&board);
+        assert!((quantised_eval as f64 - reference_eval).abs() <= 1.0, "{}: {} vs {}", fen, quantised_eval, reference_eval);
+    }
+}
+
+#[test]
+fn incremental_updates_match_refresh() {
+    let tiny_network = load_tiny_network();
+    let bucketed_network = generate_bucketed_network();
+    for network in [&tiny_network, &bucketed_network] {
+        // castling on both sides and captures
+        assert_updates_match_refresh(network, TEST_POSITIONS[2], &["e1g1", "e8c8", "d5e6", "h3g2", "g1g2", "d7e6"]);
+        // en passant
+        assert_updates_match_refresh(network, "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", &["e5f6", "g8f6", "e1e2"]);
+        // promotion with and without a capture
+        assert_updates_match_refresh(network, "1r5k/P6p/8/8/8/8/1p4PP/R5BK b - - 0 1", &["b2a1q", "a7b8n", "a1b1", "h2h3"]);
+    }
+}
+
+#[test]
+fn rejects_malformed_networks() {
+    let bytes = std::fs::read("tests/data/tiny.nnue").unwrap();
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(Network::from_bytes(&wrong_magic).is_err());
+    assert!(Network::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Network::from_bytes(&bytes[..8]).is_err());
+    let mut wrong_buckets = bytes;
+    wrong_buckets[8] = 2;
+    assert!(Network::from_bytes(&wrong_buckets).is_err());
+}
+
+#[test]
+fn searches_with_network() {
+    let board = Board::from_fen(TEST_POSITIONS[1]);
+    let mut searcher = Searcher::new();
+    searcher.network = Some(Arc::new(load_tiny_network()));
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(3), |_| {});
+    assert!(board.get_valid_moves().contains(&result.best_move.unwrap()));
+}
@@ -0,0 +1,195 @@
+use apotheosis::{
+    board::*,
+    game::*,
+    game_tree::*,
+    pgn::*,
+    score::Score
+};
+use std::time::Duration;
+
+const TWO_GAMES: &str = r#"[Event "Casual \"blitz\""]
+[White "Morphy"]
+[Black "Allies"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 {Philidor's defence,
+over two lines} 3. d4 Bg4?! (3... exd4 4. Nxd4 $1) 4. dxe5 Bxf3 5. Qxf3 dxe5
+6. Bc4 Nf6 7. Qb3 Qe7 ; a line comment
+8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7
+14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+
+[Event "Set up"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1.e4 Kd7 2.e5 *
+"#;
+
+// the node's move and the mainline after it
+fn san_moves(tree: &GameTree, node_id: NodeId) -> Vec<String> {
+    std::iter::once(node_id).chain(tree.line_from(node_id)).map(|line_node| {
+        let board_before = tree.node(tree.node(line_node).parent().unwrap()).board();
+        tree.node(line_node).board_move().unwrap().to_san(board_before)
+    }).collect()
+}
+
+#[test]
+fn reads_games_one_after_another() {
+    let games = PgnReader::new(TWO_GAMES.as_bytes()).collect::<Vec<_>>();
+    assert_eq!(games.len(), 2);
+    let opera_game = games[0].as_ref().unwrap();
+    assert_eq!(opera_game.tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(opera_game.tag("White"), Some("Morphy"));
+    assert_eq!(opera_game.result, Some(GameResult::WhiteWins));
+    // the side line is kept with the move it replaces, not played
+    assert_eq!(opera_game.moves().len(), 33);
+    assert_eq!(opera_game.outcome(), Some(GameOutcome::Checkmate(WHITE)));
+
+    let set_up_game = games[1].as_ref().unwrap();
+    assert_eq!(set_up_game.starting_board, Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    assert_eq!(set_up_game.moves().len(), 3);
+    assert_eq!(set_up_game.result, None);
+}
+
+#[test]
+fn keeps_going_after_a_bad_game() {
+    let pgn = "[Event \"one\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n\n[Event \"two\"]\n\n1. d4 0-1\n\n1. c4\n";
+    let games = PgnReader::new(pgn.as_bytes()).collect::<Vec<_>>();
+    assert_eq!(games.len(), 3);
+    let error = games[0].as_ref().unwrap_err();
+    assert!(error.to_string().contains("game 1: Ke3 is not a legal move after e4 e5"), "{}", error);
+    assert_eq!(games[1].as_ref().unwrap().result, Some(GameResult::BlackWins));
+    // cut off without a result
+    assert_eq!(games[2].as_ref().unwrap().moves().len(), 1);
+}
+
+#[test]
+fn splits_movetext_into_tokens() {
+    let pgn = "[Event \"?\"]\n\n12.e4!? {a {b} 12...Nf6 $14 (12... d5) 1/2-1/2\n";
+    let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    // brace comments do not nest, the first closing brace ends it
+    let board = Board::from_fen(STARTING_FEN);
+    assert_eq!(game.moves()[0].to_san(&board), "e4");
+    assert_eq!(game.moves().len(), 2);
+    assert_eq!(game.result, Some(GameResult::Draw));
+    assert!(is_valid_fen(STARTING_FEN));
+    assert!(!is_valid_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1"));
+}
+
+#[test]
+fn fens_that_cannot_be_played_are_invalid() {
+    // pawns on the first or last rank
+    assert!(!is_valid_fen("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+    assert!(!is_valid_fen("4k3/8/8/8/8/8/8/p3K3 b - - 0 1"));
+    // a missing or an extra king
+    assert!(!is_valid_fen("8/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    assert!(!is_valid_fen("4k3/8/8/8/8/8/8/8 b - - 0 1"));
+    assert!(!is_valid_fen("4k3/8/8/8/8/8/8/K3K3 w - - 0 1"));
+    assert!(is_valid_fen("4k3/P7/8/8/8/8/7p/4K3 w - - 0 1"));
+    // neither gets as far as the board from a fen tag
+    let pgn = "[FEN \"4k3/8/8/8/8/8/8/p3K3 b - - 0 1\"]\n[SetUp \"1\"]\n\n*\n";
+    assert!(PgnReader::new(pgn.as_bytes()).next().unwrap().is_err());
+}
+
+#[test]
+fn keeps_comments_nags_and_variations() {
+    let opera_game = PgnReader::new(TWO_GAMES.as_bytes()).next().unwrap().unwrap();
+    let annotations = opera_game.annotations();
+    assert_eq!(annotations.len(), 33);
+    assert_eq!(annotations[3].comments, vec!["Philidor's defence, over two lines".to_string()]);
+    assert_eq!(annotations[5].nags, vec![6]);
+    assert_eq!(annotations[13].comments, vec!["a line comment".to_string()]);
+    assert_eq!(*annotations[6], MoveAnnotation::default());
+
+    // the side line is a second child of the position before Bg4
+    let tree = opera_game.tree();
+    let bg4 = tree.mainline().nth(5).unwrap();
+    let siblings = tree.node(tree.node(bg4).parent().unwrap()).children();
+    assert_eq!(siblings.len(), 2);
+    assert_eq!(san_moves(tree, siblings[1]), vec!["exd4", "Nxd4"]);
+    assert_eq!(tree.node(tree.line_from(siblings[1]).next().unwrap()).annotation.nags, vec![1]);
+
+    let pgn = "{Before anything} 1. e4 (1. d4 d5 (1... Nf6 2. c4 {Indian}) 2. c4) (1. c4) e5 *\n";
+    let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let tree = game.tree();
+    assert_eq!(game.annotations()[0].comments_before, vec!["Before anything".to_string()]);
+    let first_moves = tree.node(tree.root()).children();
+    assert_eq!(first_moves.len(), 3);
+    assert_eq!(san_moves(tree, first_moves[1]), vec!["d4", "d5", "c4"]);
+    assert_eq!(san_moves(tree, first_moves[2]), vec!["c4"]);
+    let d5 = tree.line_from(first_moves[1]).next().unwrap();
+    let nested_variation = tree.node(first_moves[1]).children()[1];
+    assert_eq!(tree.node(d5).parent(), tree.node(nested_variation).parent());
+    assert_eq!(san_moves(tree, nested_variation), vec!["Nf6", "c4"]);
+    let nested_c4 = tree.line_from(nested_variation).next().unwrap();
+    assert_eq!(tree.node(nested_c4).annotation.comments, vec!["Indian".to_string()]);
+    assert_eq!(game.moves().len(), 2);
+
+    let bad_variation = "1. e4 (1. d4 Nf3) e5 *\n";
+    let error = PgnReader::new(bad_variation.as_bytes()).next().unwrap().unwrap_err();
+    assert!(error.to_string().contains("Nf3 is not a legal move in a variation after d4"), "{}", error);
+    let early_variation = "(1. d4) 1. e4 *\n";
+    assert!(PgnReader::new(early_variation.as_bytes()).next().unwrap().is_err());
+}
+
+#[test]
+fn puts_the_seven_tag_roster_first() {
+    let pgn = "[Opening \"Philidor\"]\n[White \"Morphy\"]\n[Event \"Paris\"]\n\n1. e4 e5 1-0\n";
+    let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let tag_names = game.tags.iter().map(|(tag_name, _)| tag_name.as_str()).collect::<Vec<_>>();
+    assert_eq!(tag_names, vec!["Event", "Site", "Date", "Round", "White", "Black", "Result", "Opening"]);
+    assert_eq!(game.tag("Event"), Some("Paris"));
+    assert_eq!(game.tag("Site"), Some("?"));
+    assert_eq!(game.tag("Date"), Some("????.??.??"));
+    // the result token fills in the missing tag
+    assert_eq!(game.tag("Result"), Some("1-0"));
+}
+
+#[test]
+fn writes_games_that_read_back_the_same() {
+    for game in PgnReader::new(TWO_GAMES.as_bytes()) {
+        let game = game.unwrap();
+        let pgn = game_to_pgn(&game);
+        assert!(pgn.lines().all(|line| line.len() <= 80), "{}", pgn);
+        let read_game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(read_game.moves(), game.moves());
+        assert_eq!(read_game.annotations(), game.annotations());
+        assert_eq!(read_game.starting_board, game.starting_board);
+        assert_eq!(game_to_pgn(&read_game), pgn);
+    }
+    let opera_pgn = game_to_pgn(&PgnReader::new(TWO_GAMES.as_bytes()).next().unwrap().unwrap());
+    assert!(opera_pgn.starts_with("[Event \"Casual \\\"blitz\\\"\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n"), "{}", opera_pgn);
+    assert!(opera_pgn.contains("3. d4 Bg4 $6 (3... exd4\n4. Nxd4 $1) 4. dxe5"), "{}", opera_pgn);
+    assert!(opera_pgn.ends_with("17. Rd8# 1-0\n\n"), "{}", opera_pgn);
+
+    let set_up_pgn = game_to_pgn(&PgnReader::new(TWO_GAMES.as_bytes()).nth(1).unwrap().unwrap());
+    assert!(set_up_pgn.contains("[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n"), "{}", set_up_pgn);
+    assert!(set_up_pgn.contains("\n\n1. e4 Kd7 2. e5 *\n"), "{}", set_up_pgn);
+}
+
+#[test]
+fn writes_clocks_evals_and_the_adjudicated_result() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 12");
+    for uci_move in ["e8d7", "e2e4"] {
+        assert!(game.play_move(&BoardMove::from_uci(&game.current_board, uci_move).unwrap()));
+    }
+    let first_move = game.annotation_mut(0).unwrap();
+    first_move.clock = Some(Duration::from_millis(3_723_500));
+    first_move.eval = Some(Score::from_centipawns(-35));
+    let second_move = game.annotation_mut(1).unwrap();
+    second_move.eval = Some(Score::mate_in(5));
+    second_move.comments.push("winning".to_string());
+    let pgn = game_to_pgn(&game);
+    assert!(pgn.contains("[Result \"*\"]"), "{}", pgn);
+    assert!(pgn.contains("12... Kd7 {[%eval -0.35] [%clk 1:02:03.5]} 13. e4 {[%eval #3] winning} *"), "{}", pgn);
+    let read_game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    assert_eq!(read_game.annotations(), game.annotations());
+
+    // mated on the board, though nobody wrote the result down
+    let mut game = Game::from_fen(STARTING_FEN);
+    for uci_move in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        assert!(game.play_move(&BoardMove::from_uci(&game.current_board, uci_move).unwrap()));
+    }
+    let pgn = game_to_pgn(&game);
+    assert!(pgn.contains("[Result \"0-1\"]") && pgn.ends_with("2. g4 Qh4# 0-1\n\n"), "{}", pgn);
+}
@@ -0,0 +1,55 @@
+use apotheosis::{
+    board::*,
+    game::*,
+    play::*
+};
+
+fn play_script(options: PlayOptions, script: &str) -> (Game, String) {
+    let mut output = Vec::new();
+    let game = PlaySession::new(script.as_bytes(), &mut output, options).play().unwrap();
+    (game, String::from_utf8(output).unwrap())
+}
+fn quick_options(fen: &str, human_color: BoardColor) -> PlayOptions {
+    PlayOptions { human_color, starting_fen: fen.to_string(), engine_depth: Some(1), ..PlayOptions::default() }
+}
+
+#[test]
+fn renders_the_board_both_ways() {
+    let board = Board::from_fen(STARTING_FEN);
+    assert_eq!(render_board(&board, false), board.to_string());
+    let flipped_lines = render_board(&board, true).lines().map(str::to_string).collect::<Vec<_>>();
+    assert_eq!(flipped_lines[0], "  hgfedcba");
+    assert_eq!(flipped_lines[2], "1 RNBKQBNR");
+    assert_eq!(flipped_lines[9], "8 rnbkqbnr");
+}
+
+#[test]
+fn takes_commands_and_moves() {
+    let (game, output) = play_script(quick_options(STARTING_FEN, WHITE), "flip\nNf6\ne4\nundo\nhint\ng1f3\nresign\n");
+    assert!(output.contains("  hgfedcba"), "{}", output);
+    assert!(output.contains("Nf6 is not a legal move here"), "{}", output);
+    assert!(output.contains("the engine plays"), "{}", output);
+    assert!(output.contains("hint: "), "{}", output);
+    assert!(output.contains("white 5:0"), "{}", output);
+    assert_eq!(game.result, Some(GameResult::BlackWins));
+    // e4 and its reply were taken back, Nf3 and its reply stayed
+    assert_eq!(game.moves().len(), 2);
+    assert_eq!(game.moves()[0], BoardMove::from_uci(&Board::from_fen(STARTING_FEN), "g1f3").unwrap());
+    assert_eq!(game.tag("White"), Some("you"));
+
+    // the engine opens as white, and there is nothing to undo yet
+    let (game, output) = play_script(quick_options(STARTING_FEN, BLACK), "undo\nquit\n");
+    assert!(output.contains("nothing of yours to take back"), "{}", output);
+    assert_eq!(game.moves().len(), 1);
+    assert_eq!(game.result, None);
+}
+
+#[test]
+fn the_engine_only_takes_draws_it_is_not_winning() {
+    let (game, output) = play_script(quick_options("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", WHITE), "Kd2\noffer draw\n");
+    assert!(output.contains("accepts the draw"), "{}", output);
+    assert_eq!(game.result, Some(GameResult::Draw));
+    let (game, output) = play_script(quick_options("4k3/4q3/8/8/8/8/8/4K3 w - - 0 1", WHITE), "draw\nKd2\ndraw\n");
+    assert_eq!(output.matches("declines the draw").count(), 2, "{}", output);
+    assert_eq!(game.result, None);
+}
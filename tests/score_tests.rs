@@ -0,0 +1,65 @@
+use apotheosis::score::*;
+
+#[test]
+fn scores_order_from_mated_to_mating() {
+    let scores = [
+        -Score::INFINITY,
+        Score::mated_in(0),
+        Score::mated_in(5),
+        Score::from_centipawns(-i32::MAX),
+        Score::from_centipawns(-150),
+        Score::DRAW,
+        Score::from_centipawns(150),
+        Score::from_centipawns(i32::MAX),
+        Score::mate_in(5),
+        Score::mate_in(0),
+        Score::INFINITY
+    ];
+    assert!(scores.windows(2).all(|pair| pair[0] < pair[1]));
+    // huge evaluations never turn into mates
+    assert!(!Score::from_centipawns(i32::MAX).is_mate());
+    assert!(!Score::from_centipawns(i32::MIN).is_mate());
+    assert_eq!(-Score::from_centipawns(i32::MIN), Score::from_centipawns(i32::MAX));
+}
+
+#[test]
+fn mates_count_full_moves() {
+    assert_eq!(Score::mate_in(1).mate_moves(), Some(1));
+    assert_eq!(Score::mate_in(3).mate_moves(), Some(2));
+    assert_eq!(Score::mated_in(2).mate_moves(), Some(-1));
+    assert_eq!(Score::mated_in(6).mate_moves(), Some(-3));
+    assert_eq!(-Score::mate_in(5), Score::mated_in(5));
+    assert_eq!(Score::from_centipawns(500).mate_moves(), None);
+    assert!(Score::DRAW.is_draw());
+    assert!(!Score::from_centipawns(1).is_draw());
+}
+
+#[test]
+fn window_offsets_stay_within_infinity() {
+    assert_eq!(Score::INFINITY + 100, Score::INFINITY);
+    assert_eq!(-Score::INFINITY - 100, -Score::INFINITY);
+    assert_eq!(Score::from_centipawns(20) + 5, Score::from_centipawns(25));
+}
+
+#[test]
+fn tt_values_are_relative_to_the_node() {
+    // a mate in 7 plies from the root is a mate in 3 from a node 4 plies in
+    let root_score = Score::mate_in(7);
+    let tt_value = root_score.to_tt(4);
+    assert_eq!(Score::from_tt(tt_value, 0), Score::mate_in(3));
+    assert_eq!(Score::from_tt(tt_value, 4), root_score);
+    assert_eq!(Score::from_tt(Score::mated_in(9).to_tt(2), 6), Score::mated_in(13));
+    assert_eq!(Score::from_tt(Score::from_centipawns(-321).to_tt(10), 3), Score::from_centipawns(-321));
+}
+
+#[test]
+fn scores_format_for_humans_and_uci() {
+    assert_eq!(Score::from_centipawns(123).to_string(), "+1.23");
+    assert_eq!(Score::from_centipawns(-5).to_string(), "-0.05");
+    assert_eq!(Score::DRAW.to_string(), "0.00");
+    assert_eq!(Score::mate_in(9).to_string(), "#5");
+    assert_eq!(Score::mated_in(6).to_string(), "#-3");
+    assert_eq!(Score::from_centipawns(-42).to_uci(), "cp -42");
+    assert_eq!(Score::mate_in(3).to_uci(), "mate 2");
+    assert_eq!(Score::mated_in(4).to_uci(), "mate -2");
+}
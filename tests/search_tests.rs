@@ -0,0 +1,159 @@
+use apotheosis::{
+    board::*,
+    search::*
+};
+
+#[test]
+fn finds_mate_with_full_pv() {
+    // Ra6! bxa6 b7#
+    let board = Board::from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1");
+    let result = Searcher::new().search(&board, &[], &SearchLimits::new_depth_limited(4), |_| {});
+    assert_eq!(result.score.mate_moves(), Some(2));
+    assert_eq!(result.best_move, Some(BoardMove::new(&BoardSquare::from("a1"), &BoardSquare::from("a6"))));
+    assert_eq!(result.pv.len(), 3);
+
+    let mut pv_board = board;
+    for pv_move in result.pv.iter() {
+        assert!(pv_board.get_valid_moves().contains(pv_move), "{} is not valid in the pv", pv_move);
+        pv_board = pv_board.create_board_from_move(pv_move);
+    }
+    assert!(pv_board.get_valid_moves().is_empty() && pv_board.is_in_check());
+}
+
+#[test]
+fn reports_every_iteration() {
+    let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let mut reported_depths = Vec::new();
+    let result = Searcher::new().search(&board, &[], &SearchLimits::new_depth_limited(4), |iteration_result| {
+        assert_eq!(iteration_result.pv.first().copied(), iteration_result.best_move);
+        assert!(iteration_result.pv.len() >= iteration_result.depth as usize);
+        reported_depths.push(iteration_result.depth);
+    });
+    assert_eq!(reported_depths, vec![1, 2, 3, 4]);
+    assert!(!result.score.is_mate());
+}
+
+#[test]
+fn multi_pv_ranks_distinct_root_moves() {
+    let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 2 3");
+    let mut searcher = Searcher::new();
+    searcher.multi_pv = 3;
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(3), |_| {});
+
+    assert_eq!(result.lines.len(), 3);
+    // Qxf7# is the only mate, so the other lines have to be worse
+    assert_eq!(result.lines[0].score.mate_moves(), Some(1));
+    assert!(result.lines[1..].iter().all(|line| !line.score.is_mate()));
+    assert!(result.lines.windows(2).all(|lines| lines[0].score >= lines[1].score));
+    assert!(result.lines.iter().all(|line| line.pv[0] == line.board_move));
+    assert_ne!(result.lines[1].board_move, result.lines[2].board_move);
+    assert_eq!(result.best_move, Some(result.lines[0].board_move));
+
+    // asking for more lines than there are moves gives every move once
+    let board = Board::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1");
+    searcher.multi_pv = 10;
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(2), |_| {});
+    assert_eq!(result.lines.len(), 3);
+}
+
+#[test]
+fn single_thread_search_is_deterministic() {
+    let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let first_result = Searcher::new().search(&board, &[], &SearchLimits::new_depth_limited(4), |_| {});
+    let second_result = Searcher::new().search(&board, &[], &SearchLimits::new_depth_limited(4), |_| {});
+    assert_eq!(first_result.nodes, second_result.nodes);
+    assert_eq!(first_result.pv, second_result.pv);
+    assert_eq!(first_result.score, second_result.score);
+}
+
+#[test]
+fn threaded_search_finds_mate() {
+    let board = Board::from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1");
+    let mut searcher = Searcher::new();
+    searcher.threads = 4;
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(4), |_| {});
+    assert_eq!(result.score.mate_moves(), Some(2));
+    assert_eq!(result.best_move, Some(BoardMove::new(&BoardSquare::from("a1"), &BoardSquare::from("a6"))));
+}
+
+#[test]
+fn node_limited_search_is_reproducible() {
+    let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let limits = SearchLimits {
+        nodes: Some(3000),
+        ..Default::default()
+    };
+    let first_result = Searcher::new().search(&board, &[], &limits, |_| {});
+    let second_result = Searcher::new().search(&board, &[], &limits, |_| {});
+    assert_eq!(first_result, SearchResult { elapsed: first_result.elapsed, ..second_result });
+    assert!(first_result.depth > 0 && first_result.nodes <= 3000);
+}
+
+#[test]
+fn search_moves_restrict_the_root() {
+    let board = Board::from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1");
+    let rook_lift = BoardMove::new(&BoardSquare::from("a1"), &BoardSquare::from("a2"));
+    let limits = SearchLimits {
+        depth: Some(3),
+        search_moves: vec![rook_lift],
+        ..Default::default()
+    };
+    let result = Searcher::new().search(&board, &[], &limits, |_| {});
+    assert_eq!(result.best_move, Some(rook_lift));
+    assert!(!result.score.is_mate());
+}
+
+#[test]
+fn mate_search_stops_at_the_mate() {
+    let board = Board::from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1");
+    let limits = SearchLimits {
+        mate: Some(2),
+        ..Default::default()
+    };
+    let result = Searcher::new().search(&board, &[], &limits, |_| {});
+    assert_eq!(result.score.mate_moves(), Some(2));
+    assert_eq!(result.depth, 3);
+}
+
+#[test]
+fn infinite_search_stops_from_outside() {
+    let board = Board::from_fen(STARTING_FEN);
+    let mut searcher = Searcher::new();
+    let stop_handle = searcher.stop_handle();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        stop_handle.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+    let limits = SearchLimits {
+        infinite: true,
+        ..Default::default()
+    };
+    let result = searcher.search(&board, &[], &limits, |_| {});
+    stopper.join().unwrap();
+    assert!(result.best_move.is_some());
+    assert!(!searcher.stop_handle().load(std::sync::atomic::Ordering::Relaxed));
+}
+
+#[test]
+fn ponder_search_keeps_going_until_ponder_hit() {
+    let board = Board::from_fen(STARTING_FEN);
+    let ponder_hit = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let ponder_hit_sender = ponder_hit.clone();
+    let sender = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        ponder_hit_sender.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+    let limits = SearchLimits {
+        move_time: Some(std::time::Duration::from_millis(50)),
+        ponder_hit: Some(ponder_hit),
+        ..Default::default()
+    };
+    let search_start = std::time::Instant::now();
+    let result = Searcher::new().search(&board, &[], &limits, |_| {});
+    let search_time = search_start.elapsed();
+    sender.join().unwrap();
+    // the clock only starts on the ponder hit
+    assert!(search_time >= std::time::Duration::from_millis(300));
+    assert!(search_time < std::time::Duration::from_secs(5));
+    assert!(result.best_move.is_some());
+}
@@ -0,0 +1,307 @@
+use apotheosis::{
+    board::*,
+    score::Score,
+    search::*,
+    syzygy::*,
+    tablebase::*
+};
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf
+    },
+    sync::Arc
+};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+// the files' own piece numbers
+const WHITE_KING: u8 = 6;
+const WHITE_QUEEN: u8 = 5;
+const WHITE_ROOK: u8 = 4;
+const BLACK_KING: u8 = 14;
+// positions of three unique pieces a pawnless table has room for
+const TRIPLE_TABLE_SIZE: usize = 31332;
+const BLOCK_SIZE: usize = 32;
+const SPAN: usize = 64;
+
+enum TableSide {
+    SingleValue(u8),
+    // every value a fixed length 8 bit symbol, which is still a valid huffman code
+    Values(Vec<u8>)
+}
+
+fn align(bytes: &mut Vec<u8>, alignment: usize) {
+    while !bytes.len().is_multiple_of(alignment) {
+        bytes.push(0);
+    }
+}
+
+// a pawnless table of three pieces, the way the generator lays them out
+fn build_table(magic: [u8; 4], pieces: [u8; 3], flags: u8, sides: &[TableSide]) -> Vec<u8> {
+    let mut bytes = magic.to_vec();
+    bytes.push(1);
+    bytes.push(0);
+    bytes.extend(pieces.iter().map(|piece| piece | piece << 4));
+    align(&mut bytes, 2);
+    for side in sides.iter() {
+        match side {
+            TableSide::SingleValue(value) => bytes.extend_from_slice(&[flags | 128, *value]),
+            TableSide::Values(values) => {
+                let block_count = values.len().div_ceil(BLOCK_SIZE);
+                bytes.extend_from_slice(&[flags, BLOCK_SIZE.trailing_zeros() as u8, SPAN.trailing_zeros() as u8, 2]);
+                bytes.extend_from_slice(&(block_count as u32).to_le_bytes());
+                bytes.extend_from_slice(&[8, 8, 0, 0]);
+                bytes.extend_from_slice(&5u16.to_le_bytes());
+                for symbol in 0..5u8 {
+                    bytes.extend_from_slice(&[symbol, 0xf0, 0xff]);
+                }
+                bytes.push(0);
+            }
+        }
+    }
+    for side in sides.iter() {
+        if let TableSide::Values(values) = side {
+            for sparse_entry in 0..values.len().div_ceil(SPAN) {
+                let index = sparse_entry * SPAN + SPAN / 2;
+                bytes.extend_from_slice(&((index / BLOCK_SIZE) as u32).to_le_bytes());
+                bytes.extend_from_slice(&((index % BLOCK_SIZE) as u16).to_le_bytes());
+            }
+        }
+    }
+    for side in sides.iter() {
+        if let TableSide::Values(values) = side {
+            for block_values in values.chunks(BLOCK_SIZE) {
+                bytes.extend_from_slice(&(block_values.len() as u16 - 1).to_le_bytes());
+            }
+            for _ in 0..2 {
+                bytes.extend_from_slice(&(BLOCK_SIZE as u16 - 1).to_le_bytes());
+            }
+        }
+    }
+    for side in sides.iter() {
+        align(&mut bytes, 64);
+        if let TableSide::Values(values) = side {
+            bytes.extend_from_slice(values);
+            align(&mut bytes, BLOCK_SIZE);
+        }
+    }
+    bytes.extend_from_slice(&[0; 16]);
+    bytes
+}
+
+fn table_directory(name: &str, files: &[(&str, Vec<u8>)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("apotheosis_syzygy_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    for (file_name, bytes) in files.iter() {
+        fs::write(directory.join(file_name), bytes).unwrap();
+    }
+    directory
+}
+
+fn load_syzygy(directory: &Path) -> Syzygy {
+    let mut syzygy = Syzygy::new();
+    syzygy.load(directory.to_str().unwrap()).unwrap();
+    syzygy
+}
+
+// queen against king is always won with the queen's side to move, and lost with the other
+fn kqk_files(with_dtz: bool) -> Vec<(&'static str, Vec<u8>)> {
+    let pieces = [WHITE_KING, WHITE_QUEEN, BLACK_KING];
+    let mut files = vec![("KQvK.rtbw", build_table(WDL_MAGIC, pieces, 0, &[TableSide::SingleValue(4), TableSide::SingleValue(0)]))];
+    if with_dtz {
+        // 4 full moves to zeroing, stored for white to move
+        files.push(("KQvK.rtbz", build_table(DTZ_MAGIC, pieces, 0, &[TableSide::SingleValue(4)])));
+    }
+    files
+}
+
+#[test]
+fn finds_tables_by_file_name() {
+    let mut files = kqk_files(true);
+    files.push(("notes.txt", b"not a table".to_vec()));
+    files.push(("KXvK.rtbw", Vec::new()));
+    let directory = table_directory("names", &files);
+    let mut syzygy = Syzygy::new();
+    assert_eq!(syzygy.load(directory.to_str().unwrap()).unwrap(), 1);
+    assert_eq!(syzygy.max_pieces(), 3);
+    fs::remove_dir_all(&directory).unwrap();
+    assert!(Syzygy::new().load(directory.to_str().unwrap()).is_err());
+}
+
+#[test]
+fn probes_wdl_with_captures_and_swapped_colors() {
+    let directory = table_directory("wdl", &kqk_files(false));
+    let syzygy = load_syzygy(&directory);
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/2k5/8/8/8/4K2Q w - - 0 1")), Some(Wdl::Win));
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/2k5/8/8/8/4K2Q b - - 0 1")), Some(Wdl::Loss));
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("4k2q/8/8/8/2K5/8/8/8 b - - 0 1")), Some(Wdl::Win));
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("4k2q/8/8/8/2K5/8/8/8 w - - 0 1")), Some(Wdl::Loss));
+    // the bare king takes the hanging queen
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/8/8/8/3kQ3/7K b - - 0 1")), Some(Wdl::Draw));
+    // no table, castling rights, or too many pieces
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/2k5/8/8/8/4K2R w - - 0 1")), None);
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1")), None);
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("4k3/8/8/8/8/8/8/3QK2R w - - 0 1")), None);
+    assert_eq!(Wdl::Win.to_score(3), Score::tablebase_win(3));
+    assert!(Wdl::CursedWin.to_score(0) > Score::DRAW && !Wdl::CursedWin.to_score(0).is_decisive());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn probes_dtz_for_either_side_to_move() {
+    let directory = table_directory("dtz", &kqk_files(true));
+    let syzygy = load_syzygy(&directory);
+    // stored in full moves, so 4 becomes 9 plies
+    assert_eq!(syzygy.probe_dtz(&Board::from_fen("8/8/8/2k5/8/8/8/4K2Q w - - 0 1")), Some(9));
+    // only white to move is stored, black's comes from looking a move further
+    assert_eq!(syzygy.probe_dtz(&Board::from_fen("8/8/8/2k5/8/8/8/4K2Q b - - 0 1")), Some(-10));
+    assert_eq!(syzygy.probe_dtz(&Board::from_fen("8/8/8/8/8/8/3kQ3/7K b - - 0 1")), Some(0));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn ranks_root_moves_that_keep_the_win() {
+    // the files are only read once probed, so they stay around until the end
+    let directory = table_directory("root", &kqk_files(true));
+    let wdl_only_directory = table_directory("root_wdl", &kqk_files(false));
+    let syzygy = load_syzygy(&directory);
+    let wdl_only_syzygy = load_syzygy(&wdl_only_directory);
+    let board = Board::from_fen("8/8/8/8/6Q1/8/3k4/7K w - - 0 1");
+    for (syzygy, uses_dtz) in [(&syzygy, true), (&wdl_only_syzygy, false)] {
+        let ranking = syzygy.rank_root_moves(&board, &[], &[]).unwrap();
+        assert_eq!(ranking.uses_dtz, uses_dtz);
+        assert_eq!(ranking.moves.len(), board.get_valid_moves().len());
+        for ranked_move in ranking.moves.iter() {
+            // next to the black king, with nothing to protect the queen
+            let is_hanging = ["g4d1", "g4e2"].contains(&ranked_move.board_move.to_uci(&board).as_str());
+            assert_eq!(ranked_move.rank, if is_hanging { 0 } else { 1000 }, "{}", ranked_move.board_move.to_uci(&board));
+            assert_eq!(ranked_move.score, if is_hanging { Score::DRAW } else { Score::tablebase_win(0) });
+        }
+    }
+
+    let mut searcher = Searcher::new();
+    searcher.syzygy = Some(Arc::new(syzygy));
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(2), |_| {});
+    assert!(!["g4d1", "g4e2"].contains(&result.best_move.unwrap().to_uci(&board).as_str()));
+    assert!(result.score.is_decisive() && result.score > Score::DRAW);
+    fs::remove_dir_all(&directory).unwrap();
+    fs::remove_dir_all(&wdl_only_directory).unwrap();
+}
+
+// a table with a different value nearly everywhere still has to give mirrored positions the same one
+#[test]
+fn decodes_huffman_blocks_consistently() {
+    let white_values = (0..TRIPLE_TABLE_SIZE).map(|index| (index * 7 % 5) as u8).collect();
+    let black_values = (0..TRIPLE_TABLE_SIZE).map(|index| (index * 3 % 5) as u8).collect();
+    let table = build_table(WDL_MAGIC, [WHITE_KING, WHITE_ROOK, BLACK_KING], 0, &[TableSide::Values(white_values), TableSide::Values(black_values)]);
+    let directory = table_directory("huffman", &[("KRvK.rtbw", table)]);
+    let syzygy = load_syzygy(&directory);
+
+    let mut seen_results = Vec::new();
+    let mut state = 0x7379_7a79u64;
+    for _ in 0..40 {
+        let mut squares = Vec::new();
+        while squares.len() < 3 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let square_pos = (state >> 58) as u8;
+            if !squares.contains(&square_pos) {
+                squares.push(square_pos);
+            }
+        }
+        let active_color = if state & 1 == 0 { WHITE } else { BLACK };
+        let place = |transform: u8| {
+            let pieces = [WHITE | KING, WHITE | ROOK, BLACK | KING].iter().zip(squares.iter()).map(|(piece, square_pos)| {
+                let (mut x, mut y) = (square_pos % 8, square_pos / 8);
+                if transform & 1 != 0 {
+                    x = 7 - x;
+                }
+                if transform & 2 != 0 {
+                    y = 7 - y;
+                }
+                if transform & 4 != 0 {
+                    std::mem::swap(&mut x, &mut y);
+                }
+                (*piece, BoardSquare(y * 8 + x))
+            }).collect::<Vec<_>>();
+            Board::from_pieces(&pieces, active_color)
+        };
+        let result = syzygy.probe_wdl(&place(0));
+        assert!(result.is_some());
+        for transform in 1..8 {
+            assert_eq!(syzygy.probe_wdl(&place(transform)), result, "{:?} with transform {}", squares, transform);
+        }
+        // and with the colors swapped and the board upside down
+        let swapped_pieces = [BLACK | KING, BLACK | ROOK, WHITE | KING].iter().zip(squares.iter())
+            .map(|(piece, square_pos)| (*piece, BoardSquare(square_pos ^ 0b111000)))
+            .collect::<Vec<_>>();
+        assert_eq!(syzygy.probe_wdl(&Board::from_pieces(&swapped_pieces, active_color ^ 1)), result);
+        if !seen_results.contains(&result) {
+            seen_results.push(result);
+        }
+    }
+    assert!(seen_results.len() >= 3);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+// real tables from the syzygy set are not part of the repository; copy KQvK and KRvK with both
+// extensions into this directory and run cargo test -- --ignored to check the reader against them
+const REAL_TABLE_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/syzygy");
+
+// every legal position of the ending, or every stride-th of them, against this engine's own retrograde tables
+fn check_against_generated_tables(syzygy: &Syzygy, material_name: &str, strong_piece: BoardPieceType, stride: usize) {
+    let mut tablebases = Tablebases::new();
+    tablebases.generate(&Material::parse(material_name).unwrap()).unwrap();
+    let mut checked_positions = 0;
+    for position_index in (0..64 * 64 * 64 * 2).step_by(stride) {
+        let squares = [position_index / 2 % 64, position_index / 128 % 64, position_index / 8192];
+        if squares[0] == squares[1] || squares[1] == squares[2] || squares[0] == squares[2] {
+            continue;
+        }
+        let pieces = [WHITE | KING, WHITE | strong_piece, BLACK | KING].iter().zip(squares.iter())
+            .map(|(piece, square_pos)| (*piece, BoardSquare(*square_pos as u8)))
+            .collect::<Vec<_>>();
+        let active_color = (position_index % 2) as BoardColor;
+        // the side that just moved cannot be left in check
+        if Board::from_pieces(&pieces, active_color ^ 1).is_in_check() {
+            continue;
+        }
+        let board = Board::from_pieces(&pieces, active_color);
+        let fen = board.to_fen();
+        let expected_result = tablebases.probe(&board).unwrap();
+        let expected_wdl = match expected_result {
+            TablebaseResult::Win(_) => Wdl::Win,
+            TablebaseResult::Draw => Wdl::Draw,
+            TablebaseResult::Loss(_) => Wdl::Loss
+        };
+        assert_eq!(syzygy.probe_wdl(&board), Some(expected_wdl), "{}", fen);
+        // nothing the winning side plays captures or moves a pawn, so the distance to zeroing is the
+        // distance to mate, give or take the ply lost when a table counts full moves
+        let dtz = syzygy.probe_dtz(&board).unwrap();
+        match expected_result {
+            TablebaseResult::Win(plies) => assert!(dtz == plies as i32 || dtz == plies as i32 + 1, "{} has dtz {} and mates in {}", fen, dtz, plies),
+            TablebaseResult::Loss(plies) if plies > 0 => assert!(-dtz == plies as i32 || -dtz == plies as i32 + 1, "{} has dtz {} and is mated in {}", fen, dtz, plies),
+            TablebaseResult::Loss(_) => {},
+            TablebaseResult::Draw => assert_eq!(dtz, 0, "{}", fen)
+        }
+        checked_positions += 1;
+    }
+    assert!(checked_positions > 0);
+}
+
+#[test]
+#[ignore = "needs the real KQvK and KRvK tables in tests/data/syzygy, see the README there"]
+fn real_tables_match_known_results() {
+    for file_name in ["KQvK.rtbw", "KQvK.rtbz", "KRvK.rtbw", "KRvK.rtbz"] {
+        assert!(Path::new(REAL_TABLE_DIRECTORY).join(file_name).exists(), "{} is missing from {}", file_name, REAL_TABLE_DIRECTORY);
+    }
+    let syzygy = load_syzygy(Path::new(REAL_TABLE_DIRECTORY));
+    // positions whose results are well known
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/3k4/8/8/8/4K2R w - - 0 1")), Some(Wdl::Win));
+    assert_eq!(syzygy.probe_wdl(&Board::from_fen("8/8/8/8/8/8/1kR5/7K b - - 0 1")), Some(Wdl::Draw));
+    assert_eq!(syzygy.probe_dtz(&Board::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1")), Some(1));
+    assert_eq!(syzygy.probe_dtz(&Board::from_fen("7k/8/5K2/8/8/8/8/6Q1 w - - 0 1")), Some(1));
+    check_against_generated_tables(&syzygy, "KQK", QUEEN, 7);
+    check_against_generated_tables(&syzygy, "KRK", ROOK, 7);
+}
@@ -0,0 +1,98 @@
+use apotheosis::{
+    board::*,
+    search::*,
+    tablebase::*
+};
+use std::sync::{
+    Arc,
+    OnceLock
+};
+
+// generated once and shared, it takes a few seconds in a debug build
+fn krk_tablebases() -> &'static Tablebases {
+    static TABLEBASES: OnceLock<Tablebases> = OnceLock::new();
+    TABLEBASES.get_or_init(|| {
+        let mut tablebases = Tablebases::new();
+        tablebases.generate(&Material::parse("KRK").unwrap()).unwrap();
+        tablebases
+    })
+}
+
+#[test]
+fn parses_material_names() {
+    let material = Material::parse("krkp").unwrap();
+    assert_eq!(material.name(), "KRKP");
+    assert_eq!(material.piece_count(), 4);
+    assert_eq!(Material::parse("KNRK").unwrap().name(), "KRNK");
+    assert!(Material::parse("KRXK").is_none());
+    assert!(Material::parse("RK").is_none());
+    assert!(Tablebases::new().generate(&Material::parse("KRPKR").unwrap()).is_err());
+    assert!(Tablebases::new().generate(&Material::parse("KQRKRB").unwrap()).is_err());
+}
+
+#[test]
+fn probes_known_results() {
+    let tablebases = krk_tablebases();
+    assert_eq!(tablebases.table_names(), vec!["KRK".to_string()]);
+    // already mated, and mate in one
+    assert_eq!(tablebases.probe(&Board::from_fen("k6R/8/1K6/8/8/8/8/8 b - - 0 1")), Some(TablebaseResult::Loss(0)));
+    assert_eq!(tablebases.probe(&Board::from_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1")), Some(TablebaseResult::Win(1)));
+    // the longest rook mate takes 16 moves
+    assert_eq!(tablebases.probe(&Board::from_fen("8/8/8/3k4/8/8/8/4K2R w - - 0 1")), Some(TablebaseResult::Win(27)));
+    // the bare king takes the hanging rook
+    assert_eq!(tablebases.probe(&Board::from_fen("8/8/8/8/8/8/1kR5/7K b - - 0 1")), Some(TablebaseResult::Draw));
+    // no pieces left to mate with
+    assert_eq!(tablebases.probe(&Board::from_fen("8/8/3k4/8/8/8/8/4K3 w - - 0 1")), Some(TablebaseResult::Draw));
+    // castling rights and missing tables are left to the search
+    assert_eq!(tablebases.probe(&Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1")), None);
+    assert_eq!(tablebases.probe(&Board::from_fen("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1")), None);
+}
+
+#[test]
+fn probes_with_colors_swapped() {
+    let tablebases = krk_tablebases();
+    assert_eq!(tablebases.probe(&Board::from_fen("8/8/8/8/8/1k6/8/K6r w - - 0 1")), Some(TablebaseResult::Loss(0)));
+    assert_eq!(tablebases.probe(&Board::from_fen("4k2r/8/8/8/3K4/8/8/8 b - - 0 1")), Some(TablebaseResult::Win(27)));
+}
+
+#[test]
+fn best_move_keeps_the_fastest_win() {
+    let tablebases = krk_tablebases();
+    let mut board = Board::from_fen("8/8/8/3k4/8/8/8/4K2R w - - 0 1");
+    for expected_plies in (0..=27).rev() {
+        let expected_result = if expected_plies % 2 == 1 { TablebaseResult::Win(expected_plies) } else { TablebaseResult::Loss(expected_plies) };
+        assert_eq!(tablebases.probe(&board), Some(expected_result));
+        match tablebases.best_move(&board, &[]) {
+            Some((best_move, _)) => board = board.create_board_from_move(&best_move),
+            None => assert_eq!(expected_plies, 0)
+        }
+    }
+    assert!(board.is_in_check() && board.get_valid_moves().is_empty());
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let directory = std::env::temp_dir().join(format!("apotheosis_tablebases_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    krk_tablebases().save(&directory).unwrap();
+    let mut loaded_tablebases = Tablebases::new();
+    let table_count = loaded_tablebases.load(&directory).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(table_count, 1);
+    let board = Board::from_fen("8/8/8/3k4/8/8/8/4K2R w - - 0 1");
+    assert_eq!(loaded_tablebases.probe(&board), Some(TablebaseResult::Win(27)));
+}
+
+#[test]
+fn search_plays_from_the_tablebases() {
+    let mut searcher = Searcher::new();
+    searcher.tablebases = Some(Arc::new(krk_tablebases().clone()));
+    let board = Board::from_fen("8/8/8/3k4/8/8/8/4K2R w - - 0 1");
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(4), |_| {});
+    assert_eq!(result.score.mate_moves(), Some(14));
+    assert_eq!(result.pv.len(), 27);
+    // one move in, the tables get probed inside the tree instead
+    let board = Board::from_fen("8/8/8/3k4/8/8/8/R3K3 w Q - 0 1");
+    let result = searcher.search(&board, &[], &SearchLimits::new_depth_limited(3), |_| {});
+    assert!(result.score.is_mate() && result.score.value() > 0);
+}
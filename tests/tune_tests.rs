@@ -0,0 +1,48 @@
+use apotheosis::{
+    board::*,
+    eval_params::EVAL_PARAMS,
+    params::*,
+    tune::*
+};
+
+#[test]
+fn labelled_positions_parse_in_common_formats() {
+    let labelled_lines = [
+        ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1 [1.0]", 1.0),
+        ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c9 \"1/2-1/2\";", 0.5),
+        ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0-1", 0.0)
+    ];
+    for (line, result) in labelled_lines {
+        let position = parse_labelled_position(line).expect(line);
+        assert_eq!(position.result, result);
+        assert_eq!(position.board, Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+    }
+    assert!(parse_labelled_position("not a fen 1-0").is_none());
+    // broken fens are turned away instead of reaching the board
+    assert!(parse_labelled_position("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBN b KQkq - 1-0").is_none());
+    assert!(parse_labelled_position("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR x KQkq - 1-0").is_none());
+    assert!(parse_labelled_position("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e9 1-0").is_none());
+    assert!(parse_labelled_position("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").is_none());
+}
+
+#[test]
+fn written_params_match_the_params_source() {
+    assert_eq!(write_params_source(&EVAL_PARAMS), include_str!("../src/eval_params.rs"));
+}
+
+#[test]
+fn tuning_lowers_the_error() {
+    // white is winning all of these, by more than the evaluation thinks
+    let positions = [
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 1-0",
+        "4k3/8/8/8/8/8/3PP3/4K3 b - - 0 1 1-0",
+        "4k3/8/8/8/8/4N3/8/4K3 w - - 0 1 1/2-1/2",
+        "4k3/pp6/8/8/8/8/PPP5/4K3 w - - 0 1 1-0"
+    ].iter().map(|line| parse_labelled_position(line).unwrap()).collect::<Vec<LabelledPosition>>();
+    let mut params = EVAL_PARAMS;
+    let scaling = fit_scaling(&positions, &params);
+    let starting_error = mean_squared_error(&positions, &params, scaling);
+    let tuned_error = tune_params(&positions, &mut params, scaling, 1, |_, _, _| true);
+    assert!(tuned_error < starting_error);
+    assert_eq!(tuned_error, mean_squared_error(&positions, &params, scaling));
+}